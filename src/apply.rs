@@ -0,0 +1,384 @@
+use crate::{
+    avro::RefName,
+    exit::{
+        self,
+        Failure,
+    },
+    lock::{
+        self,
+        Lock,
+        Locked,
+    },
+    manifest::{
+        Entry,
+        Manifest,
+    },
+    registry::{
+        Client,
+        Compatibility,
+        CompatibilityCheck,
+        Contract,
+    },
+    workspace::Workspace,
+    RefStrategy,
+    SchemaSource,
+};
+
+use gumdrop::Options;
+use log::*;
+use schema_registry_converter::schema_registry_common::{
+    get_subject,
+    SuppliedSchema,
+};
+
+use std::{
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
+    fmt,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+/// Reconcile the Kafka Schema Registry with a manifest, or with the manifests of all packages in a workspace.
+/// Posts the schema of every subject listed in the manifest unless it is already
+/// registered, and sets the subject's compatibility level where one is specified.
+#[derive(Debug, Options)]
+pub struct ApplySettings {
+    /// print usage and exit
+    help: bool,
+
+    /// manifest file
+    #[options(meta = "FILE", default = "ksrt.yaml")]
+    manifest: PathBuf,
+
+    /// workspace file (e.g., `ksrt.workspace.toml'; instead of `--manifest')
+    #[options(meta = "FILE")]
+    workspace: Option<PathBuf>,
+
+    /// print the plan without changing anything
+    #[options(no_short)]
+    dry_run: bool,
+
+    /// check that every new version is compatible, without changing anything (exits 3 if not)
+    #[options(no_short)]
+    check: bool,
+
+    /// lock file recording what was applied (default: `ksrt.lock' next to the manifest or workspace)
+    #[options(no_short, meta = "FILE")]
+    lock_file: Option<PathBuf>,
+
+    /// check every subject with the registry, including those unchanged since the last apply
+    #[options(no_short)]
+    full: bool,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    pub schema_registry_url: Vec<String>,
+}
+
+#[derive(Debug)]
+enum Change {
+    Create,
+    Update,
+    Unchanged {
+        version: u32,
+        id: u32,
+    },
+    /// unchanged since the last apply, as per the lock file (the registry wasn't asked)
+    Locked(u32),
+}
+
+#[derive(Debug)]
+struct Step {
+    subject: String,
+    schema: SuppliedSchema,
+    hash: String,
+    change: Change,
+    compatibility: Option<(Option<Compatibility>, Compatibility)>,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.change {
+            Change::Create => write!(f, "create {}", self.subject)?,
+            Change::Update => write!(f, "update {}", self.subject)?,
+            Change::Unchanged { version, .. } => {
+                write!(f, "unchanged {} (version {})", self.subject, version)?
+            }
+            Change::Locked(_) => write!(f, "unchanged {} (as last applied)", self.subject)?,
+        }
+
+        if let Some((current, desired)) = self.compatibility {
+            match current {
+                Some(current) => write!(f, "; compatibility {} -> {}", current, desired)?,
+                None => write!(f, "; compatibility (default) -> {}", desired)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&Entry> for SchemaSource {
+    fn from(entry: &Entry) -> Self {
+        Self {
+            schema_type: entry.schema_type,
+            file: entry.file.clone(),
+            name: None,
+            include: entry.include.clone(),
+            strip_comments: entry.strip_comments,
+            strip_options: entry.strip_options,
+            keep_options: entry.keep_options.clone(),
+            inline_refs: entry.inline_refs,
+            reference_subjects: entry.references.clone(),
+            ref_strategy: RefStrategy::default(),
+            ref_topic: None,
+            ref_name: RefName::default(),
+            include_map: Vec::new(),
+            transform: Vec::new(),
+        }
+    }
+}
+
+fn entry_subject(entry: &Entry) -> anyhow::Result<String> {
+    if let Some(subject) = &entry.subject {
+        anyhow::ensure!(
+            entry.topic.is_none() && entry.record.is_none(),
+            "subject {}: `subject' cannot be combined with `topic' or `record'",
+            subject
+        );
+
+        return Ok(format!("{}{}", entry.subject_prefix, subject));
+    }
+
+    anyhow::ensure!(
+        entry.topic.is_some() || entry.record.is_some(),
+        "{}: one of `subject', `topic', or `record' is required",
+        entry.file.display()
+    );
+
+    let sns = crate::subject_name_strategy_from_settings(
+        entry.topic.clone(),
+        entry.record.clone(),
+        entry.topic_key,
+    )?;
+
+    let subject =
+        get_subject(&sns).map_err(|e| anyhow::format_err!("error determining subject: {:?}", e))?;
+
+    Ok(format!("{}{}", entry.subject_prefix, subject))
+}
+
+fn plan(
+    client: &Client,
+    manifest: &Manifest,
+    locked: Option<&BTreeMap<String, Locked>>,
+) -> anyhow::Result<Vec<Step>> {
+    // Packages of a workspace may well share schema files; build each only once.
+    let mut schemas: HashMap<SchemaSource, SuppliedSchema> = HashMap::new();
+    manifest
+        .subjects
+        .iter()
+        .map(|entry| {
+            let subject = entry_subject(entry)?;
+            let source = SchemaSource::from(entry);
+            let schema = match schemas.get(&source) {
+                Some(schema) => schema.clone(),
+                None => {
+                    let schema = crate::supplied_schema(&source)?;
+                    schemas.insert(source, schema.clone());
+                    schema
+                }
+            };
+
+            let hash = lock::hash(&schema, entry.compatibility);
+            if let Some(locked) = locked
+                .and_then(|locked| locked.get(&subject))
+                .filter(|locked| locked.hash == hash)
+            {
+                return Ok(Step {
+                    subject,
+                    schema,
+                    hash,
+                    change: Change::Locked(locked.id),
+                    compatibility: None,
+                });
+            }
+
+            let change = match client.lookup(&subject, &schema)? {
+                Some(registered) => Change::Unchanged {
+                    version: registered.version.unwrap_or_default(),
+                    id: registered.id,
+                },
+                None if client.get_versions(&subject)?.is_empty() => Change::Create,
+                None => Change::Update,
+            };
+
+            let compatibility = match entry.compatibility {
+                Some(desired) => {
+                    let current = client.get_compatibility(&subject)?;
+                    if current == Some(desired) {
+                        None
+                    } else {
+                        Some((current, desired))
+                    }
+                }
+                None => None,
+            };
+
+            Ok(Step {
+                subject,
+                schema,
+                hash,
+                change,
+                compatibility,
+            })
+        })
+        .collect()
+}
+
+/// Checks the compatibility of each new version, returning the number of incompatible ones.
+fn check(client: &Client, steps: &[Step]) -> anyhow::Result<usize> {
+    let mut incompatible = 0;
+    for step in steps {
+        if !matches!(step.change, Change::Update) {
+            continue;
+        }
+
+        match client.check_compatibility(&step.subject, &step.schema)? {
+            CompatibilityCheck::Compatible => {}
+            CompatibilityCheck::Incompatible(messages) => {
+                outln!("incompatible: {}", step.subject);
+                for message in messages {
+                    outln!("\t{}", message);
+                }
+
+                incompatible += 1;
+            }
+            CompatibilityCheck::MissingReferences => {
+                warn!(
+                    "cannot check {} before its new references are registered",
+                    step.subject
+                );
+            }
+        }
+    }
+
+    Ok(incompatible)
+}
+
+/// Where the lock file goes unless given: next to the manifest or workspace file.
+fn default_lock_file(settings: &ApplySettings) -> PathBuf {
+    let file = settings.workspace.as_ref().unwrap_or(&settings.manifest);
+    file.parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join("ksrt.lock")
+}
+
+pub fn run_apply(client: Client, settings: &ApplySettings) -> anyhow::Result<()> {
+    let manifest = match &settings.workspace {
+        Some(workspace) => Workspace::load(workspace)?,
+        None => Manifest::load(&settings.manifest)?,
+    };
+
+    debug!("manifest: {:#?}", manifest);
+
+    let lock_file = settings
+        .lock_file
+        .clone()
+        .unwrap_or_else(|| default_lock_file(settings));
+
+    let mut lock = Lock::load(&lock_file)?;
+    let registry = client.registry_key();
+    let locked = if settings.full {
+        None
+    } else {
+        lock.registry(&registry)
+    };
+
+    let steps = plan(&client, &manifest, locked)?;
+    for step in &steps {
+        outln!("{}", step);
+    }
+
+    let pending = steps
+        .iter()
+        .filter(|step| {
+            matches!(step.change, Change::Create | Change::Update) || step.compatibility.is_some()
+        })
+        .count();
+
+    let skipped = steps
+        .iter()
+        .filter(|step| matches!(step.change, Change::Locked(_)))
+        .count();
+
+    if skipped > 0 {
+        outln!(
+            "{} of {} subject(s) to change ({} unchanged since the last apply)",
+            pending,
+            steps.len(),
+            skipped
+        );
+    } else {
+        outln!("{} of {} subject(s) to change", pending, steps.len());
+    }
+
+    if settings.check {
+        let incompatible = check(&client, &steps)?;
+        if incompatible > 0 {
+            let msg = format!("{} incompatible change(s)", incompatible);
+            return Err(Failure::new(exit::INCOMPATIBLE, msg).into());
+        }
+
+        return Ok(());
+    }
+
+    if settings.dry_run {
+        return Ok(());
+    }
+
+    // Subjects no longer in the manifest are dropped from the lock file.
+    let mut applied = lock.registry(&registry).cloned().unwrap_or_default();
+    applied.retain(|subject, _| steps.iter().any(|step| &step.subject == subject));
+
+    let result = steps.iter().try_for_each(|step| {
+        // Set the compatibility level first so that it applies to the new version.
+        if let Some((_, desired)) = step.compatibility {
+            client.set_compatibility(&step.subject, desired)?;
+            outln!("set compatibility of {} to {}", step.subject, desired);
+        }
+
+        let id = match step.change {
+            Change::Unchanged { id, .. } | Change::Locked(id) => id,
+            Change::Create | Change::Update => {
+                let (id, _) = client
+                    .register(&step.subject, &step.schema, &Contract::default())
+                    .map_err(|e| e.context(format!("error posting schema to {}", step.subject)))?;
+
+                outln!("registered {} (id {})", step.subject, id);
+                id
+            }
+        };
+
+        applied.insert(
+            step.subject.clone(),
+            Locked {
+                hash: step.hash.clone(),
+                id,
+            },
+        );
+
+        Ok(())
+    });
+
+    // Record what was applied even if something failed, so that the next run picks up where this one left off.
+    lock.set_registry(registry, applied);
+    lock.save(&lock_file)?;
+
+    result
+}
@@ -0,0 +1,792 @@
+use crate::normalize;
+
+use log::*;
+use schema_registry_converter::schema_registry_common::{
+    SchemaType,
+    SuppliedReference,
+};
+
+use serde_json::{
+    Map,
+    Value,
+};
+
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+        HashMap,
+    },
+    fmt,
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+    str::FromStr,
+};
+
+/// Names of the Avro primitive types, which never refer to named types.
+pub const PRIMITIVES: &[&str] = &[
+    "null", "boolean", "int", "long", "float", "double", "bytes", "string",
+];
+
+/// Resolves the full name of an Avro type name used within the given namespace.
+pub fn full_name(name: &str, namespace: &str) -> String {
+    if name.contains('.') || namespace.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", namespace, name)
+    }
+}
+
+/// Named type defined in an Avro schema, or the name of one used in it, as encountered by `walk'.
+enum Named<'a> {
+    Defined(String, &'a Map<String, Value>),
+    Used(String),
+}
+
+/// Walks an Avro schema used within the given namespace, and the schemas nested in it, passing each named type
+/// defined in it and each name of one used in it to the given function, with their full names.
+fn walk<'a, F>(schema: &'a Value, namespace: &str, visit: &mut F)
+where
+    F: FnMut(Named<'a>),
+{
+    match schema {
+        Value::String(name) if !PRIMITIVES.contains(&name.as_str()) => {
+            visit(Named::Used(full_name(name, namespace)));
+        }
+        Value::Array(schemas) => {
+            for schema in schemas {
+                walk(schema, namespace, visit);
+            }
+        }
+        Value::Object(object) => match object.get("type") {
+            Some(Value::String(kind))
+                if ["record", "error", "enum", "fixed"].contains(&kind.as_str()) =>
+            {
+                let name = defined_name(object, namespace);
+                let fields = object.get("fields").and_then(Value::as_array);
+                for field in fields.into_iter().flatten() {
+                    if let Some(schema) = field.get("type") {
+                        walk(schema, namespace_of(&name), visit);
+                    }
+                }
+
+                visit(Named::Defined(name, object));
+            }
+            Some(Value::String(kind)) if kind == "array" => {
+                if let Some(items) = object.get("items") {
+                    walk(items, namespace, visit);
+                }
+            }
+            Some(Value::String(kind)) if kind == "map" => {
+                if let Some(values) = object.get("values") {
+                    walk(values, namespace, visit);
+                }
+            }
+            Some(schema) => walk(schema, namespace, visit),
+            None => {}
+        },
+        _ => {}
+    }
+}
+
+/// Collects the named types defined in an Avro schema (and the schemas nested in it) by their full names.
+pub fn definitions<'a>(
+    schema: &'a Value,
+    namespace: &str,
+    defs: &mut HashMap<String, &'a Map<String, Value>>,
+) {
+    walk(schema, namespace, &mut |named| {
+        if let Named::Defined(name, object) = named {
+            defs.insert(name, object);
+        }
+    });
+}
+
+/// Full name of a named type defined within the given namespace (unless it has one of its own).
+pub fn defined_name(object: &Map<String, Value>, namespace: &str) -> String {
+    let name = object
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let namespace = object
+        .get("namespace")
+        .and_then(Value::as_str)
+        .unwrap_or(namespace);
+
+    full_name(name, namespace)
+}
+
+/// Namespace that applies within a named type (i.e., to its fields' types);
+/// the namespace of nested types defaults to that of the enclosing one.
+pub fn namespace_of(name: &str) -> &str {
+    name.rsplit_once('.').map_or("", |(namespace, _)| namespace)
+}
+
+/// Empty fingerprint of the CRC-64-AVRO (Rabin) fingerprint algorithm, which is also its polynomial.
+const EMPTY_FINGERPRINT: u64 = 0xc15d_213a_a4d7_a795;
+
+/// Computes the 64-bit Rabin fingerprint (CRC-64-AVRO) of the given data, as defined by the Avro specification.
+pub fn fingerprint64(data: &[u8]) -> u64 {
+    let mut table = [0u64; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut fp = i as u64;
+        for _ in 0..8 {
+            fp = (fp >> 1) ^ (EMPTY_FINGERPRINT & (fp & 1).wrapping_neg());
+        }
+
+        *entry = fp;
+    }
+
+    data.iter().fold(EMPTY_FINGERPRINT, |fp, b| {
+        (fp >> 8) ^ table[((fp ^ u64::from(*b)) & 0xff) as usize]
+    })
+}
+
+/// Writes a JSON string literal.
+fn write_string(s: &str, out: &mut String) {
+    out.push_str(&Value::from(s).to_string());
+}
+
+/// Writes the Parsing Canonical Form of a schema used within the given namespace.
+fn write_canonical(schema: &Value, namespace: &str, out: &mut String) -> anyhow::Result<()> {
+    match schema {
+        Value::String(name) if PRIMITIVES.contains(&name.as_str()) => write_string(name, out),
+        Value::String(name) => write_string(&full_name(name, namespace), out),
+        Value::Array(schemas) => {
+            out.push('[');
+            for (i, schema) in schemas.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+
+                write_canonical(schema, namespace, out)?;
+            }
+
+            out.push(']');
+        }
+        Value::Object(schema) => match schema.get("type") {
+            Some(Value::String(kind))
+                if ["record", "error", "enum", "fixed"].contains(&kind.as_str()) =>
+            {
+                let name = schema
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| anyhow::format_err!("named type without a name"))?;
+                let namespace = schema
+                    .get("namespace")
+                    .and_then(Value::as_str)
+                    .unwrap_or(namespace);
+
+                let name = full_name(name, namespace);
+                out.push_str("{\"name\":");
+                write_string(&name, out);
+                out.push_str(",\"type\":");
+                write_string(kind, out);
+
+                let namespace = namespace_of(&name);
+                match kind.as_str() {
+                    "enum" => {
+                        out.push_str(",\"symbols\":");
+                        let symbols = schema.get("symbols").cloned().unwrap_or_default();
+                        out.push_str(&symbols.to_string());
+                    }
+                    "fixed" => {
+                        let size = schema.get("size").and_then(Value::as_u64).ok_or_else(|| {
+                            anyhow::format_err!("fixed type without a size: {}", name)
+                        })?;
+
+                        out.push_str(&format!(",\"size\":{}", size));
+                    }
+                    _ => {
+                        out.push_str(",\"fields\":[");
+                        let fields = schema.get("fields").and_then(Value::as_array);
+                        for (i, field) in fields.into_iter().flatten().enumerate() {
+                            if i > 0 {
+                                out.push(',');
+                            }
+
+                            let field_name = field
+                                .get("name")
+                                .and_then(Value::as_str)
+                                .unwrap_or_default();
+                            let field_type = field.get("type").ok_or_else(|| {
+                                anyhow::format_err!("field without a type: {}.{}", name, field_name)
+                            })?;
+
+                            out.push_str("{\"name\":");
+                            write_string(field_name, out);
+                            out.push_str(",\"type\":");
+                            write_canonical(field_type, namespace, out)?;
+                            out.push('}');
+                        }
+
+                        out.push(']');
+                    }
+                }
+
+                out.push('}');
+            }
+            Some(Value::String(kind)) if kind == "array" => {
+                out.push_str("{\"type\":\"array\",\"items\":");
+                let items = schema
+                    .get("items")
+                    .ok_or_else(|| anyhow::format_err!("array type without items"))?;
+                write_canonical(items, namespace, out)?;
+                out.push('}');
+            }
+            Some(Value::String(kind)) if kind == "map" => {
+                out.push_str("{\"type\":\"map\",\"values\":");
+                let values = schema
+                    .get("values")
+                    .ok_or_else(|| anyhow::format_err!("map type without values"))?;
+                write_canonical(values, namespace, out)?;
+                out.push('}');
+            }
+            Some(schema) => write_canonical(schema, namespace, out)?,
+            None => anyhow::bail!("schema without a type"),
+        },
+        _ => anyhow::bail!("invalid schema: {}", schema),
+    }
+
+    Ok(())
+}
+
+/// Transforms an Avro schema into its Parsing Canonical Form, which has the same text for all schemas that
+/// read data the same way: names are fully qualified, and attributes that don't affect parsing (e.g., `doc',
+/// `aliases', and `default') are removed. Named types defined elsewhere (i.e., references) are left as names.
+pub fn canonical_form(schema: &Value) -> anyhow::Result<String> {
+    let mut out = String::new();
+    write_canonical(schema, "", &mut out)?;
+    Ok(out)
+}
+
+/// Named types (records, enums, and fixed types) defined in an Avro schema, and those it uses.
+#[derive(Debug, Default)]
+struct NamedTypes {
+    defined: BTreeSet<String>,
+    used: BTreeSet<String>,
+}
+
+impl NamedTypes {
+    fn of(schema: &Value) -> Self {
+        let mut types = Self::default();
+        walk(schema, "", &mut |named| match named {
+            Named::Defined(name, _) => {
+                types.defined.insert(name);
+            }
+            Named::Used(name) => {
+                types.used.insert(name);
+            }
+        });
+
+        types
+    }
+
+    /// Named types used but not defined in the schema, i.e., those defined in other schemas.
+    fn external(&self) -> impl Iterator<Item = &String> {
+        self.used.difference(&self.defined)
+    }
+}
+
+fn parse(path: &Path) -> anyhow::Result<Value> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| anyhow::format_err!("failed to read {}: {}", path.display(), e))?;
+
+    serde_json::from_str(&text)
+        .map_err(|e| anyhow::format_err!("invalid Avro schema {}: {}", path.display(), e))
+}
+
+/// Full name of the named type an Avro schema file defines at its top level.
+pub fn type_name(path: &Path) -> anyhow::Result<String> {
+    match parse(path)? {
+        Value::Object(object) if object.contains_key("name") => Ok(defined_name(&object, "")),
+        _ => anyhow::bail!("{} doesn't define a named type", path.display()),
+    }
+}
+
+/// Placeholders of a reference name template.
+const PLACEHOLDERS: &[&str] = &["{fullname}", "{namespace}", "{name}", "{path}"];
+
+/// How references to named types defined in other files are named (which serializers configured
+/// to resolve references by name have to agree with).
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum RefName {
+    /// The type's full name (e.g., `com.acme.Money').
+    #[default]
+    FullName,
+    /// The path of the file defining the type, relative to the directory it was found in (e.g., `com/acme/money.avsc').
+    Path,
+    /// Template in which `{fullname}', `{namespace}', `{name}', and `{path}' are replaced (e.g., `schemas/{name}.avsc').
+    Template(String),
+}
+
+impl RefName {
+    /// Names the reference to a type, given its full name and the path of the file defining it.
+    fn of(&self, full_name: &str, path: &str) -> String {
+        match self {
+            Self::FullName => full_name.to_owned(),
+            Self::Path => path.to_owned(),
+            Self::Template(template) => {
+                let name = full_name.rsplit('.').next().unwrap_or(full_name);
+                template
+                    .replace("{fullname}", full_name)
+                    .replace("{namespace}", namespace_of(full_name))
+                    .replace("{name}", name)
+                    .replace("{path}", path)
+            }
+        }
+    }
+}
+
+impl fmt::Display for RefName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FullName => write!(f, "full-name"),
+            Self::Path => write!(f, "path"),
+            Self::Template(template) => write!(f, "{}", template),
+        }
+    }
+}
+
+impl FromStr for RefName {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let naming = match s {
+            "full-name" => Self::FullName,
+            "path" => Self::Path,
+            _ => {
+                let rest = PLACEHOLDERS.iter().fold(s.to_owned(), |rest, placeholder| {
+                    rest.replace(placeholder, "")
+                });
+
+                anyhow::ensure!(
+                    rest.len() < s.len() && !rest.contains(['{', '}']),
+                    "expected `full-name', `path', or a template using {}: {}",
+                    PLACEHOLDERS.join(", "),
+                    s
+                );
+
+                Self::Template(s.to_owned())
+            }
+        };
+
+        Ok(naming)
+    }
+}
+
+/// Schema file that defines a named type.
+struct Location {
+    file: PathBuf,
+    /// path of the file relative to the directory it was found in, with `/' separators
+    path: String,
+}
+
+/// Indexes the `.avsc' files in the given directories (and their subdirectories) by the named types they define.
+/// Where several files define the same type, the first directory given wins.
+fn index_types(dirs: &[PathBuf]) -> anyhow::Result<HashMap<String, Location>> {
+    let mut index = HashMap::new();
+    for dir in dirs {
+        let mut files = Vec::new();
+        crate::find_files(dir, "avsc", &mut files)?;
+        for file in files {
+            let schema = match parse(&file) {
+                Ok(schema) => schema,
+                Err(e) => {
+                    debug!("skipping {}", e);
+                    continue;
+                }
+            };
+
+            let path = file
+                .strip_prefix(dir)
+                .unwrap_or(&file)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            for name in NamedTypes::of(&schema).defined {
+                index.entry(name).or_insert_with(|| Location {
+                    file: file.clone(),
+                    path: path.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(index)
+}
+
+struct Resolver<'a> {
+    index: HashMap<String, Location>,
+    subjects: &'a BTreeMap<String, String>,
+    naming: &'a RefName,
+    options: &'a normalize::Options,
+}
+
+impl Resolver<'_> {
+    /// Resolves the named types the schema uses from other files into references, recursively.
+    /// Each reference is named as configured and registered (by default) under the type's full name as its subject.
+    /// Types named alike (e.g., those defined in the same file, named by its path) make for a single reference.
+    fn references(
+        &self,
+        schema: &Value,
+        path: &mut Vec<String>,
+    ) -> anyhow::Result<Vec<SuppliedReference>> {
+        let mut references: Vec<SuppliedReference> = Vec::new();
+        for name in NamedTypes::of(schema).external() {
+            anyhow::ensure!(
+                !path.contains(name),
+                "circular reference: {} -> {}",
+                path.join(" -> "),
+                name
+            );
+
+            let location = self.index.get(name).ok_or_else(|| {
+                anyhow::format_err!("failed to locate a schema file defining: {}", name)
+            })?;
+
+            let text = fs::read_to_string(&location.file)?;
+            let schema = parse(&location.file)?;
+
+            path.push(name.clone());
+            let nested = self.references(&schema, path)?;
+            path.pop();
+
+            let reference = SuppliedReference {
+                name: self.naming.of(name, &location.path),
+                subject: self.subjects.get(name).unwrap_or(name).clone(),
+                schema: normalize::normalize(&text, &SchemaType::Avro, self.options),
+                references: nested,
+            };
+
+            match references.iter().find(|r| r.name == reference.name) {
+                Some(existing) => anyhow::ensure!(
+                    existing.schema == reference.schema,
+                    "{} names references to different schemas (one defining {})",
+                    reference.name,
+                    name
+                ),
+                None => references.push(reference),
+            }
+        }
+
+        Ok(references)
+    }
+}
+
+/// Resolves the named types an Avro schema file uses but doesn't define from the `.avsc' files
+/// in its directory and the include directories, and returns them as references.
+pub fn get_references(
+    file: &Path,
+    includes: &[PathBuf],
+    subjects: &BTreeMap<String, String>,
+    naming: &RefName,
+    options: &normalize::Options,
+) -> anyhow::Result<Vec<SuppliedReference>> {
+    let schema = parse(file)?;
+    if NamedTypes::of(&schema).external().next().is_none() {
+        return Ok(Vec::new());
+    }
+
+    let file = file.canonicalize()?;
+    let dirs: Vec<_> = file
+        .parent()
+        .map(Path::to_path_buf)
+        .into_iter()
+        .chain(includes.iter().cloned())
+        .collect();
+
+    let resolver = Resolver {
+        index: index_types(&dirs)?,
+        subjects,
+        naming,
+        options,
+    };
+
+    resolver.references(&schema, &mut Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write(dir: &Path, name: &str, schema: Value) -> PathBuf {
+        let file = dir.join(name);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, schema.to_string()).unwrap();
+        file
+    }
+
+    fn names(references: &[SuppliedReference]) -> Vec<&str> {
+        references.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    fn describe(references: &[SuppliedReference]) -> String {
+        references
+            .iter()
+            .map(|r| format!("{}={}[{}]", r.name, r.subject, describe(&r.references)))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn record(name: &str, fields: Value) -> Value {
+        json!({"type": "record", "name": name, "fields": fields})
+    }
+
+    #[test]
+    fn parses_reference_naming() {
+        assert_eq!("full-name".parse::<RefName>().unwrap(), RefName::FullName);
+        assert_eq!("path".parse::<RefName>().unwrap(), RefName::Path);
+        for template in ["{namespace}/{name}.avsc", "schemas/{path}", "{fullname}"] {
+            let naming = template.parse::<RefName>().unwrap();
+            assert_eq!(naming, RefName::Template(template.to_owned()));
+            assert_eq!(naming.to_string(), template);
+        }
+
+        for invalid in ["fullname", "schemas/money.avsc", "{type}.avsc", "{name}}"] {
+            assert!(invalid.parse::<RefName>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn names_references_as_configured() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "common/money.avsc",
+            json!({
+                "type": "record",
+                "name": "Money",
+                "namespace": "com.acme",
+                "fields": [
+                    {"name": "cents", "type": "long"},
+                    {"name": "currency", "type": {"type": "enum", "name": "Currency", "symbols": ["EUR"]}}
+                ]
+            }),
+        );
+
+        write(
+            dir.path(),
+            "tax.avsc",
+            json!({"type": "record", "name": "com.acme.Tax", "fields": [{"name": "rate", "type": "double"}]}),
+        );
+
+        let order = write(
+            dir.path(),
+            "order.avsc",
+            json!({
+                "type": "record",
+                "name": "Order",
+                "namespace": "com.acme",
+                "fields": [
+                    {"name": "total", "type": "Money"},
+                    {"name": "currency", "type": "Currency"},
+                    {"name": "tax", "type": "Tax"}
+                ]
+            }),
+        );
+
+        let references = |naming: &str| {
+            get_references(
+                &order,
+                &[],
+                &BTreeMap::new(),
+                &naming.parse().unwrap(),
+                &normalize::Options::default(),
+            )
+        };
+
+        let by_name = references("full-name").unwrap();
+        assert_eq!(
+            names(&by_name),
+            ["com.acme.Currency", "com.acme.Money", "com.acme.Tax"]
+        );
+        assert!(by_name
+            .iter()
+            .all(|r| r.subject == r.name && r.references.is_empty()));
+
+        // Both types are defined in the same file, so they make for a single reference.
+        let by_path = references("path").unwrap();
+        assert_eq!(names(&by_path), ["common/money.avsc", "tax.avsc"]);
+        assert_eq!(by_path[0].subject, "com.acme.Currency");
+        assert_eq!(by_path[0].schema, by_name[1].schema);
+
+        let templated = references("schemas/{namespace}/{name}.avsc").unwrap();
+        assert_eq!(
+            names(&templated),
+            [
+                "schemas/com.acme/Currency.avsc",
+                "schemas/com.acme/Money.avsc",
+                "schemas/com.acme/Tax.avsc"
+            ]
+        );
+
+        // Names that don't tell the types' schemas apart are rejected.
+        let err = references("{namespace}").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("com.acme names references to different schemas"));
+    }
+
+    #[test]
+    fn resolves_names_in_inherited_namespaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let include = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "money.avsc",
+            record("com.acme.Money", json!([])),
+        );
+        write(
+            include.path(),
+            "status.avsc",
+            record("other.Status", json!([])),
+        );
+        write(
+            include.path(),
+            "tax.avsc",
+            record("com.acme.Tax", json!([])),
+        );
+
+        // The schema's own directory wins over the include directory.
+        write(
+            include.path(),
+            "money.avsc",
+            record("com.acme.Money", json!([{"name": "x", "type": "int"}])),
+        );
+
+        // `Line' inherits the namespace of `Order', while `Shipment' declares its own.
+        let order = write(
+            dir.path(),
+            "order.avsc",
+            json!({
+                "type": "record",
+                "name": "Order",
+                "namespace": "com.acme",
+                "fields": [
+                    {"name": "lines", "type": {"type": "array", "items": record("Line", json!([
+                        {"name": "price", "type": "Money"},
+                        {"name": "tax", "type": "com.acme.Tax"}
+                    ]))}},
+                    {"name": "shipment", "type": {
+                        "type": "record",
+                        "name": "Shipment",
+                        "namespace": "other",
+                        "fields": [
+                            {"name": "status", "type": "Status"},
+                            {"name": "line", "type": "com.acme.Line"}
+                        ]
+                    }}
+                ]
+            }),
+        );
+
+        let references = get_references(
+            &order,
+            &[include.path().to_path_buf()],
+            &BTreeMap::new(),
+            &RefName::default(),
+            &normalize::Options::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            describe(&references),
+            "com.acme.Money=com.acme.Money[],com.acme.Tax=com.acme.Tax[],other.Status=other.Status[]"
+        );
+        assert_eq!(
+            references[0].schema,
+            fs::read_to_string(dir.path().join("money.avsc")).unwrap()
+        );
+    }
+
+    #[test]
+    fn resolves_references_transitively() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "currency.avsc",
+            json!({"type": "enum", "name": "com.acme.Currency", "symbols": ["EUR"]}),
+        );
+        write(
+            dir.path(),
+            "money.avsc",
+            record(
+                "com.acme.Money",
+                json!([{"name": "currency", "type": "Currency"}]),
+            ),
+        );
+        write(
+            dir.path(),
+            "tax.avsc",
+            record(
+                "com.acme.Tax",
+                json!([{"name": "currency", "type": "com.acme.Currency"}]),
+            ),
+        );
+        let order = write(
+            dir.path(),
+            "order.avsc",
+            record(
+                "com.acme.Order",
+                json!([
+                    {"name": "total", "type": ["null", "Money"]},
+                    {"name": "taxes", "type": {"type": "map", "values": "Tax"}}
+                ]),
+            ),
+        );
+
+        let mut subjects = BTreeMap::new();
+        subjects.insert("com.acme.Currency".to_owned(), "currencies".to_owned());
+        let references = |file: &Path| {
+            get_references(
+                file,
+                &[],
+                &subjects,
+                &RefName::default(),
+                &normalize::Options::default(),
+            )
+        };
+
+        // The diamond's shared type is referenced by each type using it, not by the schema.
+        assert_eq!(
+            describe(&references(&order).unwrap()),
+            "com.acme.Money=com.acme.Money[com.acme.Currency=currencies[]],\
+             com.acme.Tax=com.acme.Tax[com.acme.Currency=currencies[]]"
+        );
+
+        write(
+            dir.path(),
+            "currency.avsc",
+            json!({"type": "record", "name": "com.acme.Currency", "fields": [{"name": "rate", "type": "Tax"}]}),
+        );
+        let err = references(&order).unwrap_err();
+        assert!(
+            err.to_string().starts_with(
+                "circular reference: com.acme.Money -> com.acme.Currency -> com.acme.Tax"
+            ),
+            "{}",
+            err
+        );
+
+        let err = references(&write(
+            dir.path(),
+            "invoice.avsc",
+            record(
+                "com.acme.Invoice",
+                json!([{"name": "total", "type": "Amount"}]),
+            ),
+        ))
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed to locate a schema file defining: com.acme.Amount"
+        );
+    }
+}
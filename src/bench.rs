@@ -0,0 +1,186 @@
+use crate::registry::{
+    self,
+    Client,
+    Contract,
+};
+
+use gumdrop::Options;
+use log::*;
+use schema_registry_converter::schema_registry_common::{
+    SchemaType,
+    SuppliedSchema,
+};
+
+use std::time::{
+    Duration,
+    Instant,
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+/// Measure registration and retrieval performance of a Kafka Schema Registry.
+/// This registers `N' new synthetic Avro schemas, each with `K' fields, under
+/// their own subjects, and then fetches each of them back. The subjects are deleted
+/// permanently afterwards unless `--keep' is given; still, only run this against a test registry!
+#[derive(Debug, Options)]
+pub struct BenchSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// number of subjects to register
+    #[options(short = "n", meta = "N", default = "100")]
+    subjects: usize,
+
+    /// number of fields in each schema
+    #[options(short = "k", meta = "K", default = "10")]
+    size: usize,
+
+    /// subject name prefix
+    #[options(meta = "PREFIX", default = "ksrt-bench")]
+    prefix: String,
+
+    /// leave the subjects in the registry afterwards
+    #[options(no_short)]
+    keep: bool,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    pub schema_registry_url: Vec<String>,
+}
+
+struct Stats {
+    elapsed: Duration,
+    latencies: Vec<Duration>,
+}
+
+impl Stats {
+    fn percentile(&self, p: usize) -> Duration {
+        let rank = (p * self.latencies.len()).div_ceil(100);
+        self.latencies[rank.max(1) - 1]
+    }
+
+    fn print(&self, label: &str) {
+        let ops = self.latencies.len();
+        outln!(
+            "{}: {} ops in {:.3}s ({:.1} ops/s)",
+            label,
+            ops,
+            self.elapsed.as_secs_f64(),
+            ops as f64 / self.elapsed.as_secs_f64()
+        );
+
+        if ops > 0 {
+            outln!(
+                "\tlatency: p50 {:.1}ms, p90 {:.1}ms, p99 {:.1}ms, max {:.1}ms",
+                millis(self.percentile(50)),
+                millis(self.percentile(90)),
+                millis(self.percentile(99)),
+                millis(self.latencies[ops - 1]),
+            );
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn synthetic_schema(index: usize, size: usize) -> String {
+    let fields: Vec<_> = (0..size)
+        .map(|i| format!(r#"{{"name":"f{}","type":"long","default":0}}"#, i))
+        .collect();
+
+    format!(
+        r#"{{"type":"record","name":"Bench{}","namespace":"ksrt.bench","fields":[{}]}}"#,
+        index,
+        fields.join(",")
+    )
+}
+
+fn measure<F>(subjects: &[String], mut op: F) -> anyhow::Result<Stats>
+where
+    F: FnMut(usize, &str) -> anyhow::Result<()>,
+{
+    let mut latencies = Vec::with_capacity(subjects.len());
+    let start = Instant::now();
+    for (i, subject) in subjects.iter().enumerate() {
+        let op_start = Instant::now();
+        op(i, subject)?;
+        latencies.push(op_start.elapsed());
+    }
+
+    let elapsed = start.elapsed();
+    latencies.sort();
+
+    Ok(Stats { elapsed, latencies })
+}
+
+fn bench(client: &Client, settings: &BenchSettings, subjects: &[String]) -> anyhow::Result<()> {
+    let register = measure(subjects, |i, subject| {
+        let schema = SuppliedSchema {
+            name: None,
+            schema_type: SchemaType::Avro,
+            schema: synthetic_schema(i, settings.size),
+            references: Vec::new(),
+        };
+
+        client
+            .register(subject, &schema, &Contract::default())
+            .map_err(|e| e.context("error posting schema"))?;
+
+        Ok(())
+    })?;
+
+    let fetch = measure(subjects, |_, subject| {
+        client
+            .get_schema(subject, "latest")
+            .map_err(|e| e.context("error retrieving schema"))?;
+
+        Ok(())
+    })?;
+
+    register.print("register");
+    fetch.print("fetch");
+
+    Ok(())
+}
+
+/// Deletes the benchmark subjects permanently (those that were registered before any failure, that is).
+fn clean_up(client: &Client, subjects: &[String]) -> anyhow::Result<()> {
+    for subject in subjects {
+        match client.delete_subject(subject, true) {
+            Ok(()) => {}
+            Err(e) if registry::Error::has_code(&e, &[registry::SUBJECT_NOT_FOUND]) => {}
+            Err(e) => return Err(e.context(format!("error deleting {}", subject))),
+        }
+    }
+
+    debug!("deleted {} benchmark subject(s)", subjects.len());
+    Ok(())
+}
+
+pub fn run_bench(client: Client, settings: &BenchSettings) -> anyhow::Result<()> {
+    anyhow::ensure!(settings.subjects > 0, "`--subjects' must be at least 1");
+
+    // Make each run register brand new subjects; re-posting an existing schema is a no-op.
+    let run = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let subjects: Vec<_> = (0..settings.subjects)
+        .map(|i| format!("{}-{}-{}", settings.prefix, run, i))
+        .collect();
+
+    debug!("benchmark subjects: {:#?}", subjects);
+
+    let res = bench(&client, settings, &subjects);
+    if settings.keep {
+        return res;
+    }
+
+    // Clean up after a failure as well, but report the failure rather than any error cleaning up.
+    match (res, clean_up(&client, &subjects)) {
+        (Err(e), Err(cleanup_err)) => {
+            warn!("{:#}", cleanup_err);
+            Err(e)
+        }
+        (res, cleaned_up) => res.and(cleaned_up),
+    }
+}
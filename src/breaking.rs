@@ -0,0 +1,1544 @@
+use crate::{
+    avro,
+    exit::{
+        self,
+        Failure,
+    },
+    registry::{
+        Client,
+        Compatibility,
+    },
+    search,
+    SchemaTypeOpt,
+};
+
+use gumdrop::Options;
+use log::*;
+use prost_types::{
+    field_descriptor_proto::{
+        Label,
+        Type,
+    },
+    DescriptorProto,
+    EnumDescriptorProto,
+    FieldDescriptorProto,
+    FileDescriptorProto,
+};
+
+use serde_json::{
+    Map,
+    Value,
+};
+
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+        HashMap,
+        HashSet,
+    },
+    fmt,
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+/// Name of the file the old protobuf schema is written to when taken from a subject.
+const PROTO_ROOT: &str = "ksrt-breaking-root.proto";
+
+/// Analyze the changes between two versions of a schema locally, and report those that break compatibility,
+/// each with the reason. Backward-incompatible changes keep consumers using the new schema from reading data
+/// written with the old one; forward-incompatible changes keep consumers still using the old schema from reading
+/// data written with the new one. Exits with 3 if any change breaks the required compatibility.
+#[derive(Debug, Options)]
+pub struct BreakingSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// schema type (required; one of `avro', `json', or `protobuf')
+    #[options(long = "type", meta = "TYPE", required, short = "T")]
+    schema_type: SchemaTypeOpt,
+
+    /// old schema file (required unless `--old-from-subject' is specified)
+    #[options(meta = "FILE")]
+    old: Option<PathBuf>,
+
+    /// take the old schema from the latest version of this subject instead
+    #[options(no_short, meta = "NAME")]
+    pub old_from_subject: Option<String>,
+
+    /// new schema file (required)
+    #[options(required, meta = "FILE")]
+    new: PathBuf,
+
+    /// include directory for any imports (protobuf only; could be multiple)
+    #[options(meta = "DIR")]
+    include: Vec<PathBuf>,
+
+    /// compatibility required (e.g., `backward', `forward', or `full'); only changes that break it are reported
+    #[options(meta = "LEVEL", default = "backward")]
+    compatibility: Compatibility,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required with `--old-from-subject')
+    #[options(free)]
+    pub schema_registry_url: Vec<String>,
+}
+
+/// Which consumers a change breaks.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Direction {
+    /// Consumers using the new schema, reading data written with the old one.
+    Backward,
+    /// Consumers using the old schema, reading data written with the new one.
+    Forward,
+    Both,
+}
+
+impl Direction {
+    fn breaks(self, compatibility: Compatibility) -> bool {
+        match self {
+            Self::Backward => compatibility.is_backward(),
+            Self::Forward => compatibility.is_forward(),
+            Self::Both => compatibility.is_backward() || compatibility.is_forward(),
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Backward => write!(f, "backward"),
+            Self::Forward => write!(f, "forward"),
+            Self::Both => write!(f, "backward and forward"),
+        }
+    }
+}
+
+/// Change that breaks compatibility in the given direction.
+#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
+struct Breaking {
+    path: String,
+    direction: Direction,
+    reason: String,
+}
+
+#[derive(Default)]
+struct Report {
+    changes: BTreeSet<Breaking>,
+}
+
+impl Report {
+    fn add(&mut self, direction: Direction, path: &str, reason: impl Into<String>) {
+        self.changes.insert(Breaking {
+            path: path.to_owned(),
+            direction,
+            reason: reason.into(),
+        });
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+/// Avro schema as seen by schema resolution, with named types looked up by their full names.
+enum AvroType<'a> {
+    Primitive(&'a str),
+    /// Named type, with its definition unless defined elsewhere (i.e., referenced).
+    Named(String, Option<&'a Map<String, Value>>),
+    Array(&'a Value),
+    Map(&'a Value),
+    Union(&'a [Value]),
+    Invalid,
+}
+
+impl fmt::Display for AvroType<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Primitive(name) => write!(f, "{}", name),
+            Self::Named(name, _) => write!(f, "{}", name),
+            Self::Array(_) => write!(f, "array"),
+            Self::Map(_) => write!(f, "map"),
+            Self::Union(_) => write!(f, "union"),
+            Self::Invalid => write!(f, "(invalid)"),
+        }
+    }
+}
+
+/// Whether data of the writer's primitive type can be read as the reader's.
+fn avro_promotable(writer: &str, reader: &str) -> bool {
+    writer == reader
+        || matches!(
+            (writer, reader),
+            ("int", "long" | "float" | "double")
+                | ("long", "float" | "double")
+                | ("float", "double")
+                | ("string", "bytes")
+                | ("bytes", "string")
+        )
+}
+
+/// Resolves a reader's schema against a writer's as Avro does when reading data, reporting what fails.
+struct AvroResolver<'a> {
+    reader: HashMap<String, &'a Map<String, Value>>,
+    writer: HashMap<String, &'a Map<String, Value>>,
+    /// Whether the reader is the new schema (i.e., checking backward compatibility).
+    backward: bool,
+    /// Pairs of named types resolved (or being resolved) already, which also stops recursion.
+    resolved: HashSet<(String, String)>,
+}
+
+impl<'a> AvroResolver<'a> {
+    fn direction(&self) -> Direction {
+        if self.backward {
+            Direction::Backward
+        } else {
+            Direction::Forward
+        }
+    }
+
+    fn view(
+        schema: &'a Value,
+        namespace: &str,
+        defs: &HashMap<String, &'a Map<String, Value>>,
+    ) -> AvroType<'a> {
+        match schema {
+            Value::String(name) if avro::PRIMITIVES.contains(&name.as_str()) => {
+                AvroType::Primitive(name)
+            }
+            Value::String(name) => {
+                let name = avro::full_name(name, namespace);
+                let def = defs.get(&name).copied();
+                AvroType::Named(name, def)
+            }
+            Value::Array(schemas) => AvroType::Union(schemas),
+            Value::Object(object) => match object.get("type") {
+                Some(Value::String(kind))
+                    if ["record", "error", "enum", "fixed"].contains(&kind.as_str()) =>
+                {
+                    AvroType::Named(avro::defined_name(object, namespace), Some(object))
+                }
+                Some(Value::String(kind)) if kind == "array" => object
+                    .get("items")
+                    .map_or(AvroType::Invalid, AvroType::Array),
+                Some(Value::String(kind)) if kind == "map" => object
+                    .get("values")
+                    .map_or(AvroType::Invalid, AvroType::Map),
+                Some(schema) => Self::view(schema, namespace, defs),
+                None => AvroType::Invalid,
+            },
+            _ => AvroType::Invalid,
+        }
+    }
+
+    /// Describes a change from the writer's type to the reader's in terms of the old and new schemas.
+    fn type_change(&self, reader: &AvroType, writer: &AvroType) -> String {
+        if self.backward {
+            format!("type changed from {} to {}", writer, reader)
+        } else {
+            format!("type changed from {} to {}", reader, writer)
+        }
+    }
+
+    /// Whether the reader's type is the one a writer's type resolves to (as a union branch).
+    fn matches(reader: &AvroType, writer: &AvroType) -> bool {
+        match (reader, writer) {
+            (AvroType::Primitive(r), AvroType::Primitive(w)) => avro_promotable(w, r),
+            (AvroType::Named(r, _), AvroType::Named(w, _)) => r == w,
+            (AvroType::Array(_), AvroType::Array(_)) | (AvroType::Map(_), AvroType::Map(_)) => true,
+            _ => false,
+        }
+    }
+
+    fn resolve(
+        &mut self,
+        reader: &'a Value,
+        reader_ns: &str,
+        writer: &'a Value,
+        writer_ns: &str,
+        path: &str,
+        report: &mut Report,
+    ) {
+        let r = Self::view(reader, reader_ns, &self.reader);
+        let w = Self::view(writer, writer_ns, &self.writer);
+        match (&r, &w) {
+            (_, AvroType::Union(branches)) => {
+                for branch in branches.iter() {
+                    self.resolve(reader, reader_ns, branch, writer_ns, path, report);
+                }
+            }
+            (AvroType::Union(branches), _) => {
+                let branch = branches
+                    .iter()
+                    .find(|branch| Self::matches(&Self::view(branch, reader_ns, &self.reader), &w));
+
+                match branch {
+                    Some(branch) => {
+                        self.resolve(branch, reader_ns, writer, writer_ns, path, report)
+                    }
+                    None if self.backward => report.add(
+                        self.direction(),
+                        path,
+                        format!("{} removed from the union", w),
+                    ),
+                    None => report.add(self.direction(), path, format!("{} added to the union", w)),
+                }
+            }
+            (AvroType::Primitive(rp), AvroType::Primitive(wp)) => {
+                if !avro_promotable(wp, rp) {
+                    report.add(self.direction(), path, self.type_change(&r, &w));
+                }
+            }
+            (AvroType::Array(ri), AvroType::Array(wi)) => {
+                self.resolve(ri, reader_ns, wi, writer_ns, &join(path, "[]"), report)
+            }
+            (AvroType::Map(rv), AvroType::Map(wv)) => {
+                self.resolve(rv, reader_ns, wv, writer_ns, &join(path, "{}"), report)
+            }
+            (AvroType::Named(rn, rdef), AvroType::Named(wn, wdef)) => {
+                let aliased = rdef
+                    .and_then(|def| def.get("aliases"))
+                    .and_then(Value::as_array)
+                    .is_some_and(|aliases| {
+                        aliases.iter().any(|alias| {
+                            alias.as_str().is_some_and(|alias| {
+                                avro::full_name(alias, avro::namespace_of(rn)) == *wn
+                            })
+                        })
+                    });
+
+                if rn != wn && !aliased {
+                    report.add(self.direction(), path, self.type_change(&r, &w));
+                    return;
+                }
+
+                if !self.resolved.insert((rn.clone(), wn.clone())) {
+                    return;
+                }
+
+                // Types defined elsewhere (i.e., referenced) are taken to be the same.
+                if let (Some(rdef), Some(wdef)) = (rdef, wdef) {
+                    self.resolve_named(rn, rdef, wn, wdef, path, report);
+                }
+            }
+            _ => report.add(self.direction(), path, self.type_change(&r, &w)),
+        }
+    }
+
+    fn resolve_named(
+        &mut self,
+        reader_name: &str,
+        reader: &'a Map<String, Value>,
+        writer_name: &str,
+        writer: &'a Map<String, Value>,
+        path: &str,
+        report: &mut Report,
+    ) {
+        let kind = |def: &Map<String, Value>| {
+            def.get("type")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .replace("error", "record")
+        };
+
+        let path = if path.is_empty() { reader_name } else { path };
+        if kind(reader) != kind(writer) {
+            report.add(
+                self.direction(),
+                path,
+                format!(
+                    "{} changed from {} to {}",
+                    reader_name,
+                    if self.backward {
+                        kind(writer)
+                    } else {
+                        kind(reader)
+                    },
+                    if self.backward {
+                        kind(reader)
+                    } else {
+                        kind(writer)
+                    }
+                ),
+            );
+
+            return;
+        }
+
+        match kind(reader).as_str() {
+            "record" => self.resolve_fields(reader_name, reader, writer_name, writer, path, report),
+            "enum" => {
+                let symbols = |def: &'a Map<String, Value>| -> Vec<&'a str> {
+                    def.get("symbols")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .collect()
+                };
+
+                let reader_symbols = symbols(reader);
+                if reader.get("default").is_some() {
+                    return;
+                }
+
+                for symbol in symbols(writer) {
+                    if !reader_symbols.contains(&symbol) {
+                        let reason = if self.backward {
+                            format!("symbol {} removed (and the enum has no default)", symbol)
+                        } else {
+                            format!("symbol {} added (and the old enum has no default)", symbol)
+                        };
+
+                        report.add(self.direction(), path, reason);
+                    }
+                }
+            }
+            "fixed" => {
+                let size = |def: &Map<String, Value>| def.get("size").and_then(Value::as_u64);
+                if size(reader) != size(writer) {
+                    report.add(
+                        Direction::Both,
+                        path,
+                        format!(
+                            "size changed from {} to {}",
+                            if self.backward {
+                                size(writer)
+                            } else {
+                                size(reader)
+                            }
+                            .unwrap_or_default(),
+                            if self.backward {
+                                size(reader)
+                            } else {
+                                size(writer)
+                            }
+                            .unwrap_or_default()
+                        ),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn resolve_fields(
+        &mut self,
+        reader_name: &str,
+        reader: &'a Map<String, Value>,
+        writer_name: &str,
+        writer: &'a Map<String, Value>,
+        path: &str,
+        report: &mut Report,
+    ) {
+        let fields = |def: &'a Map<String, Value>| -> Vec<&'a Map<String, Value>> {
+            def.get("fields")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_object)
+                .collect()
+        };
+
+        let name = |field: &Map<String, Value>| {
+            field
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_owned()
+        };
+
+        let writer_fields = fields(writer);
+        for field in fields(reader) {
+            let field_name = name(field);
+            let aliases: Vec<_> = field
+                .get("aliases")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+
+            let field_path = join(path, &field_name);
+            let written = writer_fields
+                .iter()
+                .find(|w| name(w) == field_name || aliases.contains(&name(w).as_str()));
+
+            match (written, field.get("type")) {
+                (Some(written), Some(reader_type)) => {
+                    if let Some(writer_type) = written.get("type") {
+                        self.resolve(
+                            reader_type,
+                            avro::namespace_of(reader_name),
+                            writer_type,
+                            avro::namespace_of(writer_name),
+                            &field_path,
+                            report,
+                        );
+                    }
+                }
+                (None, _) if field.get("default").is_none() => {
+                    let reason = if self.backward {
+                        "field added without a default"
+                    } else {
+                        "field removed, but it has no default in the old schema"
+                    };
+
+                    report.add(self.direction(), &field_path, reason);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn check_avro(old: &Value, new: &Value, report: &mut Report) {
+    let mut old_defs = HashMap::new();
+    avro::definitions(old, "", &mut old_defs);
+    let mut new_defs = HashMap::new();
+    avro::definitions(new, "", &mut new_defs);
+
+    for backward in [true, false] {
+        let (reader, writer) = if backward { (new, old) } else { (old, new) };
+        let (reader_defs, writer_defs) = if backward {
+            (new_defs.clone(), old_defs.clone())
+        } else {
+            (old_defs.clone(), new_defs.clone())
+        };
+
+        let mut resolver = AvroResolver {
+            reader: reader_defs,
+            writer: writer_defs,
+            backward,
+            resolved: HashSet::new(),
+        };
+
+        resolver.resolve(reader, "", writer, "", "", report);
+    }
+}
+
+/// Scalar types that are interchangeable on the wire (values may be truncated or reinterpreted, as protobuf allows).
+fn protobuf_wire_group(field_type: Type) -> Option<u8> {
+    match field_type {
+        Type::Int32 | Type::Uint32 | Type::Int64 | Type::Uint64 | Type::Bool | Type::Enum => {
+            Some(0)
+        }
+        Type::Sint32 | Type::Sint64 => Some(1),
+        Type::Fixed32 | Type::Sfixed32 => Some(2),
+        Type::Fixed64 | Type::Sfixed64 => Some(3),
+        Type::String | Type::Bytes => Some(4),
+        _ => None,
+    }
+}
+
+/// Name of a protobuf field's type: the full name of a message or enum, or the name of a scalar.
+pub fn protobuf_type_name(field: &FieldDescriptorProto) -> String {
+    match field.r#type() {
+        Type::Message | Type::Enum | Type::Group => {
+            field.type_name().trim_start_matches('.').to_owned()
+        }
+        other => format!("{:?}", other).to_lowercase(),
+    }
+}
+
+/// Messages and enums declared in a protobuf file, by their full names.
+#[derive(Default)]
+struct ProtobufTypes<'a> {
+    messages: BTreeMap<String, &'a DescriptorProto>,
+    enums: BTreeMap<String, &'a EnumDescriptorProto>,
+}
+
+impl<'a> ProtobufTypes<'a> {
+    fn of(fd: &'a FileDescriptorProto) -> Self {
+        let mut types = Self::default();
+        let scope = fd.package().to_owned();
+        for message in &fd.message_type {
+            types.add_message(message, &scope);
+        }
+
+        for enum_type in &fd.enum_type {
+            types
+                .enums
+                .insert(join(&scope, enum_type.name()), enum_type);
+        }
+
+        types
+    }
+
+    fn add_message(&mut self, message: &'a DescriptorProto, scope: &str) {
+        let name = join(scope, message.name());
+        for nested in &message.nested_type {
+            self.add_message(nested, &name);
+        }
+
+        for enum_type in &message.enum_type {
+            self.enums.insert(join(&name, enum_type.name()), enum_type);
+        }
+
+        self.messages.insert(name, message);
+    }
+}
+
+/// Name of the (real, not synthetic) oneof a field belongs to, if any.
+fn protobuf_oneof<'a>(
+    message: &'a DescriptorProto,
+    field: &FieldDescriptorProto,
+) -> Option<&'a str> {
+    let oneof = message.oneof_decl.get(field.oneof_index? as usize)?.name();
+    let members = message
+        .field
+        .iter()
+        .filter(|f| f.oneof_index == field.oneof_index)
+        .count();
+
+    // Proto3 optional fields are each wrapped in a synthetic oneof of their own.
+    if members == 1 && oneof.starts_with('_') {
+        None
+    } else {
+        Some(oneof)
+    }
+}
+
+fn check_protobuf_message(
+    name: &str,
+    old: &DescriptorProto,
+    new: &DescriptorProto,
+    report: &mut Report,
+) {
+    let old_fields: BTreeMap<_, _> = old.field.iter().map(|f| (f.number(), f)).collect();
+    let new_fields: BTreeMap<_, _> = new.field.iter().map(|f| (f.number(), f)).collect();
+
+    for (number, old_field) in &old_fields {
+        let path = join(name, old_field.name());
+        let new_field = match new_fields.get(number) {
+            Some(new_field) => new_field,
+            None => {
+                if old_field.label() == Label::Required {
+                    report.add(Direction::Forward, &path, "required field removed");
+                }
+
+                continue;
+            }
+        };
+
+        let (old_type, new_type) = (old_field.r#type(), new_field.r#type());
+        let (old_group, new_group) = (protobuf_wire_group(old_type), protobuf_wire_group(new_type));
+        let type_changed = match (old_type, new_type) {
+            (Type::Message, Type::Message)
+            | (Type::Group, Type::Group)
+            | (Type::Enum, Type::Enum) => old_field.type_name() != new_field.type_name(),
+            _ => old_type != new_type && (old_group.is_none() || old_group != new_group),
+        };
+
+        if type_changed {
+            report.add(
+                Direction::Both,
+                &path,
+                format!(
+                    "type of field {} changed from {} to {}",
+                    number,
+                    protobuf_type_name(old_field),
+                    protobuf_type_name(new_field)
+                ),
+            );
+        }
+
+        let repeated = |field: &FieldDescriptorProto| field.label() == Label::Repeated;
+        if repeated(old_field) != repeated(new_field) {
+            report.add(
+                Direction::Both,
+                &path,
+                if repeated(new_field) {
+                    "field changed from singular to repeated"
+                } else {
+                    "field changed from repeated to singular"
+                },
+            );
+        }
+
+        match (
+            protobuf_oneof(old, old_field),
+            protobuf_oneof(new, new_field),
+        ) {
+            (None, Some(oneof)) => {
+                let existing = old.oneof_decl.iter().any(|o| o.name() == oneof);
+                let moved = new
+                    .field
+                    .iter()
+                    .filter(|f| protobuf_oneof(new, f) == Some(oneof))
+                    .filter(|f| old_fields.contains_key(&f.number()))
+                    .count();
+
+                if existing || moved > 1 {
+                    report.add(
+                        Direction::Both,
+                        &path,
+                        format!("field moved into oneof {}", oneof),
+                    );
+                }
+            }
+            (Some(oneof), None) => report.add(
+                Direction::Both,
+                &path,
+                format!("field moved out of oneof {}", oneof),
+            ),
+            _ => {}
+        }
+    }
+
+    for (number, new_field) in &new_fields {
+        if !old_fields.contains_key(number) && new_field.label() == Label::Required {
+            report.add(
+                Direction::Backward,
+                &join(name, new_field.name()),
+                "required field added",
+            );
+        }
+    }
+}
+
+fn check_protobuf(old: &FileDescriptorProto, new: &FileDescriptorProto, report: &mut Report) {
+    let old_types = ProtobufTypes::of(old);
+    let new_types = ProtobufTypes::of(new);
+
+    for (name, old_message) in &old_types.messages {
+        match new_types.messages.get(name) {
+            Some(new_message) => check_protobuf_message(name, old_message, new_message, report),
+            None => report.add(Direction::Backward, name, "message removed"),
+        }
+    }
+
+    for name in new_types.messages.keys() {
+        if !old_types.messages.contains_key(name) {
+            report.add(Direction::Forward, name, "message added");
+        }
+    }
+
+    for name in old_types.enums.keys() {
+        if !new_types.enums.contains_key(name) {
+            report.add(Direction::Backward, name, "enum removed");
+        }
+    }
+}
+
+/// JSON Schema keywords that bound values from above (the larger the value, the more data is valid).
+const JSON_UPPER_BOUNDS: &[&str] = &[
+    "maximum",
+    "exclusiveMaximum",
+    "maxLength",
+    "maxItems",
+    "maxProperties",
+];
+
+/// JSON Schema keywords that bound values from below (the smaller the value, the more data is valid).
+const JSON_LOWER_BOUNDS: &[&str] = &[
+    "minimum",
+    "exclusiveMinimum",
+    "minLength",
+    "minItems",
+    "minProperties",
+];
+
+fn json_types(schema: &Map<String, Value>) -> Option<BTreeSet<String>> {
+    let mut types: BTreeSet<String> = match schema.get("type")? {
+        Value::String(name) => std::iter::once(name.clone()).collect(),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_owned)
+            .collect(),
+        _ => return None,
+    };
+
+    // Integers are numbers, too.
+    if types.contains("number") {
+        types.remove("integer");
+    }
+
+    Some(types)
+}
+
+fn json_closed(schema: &Map<String, Value>) -> bool {
+    schema.get("additionalProperties") == Some(&Value::Bool(false))
+}
+
+fn json_strings(schema: &Map<String, Value>, keyword: &str) -> BTreeSet<String> {
+    schema
+        .get(keyword)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|value| match value {
+            Value::String(s) => s.clone(),
+            value => value.to_string(),
+        })
+        .collect()
+}
+
+/// Reports the changes that make data valid under one version of a JSON schema invalid under the other.
+/// Backward compatibility requires the new schema to accept everything the old one does, and vice versa.
+fn check_json(old: &Value, new: &Value, path: &str, report: &mut Report) {
+    let (old, new) = match (old, new) {
+        (Value::Object(old), Value::Object(new)) => (old, new),
+        (Value::Bool(true), Value::Object(new)) if !new.is_empty() => {
+            report.add(
+                Direction::Backward,
+                path,
+                "constraints added to a schema that accepted anything",
+            );
+            return;
+        }
+        (Value::Object(old), Value::Bool(true)) if !old.is_empty() => {
+            report.add(Direction::Forward, path, "constraints removed");
+            return;
+        }
+        _ => {
+            if old != new {
+                report.add(Direction::Both, path, "schema changed");
+            }
+
+            return;
+        }
+    };
+
+    let at = if path.is_empty() { "/" } else { path };
+    if old.get("$ref") != new.get("$ref") {
+        report.add(
+            Direction::Both,
+            at,
+            format!(
+                "reference changed from {} to {}",
+                old.get("$ref").unwrap_or(&Value::Null),
+                new.get("$ref").unwrap_or(&Value::Null)
+            ),
+        );
+    }
+
+    match (json_types(old), json_types(new)) {
+        (Some(old_types), Some(new_types)) => {
+            let removed: Vec<_> = old_types.difference(&new_types).cloned().collect();
+            let added: Vec<_> = new_types.difference(&old_types).cloned().collect();
+            if !removed.is_empty() {
+                report.add(
+                    Direction::Backward,
+                    at,
+                    format!("type {} no longer allowed", removed.join(", ")),
+                );
+            }
+
+            if !added.is_empty() {
+                report.add(
+                    Direction::Forward,
+                    at,
+                    format!("type {} now allowed", added.join(", ")),
+                );
+            }
+        }
+        (None, Some(new_types)) => report.add(
+            Direction::Backward,
+            at,
+            format!(
+                "type restricted to {}",
+                new_types.into_iter().collect::<Vec<_>>().join(", ")
+            ),
+        ),
+        (Some(_), None) => report.add(Direction::Forward, at, "type restriction removed"),
+        (None, None) => {}
+    }
+
+    if old.contains_key("enum") || new.contains_key("enum") {
+        let (old_values, new_values) = (json_strings(old, "enum"), json_strings(new, "enum"));
+        match (old.contains_key("enum"), new.contains_key("enum")) {
+            (true, true) => {
+                for value in old_values.difference(&new_values) {
+                    report.add(
+                        Direction::Backward,
+                        at,
+                        format!("enum value {} removed", value),
+                    );
+                }
+
+                for value in new_values.difference(&old_values) {
+                    report.add(
+                        Direction::Forward,
+                        at,
+                        format!("enum value {} added", value),
+                    );
+                }
+            }
+            (false, true) => report.add(Direction::Backward, at, "values restricted to an enum"),
+            _ => report.add(Direction::Forward, at, "enum restriction removed"),
+        }
+    }
+
+    for keyword in JSON_UPPER_BOUNDS.iter().chain(JSON_LOWER_BOUNDS) {
+        let upper = JSON_UPPER_BOUNDS.contains(keyword);
+        let (old_bound, new_bound) = (
+            old.get(*keyword).and_then(Value::as_f64),
+            new.get(*keyword).and_then(Value::as_f64),
+        );
+
+        let tightened = match (old_bound, new_bound) {
+            (Some(old_bound), Some(new_bound)) if old_bound == new_bound => continue,
+            (Some(old_bound), Some(new_bound)) => (new_bound < old_bound) == upper,
+            (None, Some(_)) => true,
+            (Some(_), None) => false,
+            (None, None) => continue,
+        };
+
+        let describe =
+            |bound: Option<f64>| bound.map_or_else(|| "none".to_owned(), |b| b.to_string());
+        let reason = format!(
+            "{} changed from {} to {}",
+            keyword,
+            describe(old_bound),
+            describe(new_bound)
+        );
+        let direction = if tightened {
+            Direction::Backward
+        } else {
+            Direction::Forward
+        };
+        report.add(direction, at, reason);
+    }
+
+    let (old_required, new_required) =
+        (json_strings(old, "required"), json_strings(new, "required"));
+    for name in new_required.difference(&old_required) {
+        report.add(
+            Direction::Backward,
+            at,
+            format!("property {} is now required", name),
+        );
+    }
+
+    for name in old_required.difference(&new_required) {
+        report.add(
+            Direction::Forward,
+            at,
+            format!("property {} is no longer required", name),
+        );
+    }
+
+    match (json_closed(old), json_closed(new)) {
+        (false, true) => report.add(
+            Direction::Backward,
+            at,
+            "additional properties no longer allowed",
+        ),
+        (true, false) => report.add(Direction::Forward, at, "additional properties now allowed"),
+        _ => {}
+    }
+
+    let properties = |schema: &'_ Map<String, Value>| {
+        schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    let (old_properties, new_properties) = (properties(old), properties(new));
+    for (name, old_property) in &old_properties {
+        let property_path = format!("{}/properties/{}", path, name);
+        match new_properties.get(name) {
+            Some(new_property) => check_json(old_property, new_property, &property_path, report),
+            // Data with the property is invalid unless additional properties are allowed.
+            None if json_closed(new) => {
+                report.add(Direction::Backward, &property_path, "property removed")
+            }
+            None => report.add(
+                Direction::Forward,
+                &property_path,
+                "property removed, but the new schema allows it with any value",
+            ),
+        }
+    }
+
+    for name in new_properties.keys() {
+        if !old_properties.contains_key(name) && !json_closed(old) {
+            report.add(
+                Direction::Backward,
+                &format!("{}/properties/{}", path, name),
+                "property added, but the old schema allowed it with any value",
+            );
+        }
+    }
+
+    for keyword in ["items", "additionalProperties", "not"] {
+        if let (Some(old_schema), Some(new_schema)) = (old.get(keyword), new.get(keyword)) {
+            if keyword == "not" {
+                // Negation swaps the directions.
+                check_json(
+                    new_schema,
+                    old_schema,
+                    &format!("{}/{}", path, keyword),
+                    report,
+                );
+            } else if old_schema.is_object() || new_schema.is_object() {
+                check_json(
+                    old_schema,
+                    new_schema,
+                    &format!("{}/{}", path, keyword),
+                    report,
+                );
+            }
+        }
+    }
+}
+
+fn read(path: &Path) -> anyhow::Result<String> {
+    fs::read_to_string(path)
+        .map_err(|e| anyhow::format_err!("failed to read {}: {}", path.display(), e))
+}
+
+/// Compiles a protobuf file and returns the descriptor of that file (as opposed to those of its imports).
+fn compile_protobuf(file: &Path, includes: &[PathBuf]) -> anyhow::Result<FileDescriptorProto> {
+    let file = file.canonicalize()?;
+    let dir = file
+        .parent()
+        .expect("file has a parent directory")
+        .to_path_buf();
+    let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let includes: Vec<_> = std::iter::once(dir)
+        .chain(includes.iter().cloned())
+        .collect();
+    crate::parse_protos(std::slice::from_ref(&file), &includes)?
+        .file
+        .into_iter()
+        .find(|fd| fd.name() == name)
+        .ok_or_else(|| {
+            anyhow::format_err!("failed to locate file descriptor for {}", file.display())
+        })
+}
+
+pub fn run_breaking(client: Option<Client>, settings: &BreakingSettings) -> anyhow::Result<()> {
+    let new = read(&settings.new)?;
+    let mut report = Report::default();
+
+    // Keeps the old protobuf schema taken from the registry (along with its references) for compilation.
+    let mut _tmp = None;
+    let (old, old_file) = match (&settings.old, &settings.old_from_subject, &client) {
+        (Some(old), None, _) => (read(old)?, old.clone()),
+        (None, Some(subject), Some(client)) => {
+            let schema = client
+                .get_schema(subject, "latest")
+                .map_err(|e| e.context(format!("error retrieving schema for {}", subject)))?;
+
+            let tmp = tempfile::Builder::new().prefix("ksrt-breaking").tempdir()?;
+            let file = tmp.path().join(PROTO_ROOT);
+            fs::write(&file, &schema.schema)?;
+            if settings.schema_type == SchemaTypeOpt::Protobuf {
+                search::write_references(
+                    client,
+                    &schema.references,
+                    tmp.path(),
+                    &mut HashSet::new(),
+                )?;
+            }
+
+            _tmp = Some(tmp);
+            (schema.schema, file)
+        }
+        _ => anyhow::bail!("either `--old' or `--old-from-subject' is required (but not both)"),
+    };
+
+    match settings.schema_type {
+        SchemaTypeOpt::Avro => {
+            let parse = |text: &str| {
+                serde_json::from_str::<Value>(text)
+                    .map_err(|e| anyhow::format_err!("invalid Avro schema: {}", e))
+            };
+
+            check_avro(&parse(&old)?, &parse(&new)?, &mut report);
+        }
+        SchemaTypeOpt::Json => {
+            let parse = |text: &str| {
+                serde_json::from_str::<Value>(text)
+                    .map_err(|e| anyhow::format_err!("invalid JSON schema: {}", e))
+            };
+
+            check_json(&parse(&old)?, &parse(&new)?, "", &mut report);
+        }
+        SchemaTypeOpt::Protobuf => {
+            let old = compile_protobuf(&old_file, &settings.include)?;
+            let new = compile_protobuf(&settings.new, &settings.include)?;
+            check_protobuf(&old, &new, &mut report);
+        }
+    }
+
+    debug!(
+        "{} change(s) breaking compatibility in some direction",
+        report.changes.len()
+    );
+
+    let breaking: Vec<_> = report
+        .changes
+        .into_iter()
+        .filter(|change| change.direction.breaks(settings.compatibility))
+        .collect();
+
+    for change in &breaking {
+        outln!(
+            "{}: {} (breaks {} compatibility)",
+            change.path,
+            change.reason,
+            change.direction
+        );
+    }
+
+    if !breaking.is_empty() {
+        return Err(Failure::new(
+            exit::INCOMPATIBLE,
+            format!(
+                "{} change(s) breaking {} compatibility",
+                breaking.len(),
+                settings.compatibility
+            ),
+        )
+        .into());
+    }
+
+    outln!(
+        "no changes breaking {} compatibility",
+        settings.compatibility
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn changes(report: Report) -> Vec<(String, Direction, String)> {
+        report
+            .changes
+            .into_iter()
+            .map(|change| (change.path, change.direction, change.reason))
+            .collect()
+    }
+
+    fn change(path: &str, direction: Direction, reason: &str) -> (String, Direction, String) {
+        (path.to_owned(), direction, reason.to_owned())
+    }
+
+    fn avro(old: Value, new: Value) -> Vec<(String, Direction, String)> {
+        let mut report = Report::default();
+        check_avro(&old, &new, &mut report);
+        changes(report)
+    }
+
+    fn record(fields: Value) -> Value {
+        json!({"type": "record", "name": "Order", "namespace": "com.example", "fields": fields})
+    }
+
+    fn protobuf(old: &str, new: &str) -> Vec<(String, Direction, String)> {
+        let dir = tempfile::tempdir().unwrap();
+        let compile = |name: &str, body: &str| {
+            let file = dir.path().join(name);
+            let text = format!("syntax = \"proto3\";\npackage example;\n{}", body);
+            fs::write(&file, text).unwrap();
+            compile_protobuf(&file, &[]).unwrap()
+        };
+
+        let mut report = Report::default();
+        check_protobuf(
+            &compile("old.proto", old),
+            &compile("new.proto", new),
+            &mut report,
+        );
+        changes(report)
+    }
+
+    fn json_schema(old: Value, new: Value) -> Vec<(String, Direction, String)> {
+        let mut report = Report::default();
+        check_json(&old, &new, "", &mut report);
+        changes(report)
+    }
+
+    #[test]
+    fn avro_field_added_without_default_breaks_backward() {
+        let old = record(json!([{"name": "id", "type": "string"}]));
+        assert_eq!(
+            avro(
+                old.clone(),
+                record(json!([
+                    {"name": "id", "type": "string"},
+                    {"name": "total", "type": "long"}
+                ]))
+            ),
+            [change(
+                "com.example.Order.total",
+                Direction::Backward,
+                "field added without a default"
+            )]
+        );
+
+        assert!(avro(
+            old.clone(),
+            record(json!([
+                {"name": "id", "type": "string"},
+                {"name": "total", "type": "long", "default": 0}
+            ]))
+        )
+        .is_empty());
+
+        // Removing it again breaks forward compatibility, as the old schema has no default for it.
+        assert_eq!(
+            avro(
+                record(json!([
+                    {"name": "id", "type": "string"},
+                    {"name": "total", "type": "long"}
+                ])),
+                old
+            ),
+            [change(
+                "com.example.Order.total",
+                Direction::Forward,
+                "field removed, but it has no default in the old schema"
+            )]
+        );
+    }
+
+    #[test]
+    fn avro_enum_symbol_removed() {
+        let status = |symbols: Value, default: Option<&str>| {
+            let mut status = json!({"type": "enum", "name": "Status", "symbols": symbols});
+            if let Some(default) = default {
+                status["default"] = json!(default);
+            }
+
+            record(json!([{"name": "status", "type": status}]))
+        };
+
+        assert_eq!(
+            avro(
+                status(json!(["NEW", "PAID"]), None),
+                status(json!(["NEW"]), None)
+            ),
+            [change(
+                "com.example.Order.status",
+                Direction::Backward,
+                "symbol PAID removed (and the enum has no default)"
+            )]
+        );
+
+        assert!(avro(
+            status(json!(["NEW", "PAID"]), None),
+            status(json!(["NEW"]), Some("NEW"))
+        )
+        .is_empty());
+
+        assert_eq!(
+            avro(
+                status(json!(["NEW"]), None),
+                status(json!(["NEW", "PAID"]), None)
+            ),
+            [change(
+                "com.example.Order.status",
+                Direction::Forward,
+                "symbol PAID added (and the old enum has no default)"
+            )]
+        );
+    }
+
+    #[test]
+    fn avro_union_branch_added_or_removed() {
+        let note = |types: Value| record(json!([{"name": "note", "type": types}]));
+        assert_eq!(
+            avro(
+                note(json!(["null", "string"])),
+                note(json!(["null", "string", "long"]))
+            ),
+            [change(
+                "com.example.Order.note",
+                Direction::Forward,
+                "long added to the union"
+            )]
+        );
+
+        assert_eq!(
+            avro(
+                note(json!(["null", "string", "long"])),
+                note(json!(["null", "string"]))
+            ),
+            [change(
+                "com.example.Order.note",
+                Direction::Backward,
+                "long removed from the union"
+            )]
+        );
+
+        // A type promoted into a union resolves to the matching branch, though old readers can't read its others.
+        assert_eq!(
+            avro(note(json!("int")), note(json!(["null", "long"]))),
+            [
+                change(
+                    "com.example.Order.note",
+                    Direction::Forward,
+                    "type changed from int to long"
+                ),
+                change(
+                    "com.example.Order.note",
+                    Direction::Forward,
+                    "type changed from int to null"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn avro_renames_by_alias() {
+        let old = record(json!([{"name": "total", "type": "long"}]));
+        let renamed = record(json!([{"name": "amount", "type": "long", "aliases": ["total"]}]));
+
+        // Readers of the new schema find the field by its alias, but readers of the old one don't.
+        assert_eq!(
+            avro(old.clone(), renamed),
+            [change(
+                "com.example.Order.total",
+                Direction::Forward,
+                "field removed, but it has no default in the old schema"
+            )]
+        );
+
+        assert_eq!(
+            avro(
+                old.clone(),
+                record(json!([{"name": "amount", "type": "long"}]))
+            ),
+            [
+                change(
+                    "com.example.Order.amount",
+                    Direction::Backward,
+                    "field added without a default"
+                ),
+                change(
+                    "com.example.Order.total",
+                    Direction::Forward,
+                    "field removed, but it has no default in the old schema"
+                ),
+            ]
+        );
+
+        let mut renamed = old.clone();
+        renamed["name"] = json!("Purchase");
+        renamed["aliases"] = json!(["Order"]);
+        assert!(!avro(old.clone(), renamed.clone())
+            .iter()
+            .any(|(_, direction, _)| *direction == Direction::Backward));
+
+        renamed.as_object_mut().unwrap().remove("aliases");
+        assert!(avro(old, renamed)
+            .iter()
+            .any(|(_, direction, _)| *direction == Direction::Backward));
+    }
+
+    #[test]
+    fn protobuf_field_number_reused_with_another_type() {
+        assert_eq!(
+            protobuf(
+                "message Order { string name = 2; }",
+                "message Order { int64 count = 2; }"
+            ),
+            [change(
+                "example.Order.name",
+                Direction::Both,
+                "type of field 2 changed from string to int64"
+            )]
+        );
+
+        // Types that share a wire encoding are interchangeable.
+        assert!(protobuf(
+            "message Order { int32 count = 2; }",
+            "message Order { int64 count = 2; }"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn protobuf_enum_type_changed() {
+        assert_eq!(
+            protobuf(
+                "enum A { A_0 = 0; } enum B { B_0 = 0; } message Order { A kind = 1; }",
+                "enum A { A_0 = 0; } enum B { B_0 = 0; } message Order { B kind = 1; }"
+            ),
+            [change(
+                "example.Order.kind",
+                Direction::Both,
+                "type of field 1 changed from example.A to example.B"
+            )]
+        );
+    }
+
+    #[test]
+    fn protobuf_fields_moved_into_and_out_of_oneofs() {
+        let plain = "message Order { int32 a = 1; int32 b = 2; }";
+        let oneof = "message Order { oneof choice { int32 a = 1; int32 b = 2; } }";
+        assert_eq!(
+            protobuf(plain, oneof),
+            [
+                change(
+                    "example.Order.a",
+                    Direction::Both,
+                    "field moved into oneof choice"
+                ),
+                change(
+                    "example.Order.b",
+                    Direction::Both,
+                    "field moved into oneof choice"
+                ),
+            ]
+        );
+
+        assert_eq!(
+            protobuf(oneof, plain),
+            [
+                change(
+                    "example.Order.a",
+                    Direction::Both,
+                    "field moved out of oneof choice"
+                ),
+                change(
+                    "example.Order.b",
+                    Direction::Both,
+                    "field moved out of oneof choice"
+                ),
+            ]
+        );
+
+        // A single field may move into a new oneof.
+        assert!(protobuf(
+            "message Order { int32 a = 1; }",
+            "message Order { oneof choice { int32 a = 1; string c = 3; } }"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn protobuf_singular_and_repeated() {
+        assert_eq!(
+            protobuf(
+                "message Order { string tag = 1; }",
+                "message Order { repeated string tag = 1; }"
+            ),
+            [change(
+                "example.Order.tag",
+                Direction::Both,
+                "field changed from singular to repeated"
+            )]
+        );
+
+        assert_eq!(
+            protobuf(
+                "message Order { repeated string tag = 1; }",
+                "message Order { string tag = 1; }"
+            ),
+            [change(
+                "example.Order.tag",
+                Direction::Both,
+                "field changed from repeated to singular"
+            )]
+        );
+    }
+
+    #[test]
+    fn json_required_added() {
+        let properties = json!({"id": {"type": "string"}});
+        assert_eq!(
+            json_schema(
+                json!({"type": "object", "properties": properties}),
+                json!({"type": "object", "properties": properties, "required": ["id"]})
+            ),
+            [change(
+                "/",
+                Direction::Backward,
+                "property id is now required"
+            )]
+        );
+    }
+
+    #[test]
+    fn json_additional_properties_closed() {
+        assert_eq!(
+            json_schema(
+                json!({"type": "object", "properties": {"id": {"type": "string"}}}),
+                json!({
+                    "type": "object",
+                    "properties": {"id": {"type": "string"}},
+                    "additionalProperties": false
+                })
+            ),
+            [change(
+                "/",
+                Direction::Backward,
+                "additional properties no longer allowed"
+            )]
+        );
+
+        // Removing a property of a closed schema makes data with it invalid.
+        assert_eq!(
+            json_schema(
+                json!({"properties": {"id": {}}, "additionalProperties": false}),
+                json!({"properties": {}, "additionalProperties": false})
+            ),
+            [change(
+                "/properties/id",
+                Direction::Backward,
+                "property removed"
+            )]
+        );
+    }
+
+    #[test]
+    fn json_bounds_tightened_or_loosened() {
+        let amount = |bounds: Value| {
+            let mut amount = json!({"type": "integer"});
+            amount
+                .as_object_mut()
+                .unwrap()
+                .extend(bounds.as_object().unwrap().clone());
+
+            json!({"properties": {"amount": amount}})
+        };
+
+        assert_eq!(
+            json_schema(
+                amount(json!({"minimum": 0, "maximum": 100})),
+                amount(json!({"minimum": 10, "maximum": 50}))
+            ),
+            [
+                change(
+                    "/properties/amount",
+                    Direction::Backward,
+                    "maximum changed from 100 to 50"
+                ),
+                change(
+                    "/properties/amount",
+                    Direction::Backward,
+                    "minimum changed from 0 to 10"
+                ),
+            ]
+        );
+
+        assert_eq!(
+            json_schema(amount(json!({"maximum": 100})), amount(json!({}))),
+            [change(
+                "/properties/amount",
+                Direction::Forward,
+                "maximum changed from 100 to none"
+            )]
+        );
+    }
+}
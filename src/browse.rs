@@ -0,0 +1,520 @@
+use crate::{
+    diff,
+    highlight,
+    registry::{
+        self,
+        Client,
+    },
+};
+
+use gumdrop::Options;
+use log::*;
+use ratatui::{
+    crossterm::event::{
+        self,
+        Event,
+        KeyCode,
+        KeyEvent,
+        KeyEventKind,
+        KeyModifiers,
+    },
+    layout::{
+        Constraint,
+        Layout,
+    },
+    style::{
+        Color,
+        Modifier,
+        Style,
+    },
+    text::{
+        Line,
+        Text,
+    },
+    widgets::{
+        Block,
+        List,
+        ListState,
+        Paragraph,
+    },
+    DefaultTerminal,
+    Frame,
+};
+
+use std::io::{
+    self,
+    IsTerminal,
+};
+
+/// Browse the Kafka Schema Registry interactively: list its subjects (with incremental search),
+/// drill into their versions, and view their schemas and references; diff a version with the one before it,
+/// or (soft-)delete it.
+#[derive(Debug, Options)]
+pub struct BrowseSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    pub schema_registry_url: Vec<String>,
+}
+
+/// Number of lines scrolled by Page Up/Down.
+const PAGE: u16 = 20;
+
+enum Screen {
+    Subjects {
+        filter: String,
+        searching: bool,
+        list: ListState,
+    },
+    Versions {
+        subject: String,
+        versions: Vec<u32>,
+        list: ListState,
+        confirm_delete: bool,
+    },
+    Schema {
+        title: String,
+        lines: Vec<Line<'static>>,
+        references: Vec<String>,
+        scroll: u16,
+    },
+    Diff {
+        title: String,
+        lines: Vec<Line<'static>>,
+        scroll: u16,
+    },
+}
+
+impl Screen {
+    fn subjects() -> Self {
+        Self::Subjects {
+            filter: String::new(),
+            searching: false,
+            list: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    fn help(&self) -> &'static str {
+        match self {
+            Self::Subjects {
+                searching: true, ..
+            } => "type to search, Enter: done, Esc: clear",
+            Self::Subjects { .. } => "/: search, Enter: versions, r: reload, q: quit",
+            Self::Versions { .. } => {
+                "Enter: schema, d: diff with previous, x: delete, Esc: back, q: quit"
+            }
+            Self::Schema { .. } | Self::Diff { .. } => "↑/↓/PgUp/PgDn: scroll, Esc: back, q: quit",
+        }
+    }
+}
+
+/// Moves the selection of a list with the given number of items up or down by the given amount.
+fn select(list: &mut ListState, len: usize, delta: isize) {
+    if len == 0 {
+        list.select(None);
+        return;
+    }
+
+    let current = list.selected().unwrap_or(0) as isize;
+    list.select(Some((current + delta).clamp(0, len as isize - 1) as usize));
+}
+
+fn scroll(scroll: &mut u16, key: KeyCode) {
+    *scroll = match key {
+        KeyCode::Up | KeyCode::Char('k') => scroll.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => scroll.saturating_add(1),
+        KeyCode::PageUp => scroll.saturating_sub(PAGE),
+        KeyCode::PageDown | KeyCode::Char(' ') => scroll.saturating_add(PAGE),
+        KeyCode::Home => 0,
+        _ => *scroll,
+    };
+}
+
+fn diff_line(line: &str) -> Line<'static> {
+    let style = if line.starts_with("+++") || line.starts_with("---") {
+        Style::new().add_modifier(Modifier::BOLD)
+    } else if line.starts_with('+') {
+        Style::new().fg(Color::Green)
+    } else if line.starts_with('-') {
+        Style::new().fg(Color::Red)
+    } else if line.starts_with("@@") {
+        Style::new().fg(Color::Cyan)
+    } else {
+        Style::new()
+    };
+
+    Line::styled(line.replace('\t', "    "), style)
+}
+
+struct Browser {
+    client: Client,
+    subjects: Vec<String>,
+    /// Screens navigated through, the current one last.
+    screens: Vec<Screen>,
+    /// Message (e.g., an error) shown in place of the help line until the next key press.
+    status: Option<String>,
+    quit: bool,
+}
+
+impl Browser {
+    fn load_subjects(client: &Client) -> anyhow::Result<Vec<String>> {
+        let mut subjects = client.list_subjects()?;
+        subjects.sort();
+        Ok(subjects)
+    }
+
+    /// Subjects matching the search filter (case-insensitively).
+    fn matching<'a>(subjects: &'a [String], filter: &str) -> Vec<&'a String> {
+        let filter = filter.to_lowercase();
+        subjects
+            .iter()
+            .filter(|subject| subject.to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    fn open_versions(&self, subject: &str) -> anyhow::Result<Screen> {
+        let versions = self.client.get_versions(subject)?;
+        let selected = versions.len().checked_sub(1);
+        Ok(Screen::Versions {
+            subject: subject.to_owned(),
+            versions,
+            list: ListState::default().with_selected(selected),
+            confirm_delete: false,
+        })
+    }
+
+    fn open_schema(&self, subject: &str, version: u32) -> anyhow::Result<Screen> {
+        let schema = self.client.get_schema(subject, &version.to_string())?;
+        let references = schema
+            .references
+            .iter()
+            .map(|r| format!("{} ({} version {})", r.name, r.subject, r.version))
+            .collect();
+
+        Ok(Screen::Schema {
+            title: format!(
+                "{} version {} (id {}, {})",
+                subject,
+                version,
+                schema.id,
+                registry::schema_type_name(&schema.schema_type())
+            ),
+            lines: highlight::highlight(
+                &schema.schema.replace('\t', "    "),
+                &schema.schema_type(),
+            ),
+            references,
+            scroll: 0,
+        })
+    }
+
+    fn open_diff(
+        &self,
+        subject: &str,
+        from_version: u32,
+        to_version: u32,
+    ) -> anyhow::Result<Screen> {
+        let from = self.client.get_schema(subject, &from_version.to_string())?;
+        let to = self.client.get_schema(subject, &to_version.to_string())?;
+
+        let unified = diff::unified_diff(subject, from_version, &from, to_version, &to);
+        let mut lines: Vec<_> = if unified.is_empty() {
+            vec![Line::raw("schema: unchanged")]
+        } else {
+            unified.lines().map(diff_line).collect()
+        };
+
+        let changes = diff::reference_changes(&from, &to);
+        if changes.is_empty() {
+            lines.push(Line::raw("references: unchanged"));
+        } else {
+            lines.push(Line::raw("references:"));
+            lines.extend(
+                changes
+                    .iter()
+                    .map(|change| diff_line(&format!("\t{}", change))),
+            );
+        }
+
+        Ok(Screen::Diff {
+            title: format!("{} version {} -> {}", subject, from_version, to_version),
+            lines,
+            scroll: 0,
+        })
+    }
+
+    /// Handles a key press on the current screen, returning the screen to navigate to (if any).
+    fn handle_key(&mut self, key: KeyEvent) -> anyhow::Result<Option<Screen>> {
+        let screen = self.screens.last_mut().expect("screen");
+        match screen {
+            Screen::Subjects {
+                filter,
+                searching,
+                list,
+            } => {
+                if *searching {
+                    match key.code {
+                        KeyCode::Char(c) => filter.push(c),
+                        KeyCode::Backspace => {
+                            filter.pop();
+                        }
+                        KeyCode::Esc => {
+                            filter.clear();
+                            *searching = false;
+                        }
+                        KeyCode::Enter => *searching = false,
+                        _ => return Ok(None),
+                    }
+
+                    list.select(Some(0));
+                    return Ok(None);
+                }
+
+                let matching = Self::matching(&self.subjects, filter);
+                match key.code {
+                    KeyCode::Char('/') => *searching = true,
+                    KeyCode::Esc if !filter.is_empty() => {
+                        filter.clear();
+                        list.select(Some(0));
+                    }
+                    KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+                    KeyCode::Up | KeyCode::Char('k') => select(list, matching.len(), -1),
+                    KeyCode::Down | KeyCode::Char('j') => select(list, matching.len(), 1),
+                    KeyCode::PageUp => select(list, matching.len(), -(PAGE as isize)),
+                    KeyCode::PageDown => select(list, matching.len(), PAGE as isize),
+                    KeyCode::Char('r') => {
+                        self.subjects = Self::load_subjects(&self.client)?;
+                        self.status = Some(format!("{} subjects", self.subjects.len()));
+                    }
+                    KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                        if let Some(subject) = list.selected().and_then(|i| matching.get(i)) {
+                            let subject = subject.to_string();
+                            return self.open_versions(&subject).map(Some);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Screen::Versions {
+                subject,
+                versions,
+                list,
+                confirm_delete,
+            } => {
+                let selected = list.selected().and_then(|i| versions.get(i).copied());
+                if *confirm_delete {
+                    *confirm_delete = false;
+                    if let (KeyCode::Char('y'), Some(version)) = (key.code, selected) {
+                        let subject = subject.clone();
+                        self.client.delete_version(&subject, version)?;
+                        info!("deleted {} version {}", subject, version);
+
+                        let versions = self.open_versions(&subject)?;
+                        *self.screens.last_mut().expect("screen") = versions;
+                        self.status = Some(format!("deleted {} version {}", subject, version));
+                    }
+
+                    return Ok(None);
+                }
+
+                match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => select(list, versions.len(), -1),
+                    KeyCode::Down | KeyCode::Char('j') => select(list, versions.len(), 1),
+                    KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                        if let Some(version) = selected {
+                            let subject = subject.clone();
+                            return self.open_schema(&subject, version).map(Some);
+                        }
+                    }
+                    KeyCode::Char('d') => {
+                        let index = list.selected().unwrap_or(0);
+                        match (index.checked_sub(1).map(|i| versions[i]), selected) {
+                            (Some(from), Some(to)) => {
+                                let subject = subject.clone();
+                                return self.open_diff(&subject, from, to).map(Some);
+                            }
+                            _ => self.status = Some("no previous version to diff with".to_owned()),
+                        }
+                    }
+                    KeyCode::Char('x') | KeyCode::Delete => {
+                        if let Some(version) = selected {
+                            *confirm_delete = true;
+                            self.status = Some(format!(
+                                "delete {} version {}? (y: yes, any other key: no)",
+                                subject, version
+                            ));
+                        }
+                    }
+                    _ => self.navigate_back(key.code),
+                }
+            }
+            Screen::Schema { scroll: pos, .. } | Screen::Diff { scroll: pos, .. } => {
+                match key.code {
+                    KeyCode::Up
+                    | KeyCode::Down
+                    | KeyCode::Char('k')
+                    | KeyCode::Char('j')
+                    | KeyCode::PageUp
+                    | KeyCode::PageDown
+                    | KeyCode::Char(' ')
+                    | KeyCode::Home => scroll(pos, key.code),
+                    _ => self.navigate_back(key.code),
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Goes back to the previous screen, or quits, as the key demands.
+    fn navigate_back(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Esc | KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => {
+                self.screens.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, footer] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+        let highlight = Style::new().add_modifier(Modifier::REVERSED);
+        let screen = self.screens.last_mut().expect("screen");
+        let help = screen.help();
+        match screen {
+            Screen::Subjects {
+                filter,
+                searching,
+                list,
+            } => {
+                let matching = Self::matching(&self.subjects, filter);
+                let title = if *searching || !filter.is_empty() {
+                    format!(
+                        " subjects ({} of {}) /{} ",
+                        matching.len(),
+                        self.subjects.len(),
+                        filter
+                    )
+                } else {
+                    format!(" subjects ({}) ", self.subjects.len())
+                };
+
+                let items = matching.into_iter().map(String::as_str);
+                let widget = List::new(items)
+                    .block(Block::bordered().title(title))
+                    .highlight_style(highlight);
+
+                frame.render_stateful_widget(widget, main, list);
+            }
+            Screen::Versions {
+                subject,
+                versions,
+                list,
+                ..
+            } => {
+                let items = versions.iter().map(|v| format!("version {}", v));
+                let widget = List::new(items)
+                    .block(Block::bordered().title(format!(" {} ", subject)))
+                    .highlight_style(highlight);
+
+                frame.render_stateful_widget(widget, main, list);
+            }
+            Screen::Schema {
+                title,
+                lines,
+                references,
+                scroll,
+            } => {
+                let [text, refs] = Layout::horizontal([
+                    Constraint::Min(1),
+                    Constraint::Length(if references.is_empty() { 0 } else { 48 }),
+                ])
+                .areas(main);
+
+                let widget = Paragraph::new(Text::from(lines.clone()))
+                    .block(Block::bordered().title(format!(" {} ", title)))
+                    .scroll((*scroll, 0));
+
+                frame.render_widget(widget, text);
+                if !references.is_empty() {
+                    let widget = List::new(references.iter().map(String::as_str))
+                        .block(Block::bordered().title(" references "));
+
+                    frame.render_widget(widget, refs);
+                }
+            }
+            Screen::Diff {
+                title,
+                lines,
+                scroll,
+            } => {
+                let widget = Paragraph::new(Text::from(lines.clone()))
+                    .block(Block::bordered().title(format!(" {} ", title)))
+                    .scroll((*scroll, 0));
+
+                frame.render_widget(widget, main);
+            }
+        }
+
+        let footer_text = match &self.status {
+            Some(status) => Line::styled(status.clone(), Style::new().fg(Color::Yellow)),
+            None => Line::styled(help, Style::new().fg(Color::DarkGray)),
+        };
+
+        frame.render_widget(Paragraph::new(footer_text), footer);
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+        while !self.quit && !self.screens.is_empty() {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let key = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                break;
+            }
+
+            self.status = None;
+            match self.handle_key(key) {
+                Ok(Some(screen)) => self.screens.push(screen),
+                Ok(None) => {}
+                Err(e) => {
+                    debug!("{:?}", e);
+                    self.status = Some(format!("error: {}", e));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn run_browse(client: Client, _settings: &BrowseSettings) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        io::stdin().is_terminal() && io::stdout().is_terminal(),
+        "browsing requires a terminal"
+    );
+
+    let subjects = Browser::load_subjects(&client)?;
+    let mut browser = Browser {
+        client,
+        subjects,
+        screens: vec![Screen::subjects()],
+        status: None,
+        quit: false,
+    };
+
+    let mut terminal = ratatui::init();
+    let res = browser.run(&mut terminal);
+    ratatui::restore();
+
+    res
+}
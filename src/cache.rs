@@ -0,0 +1,148 @@
+use log::*;
+
+use sha2::{
+    Digest,
+    Sha256,
+};
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::PathBuf,
+    sync::Mutex,
+    time::{
+        Duration,
+        Instant,
+        SystemTime,
+    },
+};
+
+/// How long cached responses remain valid unless configured otherwise.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Cache of Schema Registry responses, kept in memory and, optionally, in a directory so that they carry over
+/// from one run to the next. Entries expire after the time to live, since even immutable resources
+/// (e.g., schema versions) may be deleted.
+#[derive(Debug)]
+pub struct Cache {
+    /// Response text by key, along with when it was retrieved.
+    entries: Mutex<HashMap<String, (Instant, String)>>,
+    dir: Option<PathBuf>,
+    ttl: Duration,
+}
+
+impl Cache {
+    pub fn new(dir: Option<PathBuf>, ttl: Duration) -> anyhow::Result<Self> {
+        if let Some(dir) = &dir {
+            fs::create_dir_all(dir).map_err(|e| {
+                anyhow::format_err!("failed to create cache directory {}: {}", dir.display(), e)
+            })?;
+        }
+
+        Ok(Self {
+            entries: Mutex::new(HashMap::new()),
+            dir,
+            ttl,
+        })
+    }
+
+    /// File that caches the entry with the given key, named after the key's digest.
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        let digest: String = Sha256::digest(key.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", digest)))
+    }
+
+    /// Looks up an entry that hasn't expired yet, in memory first and then on disk.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let mut entries = self.entries.lock().expect("cache lock");
+        if let Some((retrieved, text)) = entries.get(key) {
+            if retrieved.elapsed() < self.ttl {
+                return Some(text.clone());
+            }
+
+            entries.remove(key);
+        }
+
+        let path = self.path(key)?;
+        let age = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+
+        if age >= self.ttl {
+            return None;
+        }
+
+        let text = fs::read_to_string(&path)
+            .map_err(|e| debug!("error reading {}: {}", path.display(), e))
+            .ok()?;
+
+        let retrieved = Instant::now().checked_sub(age).unwrap_or_else(Instant::now);
+        entries.insert(key.to_owned(), (retrieved, text.clone()));
+        Some(text)
+    }
+
+    /// Adds an entry, writing it to disk too if so configured (failing which is only worth a warning).
+    pub fn put(&self, key: &str, text: &str) {
+        self.entries
+            .lock()
+            .expect("cache lock")
+            .insert(key.to_owned(), (Instant::now(), text.to_owned()));
+
+        let path = match self.path(key) {
+            Some(path) => path,
+            None => return,
+        };
+
+        // Write to a temporary file first, so that concurrent runs never read a partial entry.
+        let dir = path.parent().expect("file has a parent directory");
+        let res = tempfile::NamedTempFile::new_in(dir).and_then(|mut file| {
+            file.write_all(text.as_bytes())?;
+            file.persist(&path).map_err(|e| e.error)?;
+            Ok(())
+        });
+
+        if let Err(e) = res {
+            warn!("error caching response in {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_entries_for_the_ttl() {
+        let cache = Cache::new(None, DEFAULT_TTL).unwrap();
+        assert_eq!(cache.get("a"), None);
+
+        cache.put("a", "text");
+        assert_eq!(cache.get("a").as_deref(), Some("text"));
+        assert_eq!(cache.get("b"), None);
+
+        let cache = Cache::new(None, Duration::ZERO).unwrap();
+        cache.put("a", "text");
+        assert_eq!(cache.get("a"), None);
+    }
+
+    #[test]
+    fn carries_entries_over_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(dir.path().join("cache")), DEFAULT_TTL).unwrap();
+        cache.put("a", "text");
+
+        let cache = Cache::new(Some(dir.path().join("cache")), DEFAULT_TTL).unwrap();
+        assert_eq!(cache.get("a").as_deref(), Some("text"));
+
+        let cache = Cache::new(Some(dir.path().join("cache")), Duration::ZERO).unwrap();
+        assert_eq!(cache.get("a"), None);
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{
+    exit::{
+        self,
+        Failure,
+    },
+    registry::{
+        Client,
+        CompatibilityCheck,
+    },
+    SchemaSource,
+};
+
+use gumdrop::Options;
+use schema_registry_converter::schema_registry_common::get_subject;
+
+schema_file_settings! {
+    /// Verify a schema against the Kafka Schema Registry without changing anything.
+    /// Takes the same inputs as `post' and checks that the schema is compatible with
+    /// the latest registered version of the subject, and optionally that it is already registered.
+    /// Exits with 2 if the schema is not registered, 3 if it is incompatible,
+    /// and 5 if the registry is unreachable.
+    #[derive(Debug, Options)]
+    pub struct CiCheckSettings {
+        file: "schema file (required; `-' to read from standard input)",
+
+        /// also require the schema to be registered already
+        #[options(no_short)]
+        require_registered: bool,
+    }
+}
+
+pub fn run_ci_check(client: Client, settings: &CiCheckSettings) -> anyhow::Result<()> {
+    let schema = crate::supplied_schema(&SchemaSource::from(settings))?;
+    let sns = crate::subject_name_strategy_from_settings(
+        settings.topic.clone(),
+        settings.record.clone(),
+        settings.topic_key,
+    )?;
+
+    let subject =
+        get_subject(&sns).map_err(|e| anyhow::format_err!("error determining subject: {:?}", e))?;
+
+    match client.check_compatibility(&subject, &schema)? {
+        CompatibilityCheck::Compatible => {
+            outln!("compatible with the latest version of {}", subject)
+        }
+        CompatibilityCheck::Incompatible(messages) => {
+            let mut msg = format!("incompatible with the latest version of {}", subject);
+            for message in messages {
+                msg.push_str("\n\t");
+                msg.push_str(&message);
+            }
+
+            return Err(Failure::new(exit::INCOMPATIBLE, msg).into());
+        }
+        CompatibilityCheck::MissingReferences => {
+            let msg = format!("not all references of {} are registered", subject);
+            return Err(Failure::new(exit::NOT_FOUND, msg).into());
+        }
+    }
+
+    if settings.require_registered {
+        match client.lookup(&subject, &schema)? {
+            Some(registered) => outln!(
+                "registered as version {} of {} (id {})",
+                registered.version.unwrap_or_default(),
+                subject,
+                registered.id
+            ),
+            None => {
+                let msg = format!("not registered under {}", subject);
+                return Err(Failure::new(exit::NOT_FOUND, msg).into());
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use crate::{
+    exit::{
+        self,
+        Failure,
+    },
+    registry::{
+        Client,
+        Schema,
+    },
+};
+
+use gumdrop::Options;
+use log::*;
+use regex::Regex;
+use sha2::{
+    Digest,
+    Sha256,
+};
+
+use std::collections::BTreeSet;
+
+/// Compare the subjects of two Kafka Schema Registries (e.g., a primary and its mirror).
+/// Reports subjects that are missing from either registry, have a different number of versions,
+/// or whose latest schemas differ, and exits 1 if there are any.
+#[derive(Debug, Options)]
+pub struct CompareSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// only compare subjects matching this regular expression
+    #[options(meta = "REGEX")]
+    filter: Option<Regex>,
+
+    /// URLs (or configured profile names) of the two Schema Registries to compare (required)
+    #[options(free, required)]
+    schema_registry_url: Vec<String>,
+}
+
+struct SubjectInfo {
+    versions: usize,
+    fingerprint: String,
+}
+
+/// Computes a fingerprint of the schema's type, text, and references.
+fn fingerprint(schema: &Schema) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(schema.schema_type.as_deref().unwrap_or("AVRO"));
+    hasher.update([0]);
+    hasher.update(&schema.schema);
+    for reference in &schema.references {
+        hasher.update([0]);
+        hasher.update(format!(
+            "{}:{}:{}",
+            reference.name, reference.subject, reference.version
+        ));
+    }
+
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn subjects(client: &Client, filter: Option<&Regex>) -> anyhow::Result<BTreeSet<String>> {
+    let subjects = client
+        .list_subjects()?
+        .into_iter()
+        .filter(|subject| filter.is_none_or(|filter| filter.is_match(subject)))
+        .collect();
+
+    Ok(subjects)
+}
+
+fn subject_info(client: &Client, subject: &str) -> anyhow::Result<SubjectInfo> {
+    let versions = client.get_versions(subject)?.len();
+    let latest = client.get_schema(subject, "latest")?;
+
+    Ok(SubjectInfo {
+        versions,
+        fingerprint: fingerprint(&latest),
+    })
+}
+
+impl CompareSettings {
+    /// The URLs of the two Schema Registries to compare.
+    pub fn urls(&self) -> anyhow::Result<(&str, &str)> {
+        match self.schema_registry_url.as_slice() {
+            [a, b] => Ok((a, b)),
+            _ => anyhow::bail!("exactly two Schema Registry URLs are required"),
+        }
+    }
+}
+
+pub fn run_compare(
+    client_a: Client,
+    client_b: Client,
+    settings: &CompareSettings,
+) -> anyhow::Result<()> {
+    let (url_a, url_b) = settings.urls()?;
+
+    let subjects_a = subjects(&client_a, settings.filter.as_ref())?;
+    let subjects_b = subjects(&client_b, settings.filter.as_ref())?;
+
+    let mut differences = 0;
+    let all: BTreeSet<_> = subjects_a.union(&subjects_b).collect();
+    for subject in &all {
+        if !subjects_b.contains(*subject) {
+            outln!("{}: missing in {}", subject, url_b);
+            differences += 1;
+            continue;
+        }
+
+        if !subjects_a.contains(*subject) {
+            outln!("{}: missing in {}", subject, url_a);
+            differences += 1;
+            continue;
+        }
+
+        let info_a = subject_info(&client_a, subject)?;
+        let info_b = subject_info(&client_b, subject)?;
+
+        debug!(
+            "{}: {} vs. {}",
+            subject, info_a.fingerprint, info_b.fingerprint
+        );
+
+        let mut differs = false;
+        if info_a.versions != info_b.versions {
+            outln!(
+                "{}: {} version(s) in {}, {} in {}",
+                subject,
+                info_a.versions,
+                url_a,
+                info_b.versions,
+                url_b
+            );
+            differs = true;
+        }
+
+        if info_a.fingerprint != info_b.fingerprint {
+            outln!(
+                "{}: latest schema differs ({} in {}, {} in {})",
+                subject,
+                &info_a.fingerprint[..12],
+                url_a,
+                &info_b.fingerprint[..12],
+                url_b
+            );
+            differs = true;
+        }
+
+        if differs {
+            differences += 1;
+        }
+    }
+
+    outln!("{} subject(s) compared, {} differ", all.len(), differences);
+
+    if differences > 0 {
+        let msg = format!("{} subject(s) differ between the registries", differences);
+        return Err(Failure::new(exit::FAILURE, msg).into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use crate::registry::Flavor;
+
+use serde::Deserialize;
+
+use std::{
+    collections::BTreeMap,
+    env,
+    fmt,
+    fs,
+    io,
+    path::PathBuf,
+};
+
+/// Environment variable that overrides the location of the configuration file.
+const CONFIG_VAR: &str = "KSRT_CONFIG";
+
+/// User configuration, read from `$KSRT_CONFIG' or `~/.config/ksrt/config.yaml'.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Named Schema Registry endpoint (e.g., a Confluent Cloud cluster) along with its credentials.
+/// The profile name can be given wherever a Schema Registry URL is expected.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    pub url: String,
+    pub api_key: Option<String>,
+    pub api_secret: Option<String>,
+    pub registry_flavor: Option<Flavor>,
+    pub apicurio_group: Option<String>,
+    /// HTTP headers to send with every request, by name
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl fmt::Debug for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Profile")
+            .field("url", &self.url)
+            .field("api_key", &self.api_key)
+            .field("registry_flavor", &self.registry_flavor)
+            .field("apicurio_group", &self.apicurio_group)
+            .field("headers", &self.headers.keys().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Config {
+    fn path() -> Option<PathBuf> {
+        match env::var_os(CONFIG_VAR) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => {
+                env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/ksrt/config.yaml"))
+            }
+        }
+    }
+
+    /// Loads the configuration file; a missing file amounts to an empty configuration.
+    pub fn load() -> anyhow::Result<Self> {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Ok(Self::default()),
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => anyhow::bail!("failed to read {}: {}", path.display(), e),
+        };
+
+        serde_yaml::from_str(&text)
+            .map_err(|e| anyhow::format_err!("invalid configuration {}: {}", path.display(), e))
+    }
+}
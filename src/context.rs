@@ -0,0 +1,58 @@
+use crate::registry::Client;
+
+use gumdrop::Options;
+
+/// Work with the schema contexts of the Kafka Schema Registry.
+/// Use the global `--context' option to operate on the subjects of a particular context.
+#[derive(Debug, Options)]
+pub struct ContextSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// command
+    #[options(command, required)]
+    command: Option<ContextCmd>,
+}
+
+#[derive(Debug, Options)]
+enum ContextCmd {
+    /// list all schema contexts
+    List(ListSettings),
+}
+
+/// List all schema contexts.
+#[derive(Debug, Options)]
+struct ListSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    schema_registry_url: Vec<String>,
+}
+
+impl ContextSettings {
+    /// The Schema Registry URL(s) given to the subcommand.
+    pub fn schema_registry_url(&self) -> &[String] {
+        match self.command.as_ref().expect("command") {
+            ContextCmd::List(settings) => &settings.schema_registry_url,
+        }
+    }
+}
+
+fn run_list(client: Client) -> anyhow::Result<()> {
+    let mut contexts = client.list_contexts()?;
+    contexts.sort();
+
+    for context in contexts {
+        outln!("{}", context);
+    }
+
+    Ok(())
+}
+
+pub fn run_context(client: Client, settings: ContextSettings) -> anyhow::Result<()> {
+    match settings.command.expect("command") {
+        ContextCmd::List(_) => run_list(client),
+    }
+}
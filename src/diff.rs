@@ -0,0 +1,324 @@
+use crate::{
+    exit::{
+        self,
+        Failure,
+    },
+    highlight,
+    registry::{
+        Client,
+        Schema,
+    },
+};
+
+use gumdrop::Options;
+use log::*;
+use schema_registry_converter::schema_registry_common::{
+    get_subject,
+    SchemaType,
+};
+use similar::TextDiff;
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    str::FromStr,
+};
+
+/// Show the differences between two registered versions of a subject's schema:
+/// a unified diff of the schema text followed by a summary of reference changes.
+/// The diff is colorized when printed to a terminal (see the global `--color' option).
+#[derive(Debug, Options)]
+pub struct DiffSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// topic name (required unless `--record' is specified)
+    #[options(meta = "NAME")]
+    topic: Option<String>,
+
+    /// whether the subject is for the topic key (vs. value; only with `--topic' alone)
+    #[options(short = "k")]
+    topic_key: bool,
+
+    /// record name (required unless `--topic' is specified)
+    #[options(meta = "NAME")]
+    record: Option<String>,
+
+    /// version to diff from (a number, `latest', or `latest~N' for N versions before the latest)
+    #[options(no_short, meta = "VERSION", default = "latest~1")]
+    from_version: VersionSpec,
+
+    /// version to diff to (same forms as `--from-version')
+    #[options(no_short, meta = "VERSION", default = "latest")]
+    to_version: VersionSpec,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    pub schema_registry_url: Vec<String>,
+}
+
+/// Version of a subject, either absolute or relative to the latest one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VersionSpec {
+    Number(u32),
+    /// The given number of versions before the latest.
+    Latest(usize),
+}
+
+impl fmt::Display for VersionSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(version) => write!(f, "{}", version),
+            Self::Latest(0) => write!(f, "latest"),
+            Self::Latest(back) => write!(f, "latest~{}", back),
+        }
+    }
+}
+
+impl FromStr for VersionSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let spec = match s.strip_prefix("latest") {
+            Some("") => Self::Latest(0),
+            Some(back) => Self::Latest(
+                back.strip_prefix('~')
+                    .and_then(|back| back.parse().ok())
+                    .ok_or_else(|| anyhow::format_err!("invalid version: {}", s))?,
+            ),
+            None => Self::Number(
+                s.parse()
+                    .map_err(|_| anyhow::format_err!("invalid version: {}", s))?,
+            ),
+        };
+
+        Ok(spec)
+    }
+}
+
+impl VersionSpec {
+    /// Resolves the version number among the given (ascending) versions of a subject.
+    pub fn resolve(self, versions: &[u32]) -> anyhow::Result<u32> {
+        match self {
+            Self::Number(version) => Ok(version),
+            Self::Latest(back) => back
+                .checked_add(1)
+                .and_then(|n| versions.len().checked_sub(n))
+                .map(|i| versions[i])
+                .ok_or_else(|| {
+                    let msg = format!("{}: there are only {} version(s)", self, versions.len());
+                    Failure::new(exit::NOT_FOUND, msg).into()
+                }),
+        }
+    }
+}
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Prints a line of a unified diff; when colorizing, unchanged lines are highlighted according to the schema type.
+fn print_line(line: &str, color: bool, schema_type: &SchemaType, in_comment: &mut bool) {
+    let style = if !color {
+        None
+    } else if line.starts_with("+++") || line.starts_with("---") {
+        Some(BOLD)
+    } else if line.starts_with('+') {
+        Some(GREEN)
+    } else if line.starts_with('-') {
+        Some(RED)
+    } else if line.starts_with("@@") {
+        Some(CYAN)
+    } else {
+        None
+    };
+
+    match (style, line.strip_prefix(' ')) {
+        (Some(style), _) => outln!("{}{}{}", style, line, RESET),
+        (None, Some(context)) if color => {
+            let context = highlight::highlight_line(context, schema_type, in_comment);
+            outln!(" {}", highlight::ansi(&context))
+        }
+        _ => outln!("{}", line),
+    }
+}
+
+/// Lists the references that were added (`+'), removed (`-'), or changed (`~') between the two versions,
+/// ordered by name.
+pub fn reference_changes(from: &Schema, to: &Schema) -> Vec<String> {
+    let from_refs: BTreeMap<_, _> = from.references.iter().map(|r| (&r.name, r)).collect();
+    let to_refs: BTreeMap<_, _> = to.references.iter().map(|r| (&r.name, r)).collect();
+
+    let mut changes = Vec::new();
+    for (name, from_ref) in &from_refs {
+        match to_refs.get(name) {
+            None => changes.push(format!(
+                "- {} ({} version {})",
+                name, from_ref.subject, from_ref.version
+            )),
+            Some(to_ref)
+                if to_ref.subject != from_ref.subject || to_ref.version != from_ref.version =>
+            {
+                changes.push(format!(
+                    "~ {} ({} version {} -> {} version {})",
+                    name, from_ref.subject, from_ref.version, to_ref.subject, to_ref.version
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+
+    for (name, to_ref) in &to_refs {
+        if !from_refs.contains_key(name) {
+            changes.push(format!(
+                "+ {} ({} version {})",
+                name, to_ref.subject, to_ref.version
+            ));
+        }
+    }
+
+    changes.sort_by(|a, b| a[2..].cmp(&b[2..]));
+    changes
+}
+
+/// Prints the references that were added, removed, or changed between the two versions.
+fn print_reference_changes(from: &Schema, to: &Schema, color: bool) {
+    let changes = reference_changes(from, to);
+    if changes.is_empty() {
+        outln!("references: unchanged");
+        return;
+    }
+
+    outln!("references:");
+    for change in changes {
+        let line = format!("\t{}", change);
+        match change.chars().next() {
+            Some('+') if color => outln!("{}{}{}", GREEN, line, RESET),
+            Some('-') if color => outln!("{}{}{}", RED, line, RESET),
+            _ => outln!("{}", line),
+        }
+    }
+}
+
+/// Produces a unified diff of the schema text of two versions of a subject (empty if unchanged).
+/// JSON (including Avro) schemas are pretty-printed first, so that the diff shows the lines that changed.
+pub fn unified_diff(
+    subject: &str,
+    from_version: u32,
+    from: &Schema,
+    to_version: u32,
+    to: &Schema,
+) -> String {
+    let from_text = highlight::pretty(&from.schema, &from.schema_type());
+    let to_text = highlight::pretty(&to.schema, &to.schema_type());
+
+    TextDiff::from_lines(&from_text, &to_text)
+        .unified_diff()
+        .header(
+            &format!("{} version {} (id {})", subject, from_version, from.id),
+            &format!("{} version {} (id {})", subject, to_version, to.id),
+        )
+        .to_string()
+}
+
+/// Prints the differences between two versions of a subject: a unified diff of the schema text
+/// (colorized as per `--color') followed by the reference changes.
+pub fn print_diff(subject: &str, from_version: u32, from: &Schema, to_version: u32, to: &Schema) {
+    let color = highlight::enabled();
+    let unified = unified_diff(subject, from_version, from, to_version, to);
+
+    if unified.is_empty() {
+        outln!("schema: unchanged");
+    } else {
+        let schema_type = to.schema_type();
+        let mut in_comment = false;
+        for line in unified.lines() {
+            print_line(line, color, &schema_type, &mut in_comment);
+        }
+    }
+
+    print_reference_changes(from, to, color);
+}
+
+pub fn run_diff(client: Client, settings: &DiffSettings) -> anyhow::Result<()> {
+    let sns = crate::subject_name_strategy_from_settings(
+        settings.topic.clone(),
+        settings.record.clone(),
+        settings.topic_key,
+    )?;
+
+    let subject =
+        get_subject(&sns).map_err(|e| anyhow::format_err!("error determining subject: {:?}", e))?;
+
+    let versions = client.get_versions(&subject)?;
+    if versions.is_empty() {
+        let msg = format!("subject not found: {}", subject);
+        return Err(Failure::new(exit::NOT_FOUND, msg).into());
+    }
+
+    let from_version = settings.from_version.resolve(&versions)?;
+    let to_version = settings.to_version.resolve(&versions)?;
+
+    debug!(
+        "diffing {} version {} to {}",
+        subject, from_version, to_version
+    );
+
+    let from = client
+        .get_schema(&subject, &from_version.to_string())
+        .map_err(|e| e.context(format!("error retrieving version {}", from_version)))?;
+
+    let to = client
+        .get_schema(&subject, &to_version.to_string())
+        .map_err(|e| e.context(format!("error retrieving version {}", to_version)))?;
+
+    print_diff(&subject, from_version, &from, to_version, &to);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_version_specs() {
+        assert_eq!("3".parse::<VersionSpec>().unwrap(), VersionSpec::Number(3));
+        assert_eq!(
+            "latest".parse::<VersionSpec>().unwrap(),
+            VersionSpec::Latest(0)
+        );
+        assert_eq!(
+            "latest~2".parse::<VersionSpec>().unwrap(),
+            VersionSpec::Latest(2)
+        );
+
+        for invalid in ["", "-1", "latest~", "latest2", "latest~x", "v3"] {
+            assert!(invalid.parse::<VersionSpec>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn resolves_version_specs() {
+        let versions = [1, 2, 5];
+        assert_eq!(VersionSpec::Number(4).resolve(&versions).unwrap(), 4);
+        assert_eq!(VersionSpec::Latest(0).resolve(&versions).unwrap(), 5);
+        assert_eq!(VersionSpec::Latest(2).resolve(&versions).unwrap(), 1);
+
+        let err = VersionSpec::Latest(3).resolve(&versions).unwrap_err();
+        assert_eq!(exit::code(&err), exit::NOT_FOUND);
+
+        let spec: VersionSpec = "latest~18446744073709551615".parse().unwrap();
+        let err = spec.resolve(&versions).unwrap_err();
+        assert_eq!(exit::code(&err), exit::NOT_FOUND);
+    }
+
+    #[test]
+    fn displays_version_specs_as_parsed() {
+        for spec in ["7", "latest", "latest~1"] {
+            assert_eq!(spec.parse::<VersionSpec>().unwrap().to_string(), spec);
+        }
+    }
+}
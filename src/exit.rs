@@ -0,0 +1,171 @@
+use crate::{
+    registry::{
+        self,
+        Client,
+    },
+    telemetry::LogFormat,
+};
+
+use serde_json::json;
+
+use std::{
+    error,
+    fmt,
+};
+
+/// Exit code of any failure not covered by a more specific one.
+pub const FAILURE: i32 = 1;
+
+/// Exit code when a subject, schema, or version is not found (e.g., not registered).
+pub const NOT_FOUND: i32 = 2;
+
+/// Exit code when a schema is not compatible with the registered version(s).
+pub const INCOMPATIBLE: i32 = 3;
+
+/// Exit code when authentication with the Schema Registry (or the OAuth token endpoint) fails.
+pub const AUTH: i32 = 4;
+
+/// Exit code when none of the Schema Registry URLs could be reached.
+pub const UNREACHABLE: i32 = 5;
+
+/// Exit code when the Schema Registry rejects a schema (or another part of a request) as invalid.
+pub const INVALID: i32 = 6;
+
+/// Error that determines the exit code of the program.
+#[derive(Debug)]
+pub struct Failure {
+    pub code: i32,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl error::Error for Failure {}
+
+/// Maps the error the program failed with to its exit code.
+pub fn code(err: &anyhow::Error) -> i32 {
+    if let Some(failure) = err.downcast_ref::<Failure>() {
+        return failure.code;
+    }
+
+    if Client::is_unreachable(err) {
+        return UNREACHABLE;
+    }
+
+    match err.downcast_ref::<registry::Error>() {
+        Some(e) => match (e.error_code, e.status) {
+            (Some(registry::INCOMPATIBLE_SCHEMA), _) | (_, 409) => INCOMPATIBLE,
+            (Some(40400..=40499), _) | (_, 404) => NOT_FOUND,
+            (Some(42200..=42299), _) | (_, 422) => INVALID,
+            (_, 401 | 403) => AUTH,
+            _ => FAILURE,
+        },
+        None => FAILURE,
+    }
+}
+
+/// Prints the error the program failed with to standard error, along with a hint if there is one:
+/// as text, or with the JSON log format, as a JSON object (with the exit code and the registry's error code, if any).
+pub fn report(err: &anyhow::Error, format: LogFormat) {
+    let registry_error = err.downcast_ref::<registry::Error>();
+    let hint = registry_error.and_then(registry::Error::hint);
+
+    match format {
+        LogFormat::Text => {
+            eprintln!("Error: {:?}", err);
+            if let Some(hint) = hint {
+                eprintln!("hint: {}", hint);
+            }
+        }
+        LogFormat::Json => {
+            let report = json!({
+                "level": "ERROR",
+                "message": format!("{:#}", err),
+                "exitCode": code(err),
+                "status": registry_error.map(|e| e.status),
+                "errorCode": registry_error.and_then(|e| e.error_code),
+                "hint": hint,
+            });
+
+            eprintln!("{}", report);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry_error(status: u16, error_code: Option<i32>) -> registry::Error {
+        registry::Error {
+            status,
+            error_code,
+            message: "mock".to_owned(),
+        }
+    }
+
+    #[test]
+    fn maps_registry_errors_to_exit_codes() {
+        let cases = [
+            (409, Some(registry::INCOMPATIBLE_SCHEMA), INCOMPATIBLE),
+            (409, None, INCOMPATIBLE),
+            (404, Some(registry::SUBJECT_NOT_FOUND), NOT_FOUND),
+            (404, Some(registry::SUBJECT_MODE_NOT_CONFIGURED), NOT_FOUND),
+            (404, None, NOT_FOUND),
+            (422, Some(registry::INVALID_SCHEMA), INVALID),
+            (422, None, INVALID),
+            (401, None, AUTH),
+            (403, Some(40301), AUTH),
+            (500, Some(50001), FAILURE),
+        ];
+
+        for (status, error_code, expected) in cases {
+            let err = anyhow::Error::new(registry_error(status, error_code));
+            assert_eq!(code(&err), expected, "{} {:?}", status, error_code);
+        }
+    }
+
+    #[test]
+    fn keeps_exit_codes_through_context() {
+        let err = anyhow::Error::new(registry_error(409, Some(registry::INCOMPATIBLE_SCHEMA)))
+            .context("error posting schema to orders-value");
+        assert_eq!(code(&err), INCOMPATIBLE);
+
+        let err = anyhow::Error::new(Failure::new(NOT_FOUND, "subject not found: orders-value"));
+        assert_eq!(code(&err), NOT_FOUND);
+
+        assert_eq!(code(&anyhow::format_err!("anything else")), FAILURE);
+    }
+
+    #[test]
+    fn hints_at_registry_errors() {
+        let hint = |status, error_code| registry_error(status, error_code).hint();
+
+        assert!(hint(404, Some(registry::SUBJECT_NOT_FOUND))
+            .unwrap()
+            .contains("ksrt list"));
+        assert!(hint(409, None).unwrap().contains("ksrt breaking"));
+        assert!(hint(422, Some(registry::OPERATION_NOT_PERMITTED))
+            .unwrap()
+            .contains("IMPORT mode"));
+        assert!(hint(422, Some(registry::REFERENCE_EXISTS))
+            .unwrap()
+            .contains("ksrt refs-to"));
+        assert!(hint(401, None).unwrap().contains("credentials"));
+        assert!(hint(503, None).unwrap().contains("try again"));
+        assert_eq!(hint(400, None), None);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::registry::{
+    Client,
+    Exporter,
+};
+
+use gumdrop::Options;
+use serde_json::json;
+
+use std::str::FromStr;
+
+/// Manage the schema exporters of the Kafka Schema Registry, which copy subjects to another registry
+/// (i.e., schema linking).
+#[derive(Debug, Options)]
+pub struct ExporterSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// command
+    #[options(command, required)]
+    command: Option<ExporterCmd>,
+}
+
+#[derive(Debug, Options)]
+enum ExporterCmd {
+    /// list all schema exporters
+    List(ListSettings),
+
+    /// show the configuration and state of a schema exporter
+    Get(GetSettings),
+
+    /// create a schema exporter
+    Create(CreateSettings),
+
+    /// pause a schema exporter
+    Pause(NameSettings),
+
+    /// resume a paused schema exporter
+    Resume(NameSettings),
+
+    /// reset the offset of a schema exporter, so that it exports all subjects again
+    Reset(NameSettings),
+}
+
+/// List all schema exporters.
+#[derive(Debug, Options)]
+struct ListSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// print as JSON
+    #[options(no_short)]
+    json: bool,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    schema_registry_url: Vec<String>,
+}
+
+/// Show the configuration and state of a schema exporter.
+#[derive(Debug, Options)]
+struct GetSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// exporter name (required)
+    #[options(required, meta = "NAME")]
+    name: String,
+
+    /// print as JSON
+    #[options(no_short)]
+    json: bool,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    schema_registry_url: Vec<String>,
+}
+
+/// Pause, resume, or reset a schema exporter.
+#[derive(Debug, Options)]
+struct NameSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// exporter name (required)
+    #[options(required, meta = "NAME")]
+    name: String,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    schema_registry_url: Vec<String>,
+}
+
+/// Create a schema exporter, which starts exporting right away.
+#[derive(Debug, Options)]
+struct CreateSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// exporter name (required)
+    #[options(required, meta = "NAME")]
+    name: String,
+
+    /// subject to export, or `*' for all (could be multiple; all by default)
+    #[options(meta = "NAME")]
+    subject: Vec<String>,
+
+    /// how to determine the destination context (one of `AUTO', `CUSTOM', or `NONE')
+    #[options(no_short, meta = "TYPE")]
+    context_type: Option<String>,
+
+    /// destination context (with `--context-type CUSTOM')
+    #[options(no_short, meta = "NAME")]
+    context: Option<String>,
+
+    /// format of the exported subjects' names, in which `${subject}' stands for the original one
+    #[options(no_short, meta = "FORMAT")]
+    subject_rename_format: Option<String>,
+
+    /// destination registry setting (e.g., `schema.registry.url=https://...'; could be multiple)
+    #[options(no_short, meta = "KEY=VALUE")]
+    config: Vec<Property>,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    schema_registry_url: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Property {
+    key: String,
+    value: String,
+}
+
+impl FromStr for Property {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow::format_err!("expected KEY=VALUE: {}", s))?;
+
+        anyhow::ensure!(!key.is_empty(), "missing key: {}", s);
+
+        Ok(Self {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        })
+    }
+}
+
+impl ExporterSettings {
+    /// The Schema Registry URL(s) given to the subcommand.
+    pub fn schema_registry_url(&self) -> &[String] {
+        match self.command.as_ref().expect("command") {
+            ExporterCmd::List(settings) => &settings.schema_registry_url,
+            ExporterCmd::Get(settings) => &settings.schema_registry_url,
+            ExporterCmd::Create(settings) => &settings.schema_registry_url,
+            ExporterCmd::Pause(settings)
+            | ExporterCmd::Resume(settings)
+            | ExporterCmd::Reset(settings) => &settings.schema_registry_url,
+        }
+    }
+}
+
+fn run_list(client: Client, settings: ListSettings) -> anyhow::Result<()> {
+    let mut names = client
+        .list_exporters()
+        .map_err(|e| e.context("error listing exporters"))?;
+
+    names.sort();
+
+    if settings.json {
+        outln!("{}", serde_json::to_string_pretty(&names)?);
+        return Ok(());
+    }
+
+    for name in names {
+        outln!("{}", name);
+    }
+
+    Ok(())
+}
+
+fn run_get(client: Client, settings: GetSettings) -> anyhow::Result<()> {
+    let context = || format!("error retrieving exporter {}", settings.name);
+    let exporter = client
+        .get_exporter(&settings.name)
+        .map_err(|e| e.context(context()))?;
+    let status = client
+        .get_exporter_status(&settings.name)
+        .map_err(|e| e.context(context()))?;
+
+    if settings.json {
+        let value = json!({ "exporter": exporter, "status": status });
+        outln!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+
+    outln!("name: {}", exporter.name);
+    outln!("state: {} (offset {})", status.state, status.offset);
+    if let Some(trace) = &status.trace {
+        outln!("error: {}", trace);
+    }
+
+    if let Some(context_type) = &exporter.context_type {
+        outln!("context type: {}", context_type);
+    }
+
+    if let Some(context) = &exporter.context {
+        outln!("context: {}", context);
+    }
+
+    outln!("subjects: {}", exporter.subjects.join(", "));
+    if let Some(format) = &exporter.subject_rename_format {
+        outln!("subject rename format: {}", format);
+    }
+
+    if !exporter.config.is_empty() {
+        outln!("config:");
+        for (key, value) in &exporter.config {
+            outln!("\t{}: {}", key, value);
+        }
+    }
+
+    Ok(())
+}
+
+fn run_create(client: Client, mut settings: CreateSettings) -> anyhow::Result<()> {
+    if settings.subject.is_empty() {
+        settings.subject.push("*".to_owned());
+    }
+
+    let exporter = Exporter {
+        name: settings.name,
+        context_type: settings.context_type.map(|t| t.to_uppercase()),
+        context: settings.context,
+        subjects: settings.subject,
+        subject_rename_format: settings.subject_rename_format,
+        config: settings
+            .config
+            .into_iter()
+            .map(|property| (property.key, property.value))
+            .collect(),
+    };
+
+    client
+        .create_exporter(&exporter)
+        .map_err(|e| e.context(format!("error creating exporter {}", exporter.name)))?;
+
+    outln!("created exporter {}", exporter.name);
+    Ok(())
+}
+
+pub fn run_exporter(client: Client, settings: ExporterSettings) -> anyhow::Result<()> {
+    let (name, result, done) = match settings.command.expect("command") {
+        ExporterCmd::List(settings) => return run_list(client, settings),
+        ExporterCmd::Get(settings) => return run_get(client, settings),
+        ExporterCmd::Create(settings) => return run_create(client, settings),
+        ExporterCmd::Pause(settings) => {
+            let result = client.pause_exporter(&settings.name);
+            (settings.name, result, "paused")
+        }
+        ExporterCmd::Resume(settings) => {
+            let result = client.resume_exporter(&settings.name);
+            (settings.name, result, "resumed")
+        }
+        ExporterCmd::Reset(settings) => {
+            let result = client.reset_exporter(&settings.name);
+            (settings.name, result, "reset")
+        }
+    };
+
+    result.map_err(|e| e.context(format!("error updating exporter {}", name)))?;
+    outln!("{} exporter {}", done, name);
+    Ok(())
+}
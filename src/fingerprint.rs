@@ -0,0 +1,310 @@
+use crate::{
+    avro,
+    normalize::{
+        self,
+        string_literal_len,
+    },
+    registry::{
+        self,
+        Client,
+    },
+    SchemaTypeOpt,
+};
+
+use gumdrop::Options;
+use log::*;
+use schema_registry_converter::schema_registry_common::SchemaType;
+use serde_json::Value;
+
+use sha2::{
+    Digest,
+    Sha256,
+};
+
+use std::{
+    fs,
+    path::PathBuf,
+};
+
+/// Compute the fingerprint of a schema file: the CRC-64-AVRO (Rabin) fingerprint of the Parsing Canonical Form
+/// of an Avro schema (as 16 hex digits), or the SHA-256 digest of the canonical form of a JSON or protobuf schema
+/// (JSON with sorted keys and no whitespace, or protobuf without comments and redundant whitespace).
+/// Optionally, verify that it matches the fingerprint of the latest version of a subject.
+#[derive(Debug, Options)]
+pub struct FingerprintSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// schema type (required; one of `avro', `json', or `protobuf')
+    #[options(long = "type", meta = "TYPE", required, short = "T")]
+    schema_type: SchemaTypeOpt,
+
+    /// schema file (required)
+    #[options(required)]
+    file: PathBuf,
+
+    /// fail unless the fingerprint matches that of the latest version of this subject
+    #[options(no_short, meta = "NAME")]
+    pub verify_against_subject: Option<String>,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required with `--verify-against-subject')
+    #[options(free)]
+    pub schema_registry_url: Vec<String>,
+}
+
+/// Writes a JSON value with the keys of all objects sorted.
+fn write_sorted_json(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(object) => {
+            let mut entries: Vec<_> = object.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+
+                out.push_str(&Value::from(key.as_str()).to_string());
+                out.push(':');
+                write_sorted_json(value, out);
+            }
+
+            out.push('}');
+        }
+        Value::Array(values) => {
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+
+                write_sorted_json(value, out);
+            }
+
+            out.push(']');
+        }
+        value => out.push_str(&value.to_string()),
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+/// Removes the comments from protobuf schema text and collapses whitespace outside string literals,
+/// keeping a single space only where it separates two words (e.g., `int32 id = 1;' becomes `int32 id=1;').
+fn canonical_protobuf(schema: &str) -> String {
+    let stripped = normalize::normalize(
+        schema,
+        &SchemaType::Protobuf,
+        &normalize::Options {
+            strip_comments: true,
+            ..normalize::Options::default()
+        },
+    );
+
+    let mut buf = String::with_capacity(stripped.len());
+    let mut rest = stripped.as_str();
+    let mut space = false;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            space = true;
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+
+        if space && buf.ends_with(is_word_char) && is_word_char(c) {
+            buf.push(' ');
+        }
+
+        space = false;
+        let len = if c == '"' || c == '\'' {
+            string_literal_len(rest)
+        } else {
+            c.len_utf8()
+        };
+
+        buf.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+
+    buf
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Computes the fingerprint of schema text of the given type.
+fn fingerprint(schema: &str, schema_type: SchemaTypeOpt) -> anyhow::Result<String> {
+    let fingerprint = match schema_type {
+        SchemaTypeOpt::Avro => {
+            let value = serde_json::from_str(schema)
+                .map_err(|e| anyhow::format_err!("invalid Avro schema: {}", e))?;
+
+            let canonical = avro::canonical_form(&value)?;
+            debug!("canonical form: {}", canonical);
+            format!("{:016x}", avro::fingerprint64(canonical.as_bytes()))
+        }
+        SchemaTypeOpt::Json => {
+            let value: Value = serde_json::from_str(schema)
+                .map_err(|e| anyhow::format_err!("invalid JSON schema: {}", e))?;
+
+            let mut canonical = String::new();
+            write_sorted_json(&value, &mut canonical);
+            debug!("canonical form: {}", canonical);
+            hex(&Sha256::digest(canonical.as_bytes()))
+        }
+        SchemaTypeOpt::Protobuf => {
+            let canonical = canonical_protobuf(schema);
+            debug!("canonical form: {}", canonical);
+            hex(&Sha256::digest(canonical.as_bytes()))
+        }
+    };
+
+    Ok(fingerprint)
+}
+
+pub fn run_fingerprint(
+    client: Option<Client>,
+    settings: &FingerprintSettings,
+) -> anyhow::Result<()> {
+    let schema = fs::read_to_string(&settings.file)
+        .map_err(|e| anyhow::format_err!("failed to read {}: {}", settings.file.display(), e))?;
+
+    let actual = fingerprint(&schema, settings.schema_type)?;
+    outln!("{}", actual);
+
+    let (subject, client) = match (&settings.verify_against_subject, client) {
+        (Some(subject), Some(client)) => (subject, client),
+        _ => return Ok(()),
+    };
+
+    let registered = client
+        .get_schema(subject, "latest")
+        .map_err(|e| e.context(format!("error retrieving schema for {}", subject)))?;
+
+    let registered_type = registered.schema_type();
+    anyhow::ensure!(
+        registry::schema_type_name(&registered_type)
+            == registry::schema_type_name(&SchemaType::from(settings.schema_type)),
+        "schema of {} is of type {}, not {}",
+        subject,
+        registry::schema_type_name(&registered_type),
+        settings.schema_type
+    );
+
+    let expected = fingerprint(&registered.schema, settings.schema_type)?;
+    let version = registered
+        .version
+        .map_or_else(|| "latest".to_owned(), |v| v.to_string());
+
+    anyhow::ensure!(
+        actual == expected,
+        "fingerprint of {} does not match that of {} (version {}): {}",
+        settings.file.display(),
+        subject,
+        version,
+        expected
+    );
+
+    info!("fingerprint matches {} (version {})", subject, version);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprints_avro_primitives_per_the_spec() {
+        // Test vectors of the Avro specification, as signed 64-bit integers.
+        for (schema, expected) in [
+            ("\"null\"", 7195948357588979594i64),
+            ("\"boolean\"", -6970731678124411036),
+            ("\"int\"", 8247732601305521295),
+            ("\"long\"", -3434872931120570953),
+            ("\"float\"", 5583340709985441680),
+            ("\"double\"", -8181574048448539266),
+            ("\"bytes\"", 5746618253357095269),
+            ("\"string\"", -8142146995180207161),
+            ("{\"type\": \"null\"}", 7195948357588979594),
+        ] {
+            assert_eq!(
+                fingerprint(schema, SchemaTypeOpt::Avro).unwrap(),
+                format!("{:016x}", expected as u64),
+                "{}",
+                schema
+            );
+        }
+    }
+
+    #[test]
+    fn fingerprints_the_canonical_form_of_avro_records() {
+        let schema = r#"{
+            "fields": [
+                {"type": "int", "name": "f", "default": 0, "doc": "ignored"},
+                {"name": "e", "type": {"symbols": ["A", "B"], "type": "enum", "name": "E"}}
+            ],
+            "doc": "ignored",
+            "type": "record",
+            "aliases": ["ignored"],
+            "namespace": "a",
+            "name": "R"
+        }"#;
+
+        assert_eq!(
+            avro::canonical_form(&serde_json::from_str(schema).unwrap()).unwrap(),
+            r#"{"name":"a.R","type":"record","fields":[{"name":"f","type":"int"},{"name":"e","type":{"name":"a.E","type":"enum","symbols":["A","B"]}}]}"#
+        );
+
+        assert_eq!(
+            fingerprint(schema, SchemaTypeOpt::Avro).unwrap(),
+            "50ee48a18679f90a"
+        );
+    }
+
+    #[test]
+    fn fingerprints_json_regardless_of_key_order_and_whitespace() {
+        let expected = "08c8cc0e5745b1461ff0e3254761829dcd326eb328e9f4375a1ad67be880e009";
+        for schema in [
+            r#"{"properties":{"a":{"type":"string"}},"type":"object"}"#,
+            r#"{"type": "object", "properties": {"a": {"type": "string"}}}"#,
+            "{\n  \"type\" : \"object\",\n  \"properties\" : {\n    \"a\" : { \"type\" : \"string\" }\n  }\n}\n",
+        ] {
+            assert_eq!(fingerprint(schema, SchemaTypeOpt::Json).unwrap(), expected);
+        }
+
+        assert_ne!(
+            fingerprint(
+                r#"{"type":"object","properties":{"b":{"type":"string"}}}"#,
+                SchemaTypeOpt::Json
+            )
+            .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn fingerprints_protobuf_regardless_of_comments_and_whitespace() {
+        let canonical =
+            r#"syntax="proto3";package a;message M{int32 id=1;string name=2[json_name="n x"];}"#;
+        let expected = "ad8623efd14cfce7a0d0955a2c8531aac26a4091ce959c31db38a18076c4ffad";
+        for schema in [
+            canonical,
+            "syntax = \"proto3\";\npackage a;\n\n// A message.\nmessage M {\n  int32 id = 1; /* the id */\n  string name = 2 [json_name = \"n x\"];\n}\n",
+            "syntax=\"proto3\";   package   a;\tmessage M\n{ int32 id=1 ; string name=2 [ json_name=\"n x\" ] ; }",
+        ] {
+            assert_eq!(canonical_protobuf(schema), canonical);
+            assert_eq!(fingerprint(schema, SchemaTypeOpt::Protobuf).unwrap(), expected);
+        }
+
+        // Whitespace within string literals is kept.
+        assert_ne!(
+            fingerprint(&canonical.replace("n x", "n  x"), SchemaTypeOpt::Protobuf).unwrap(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,348 @@
+use crate::normalize::string_literal_len;
+
+use ratatui::{
+    style::{
+        Color,
+        Modifier,
+        Style,
+    },
+    text::{
+        Line,
+        Span,
+    },
+};
+
+use schema_registry_converter::schema_registry_common::SchemaType;
+use serde_json::Value;
+
+use std::{
+    env,
+    fmt,
+    io::{
+        self,
+        IsTerminal,
+    },
+    str::FromStr,
+    sync::atomic::{
+        AtomicBool,
+        Ordering,
+    },
+};
+
+/// Whether command output is colorized.
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// When to colorize command output.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum ColorChoice {
+    /// When printing to a terminal, unless NO_COLOR is set (to anything but an empty string).
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Always => write!(f, "always"),
+            Self::Never => write!(f, "never"),
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let choice = match s {
+            "auto" => Self::Auto,
+            "always" => Self::Always,
+            "never" => Self::Never,
+            _ => anyhow::bail!("unsupported color choice: {}", s),
+        };
+
+        Ok(choice)
+    }
+}
+
+/// Decides whether to colorize command output from now on.
+pub fn init(choice: ColorChoice) {
+    let color = match choice {
+        ColorChoice::Auto => {
+            io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+        }
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+
+    COLOR.store(color, Ordering::Relaxed);
+}
+
+/// Whether command output is to be colorized.
+pub fn enabled() -> bool {
+    COLOR.load(Ordering::Relaxed)
+}
+
+const PROTOBUF_KEYWORDS: &[&str] = &[
+    "syntax",
+    "edition",
+    "package",
+    "import",
+    "public",
+    "weak",
+    "option",
+    "message",
+    "enum",
+    "service",
+    "rpc",
+    "returns",
+    "stream",
+    "repeated",
+    "optional",
+    "required",
+    "map",
+    "oneof",
+    "reserved",
+    "extend",
+    "extensions",
+    "to",
+    "max",
+    "group",
+];
+
+const PROTOBUF_SCALARS: &[&str] = &[
+    "double", "float", "int32", "int64", "uint32", "uint64", "sint32", "sint64", "fixed32",
+    "fixed64", "sfixed32", "sfixed64", "bool", "string", "bytes",
+];
+
+/// Accumulates the spans of a line, merging consecutive unstyled text.
+#[derive(Default)]
+struct LineBuilder {
+    spans: Vec<Span<'static>>,
+    plain: String,
+}
+
+impl LineBuilder {
+    fn plain(&mut self, text: &str) {
+        self.plain.push_str(text);
+    }
+
+    fn styled(&mut self, text: &str, style: Style) {
+        self.flush();
+        self.spans.push(Span::styled(text.to_owned(), style));
+    }
+
+    fn flush(&mut self) {
+        if !self.plain.is_empty() {
+            self.spans.push(Span::raw(std::mem::take(&mut self.plain)));
+        }
+    }
+
+    fn build(mut self) -> Line<'static> {
+        self.flush();
+        Line::from(self.spans)
+    }
+}
+
+/// Length of the run of characters at the start of the text that satisfy the predicate.
+fn run_len(text: &str, pred: impl Fn(char) -> bool) -> usize {
+    text.find(|c| !pred(c)).unwrap_or(text.len())
+}
+
+/// Highlights a line of JSON: keys, string values, numbers, and literals (`true', `false', `null').
+fn highlight_json(line: &str) -> Line<'static> {
+    let mut builder = LineBuilder::default();
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        let (len, style) = if c == '"' {
+            let len = string_literal_len(rest);
+            let color = if rest[len..].trim_start().starts_with(':') {
+                Color::Cyan
+            } else {
+                Color::Green
+            };
+
+            (len, Some(Style::new().fg(color)))
+        } else if c == '-' || c.is_ascii_digit() {
+            let len = run_len(rest, |c| c.is_ascii_digit() || "+-.eE".contains(c));
+            (len, Some(Style::new().fg(Color::Yellow)))
+        } else if c.is_ascii_alphabetic() {
+            let len = run_len(rest, |c| c.is_ascii_alphabetic());
+            (len, Some(Style::new().fg(Color::Magenta)))
+        } else {
+            (c.len_utf8(), None)
+        };
+
+        match style {
+            Some(style) => builder.styled(&rest[..len], style),
+            None => builder.plain(&rest[..len]),
+        }
+
+        rest = &rest[len..];
+    }
+
+    builder.build()
+}
+
+/// Highlights a line of protobuf: keywords, scalar types, strings, numbers, and comments
+/// (tracking whether the line starts within a block comment).
+fn highlight_protobuf(line: &str, in_comment: &mut bool) -> Line<'static> {
+    let comment = Style::new().fg(Color::DarkGray);
+    let mut builder = LineBuilder::default();
+    let mut rest = line;
+    while !rest.is_empty() {
+        if *in_comment {
+            let len = rest.find("*/").map_or(rest.len(), |end| {
+                *in_comment = false;
+                end + 2
+            });
+
+            builder.styled(&rest[..len], comment);
+            rest = &rest[len..];
+            continue;
+        }
+
+        let c = rest.chars().next().expect("non-empty");
+        let (len, style) = if rest.starts_with("//") {
+            (rest.len(), Some(comment))
+        } else if rest.starts_with("/*") {
+            *in_comment = true;
+            (2, Some(comment))
+        } else if c == '"' || c == '\'' {
+            (
+                string_literal_len(rest),
+                Some(Style::new().fg(Color::Green)),
+            )
+        } else if c.is_ascii_digit() {
+            let len = run_len(rest, |c| c.is_ascii_alphanumeric() || c == '.');
+            (len, Some(Style::new().fg(Color::Yellow)))
+        } else if c.is_alphabetic() || c == '_' {
+            let len = run_len(rest, |c| c.is_alphanumeric() || c == '_');
+            let word = &rest[..len];
+            let style = if PROTOBUF_KEYWORDS.contains(&word) {
+                Some(Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD))
+            } else if PROTOBUF_SCALARS.contains(&word) {
+                Some(Style::new().fg(Color::Cyan))
+            } else {
+                None
+            };
+
+            (len, style)
+        } else {
+            (c.len_utf8(), None)
+        };
+
+        match style {
+            Some(style) => builder.styled(&rest[..len], style),
+            None => builder.plain(&rest[..len]),
+        }
+
+        rest = &rest[len..];
+    }
+
+    builder.build()
+}
+
+/// Highlights a line of schema text according to its type
+/// (tracking whether a protobuf line starts within a block comment).
+pub fn highlight_line(
+    line: &str,
+    schema_type: &SchemaType,
+    in_comment: &mut bool,
+) -> Line<'static> {
+    match schema_type {
+        SchemaType::Avro | SchemaType::Json => highlight_json(line),
+        SchemaType::Protobuf => highlight_protobuf(line, in_comment),
+        SchemaType::Other(_) => Line::raw(line.to_owned()),
+    }
+}
+
+/// Highlights schema text according to its type; JSON (including Avro) is pretty-printed first.
+pub fn highlight(text: &str, schema_type: &SchemaType) -> Vec<Line<'static>> {
+    let mut in_comment = false;
+    pretty(text, schema_type)
+        .lines()
+        .map(|line| highlight_line(line, schema_type, &mut in_comment))
+        .collect()
+}
+
+/// Pretty-prints JSON (including Avro) schema text, with keys in their original order and consistent indentation;
+/// any other schema text (or JSON that doesn't parse) is returned as is.
+pub fn pretty(text: &str, schema_type: &SchemaType) -> String {
+    match schema_type {
+        SchemaType::Avro | SchemaType::Json => serde_json::from_str::<Value>(text)
+            .and_then(|value| serde_json::to_string_pretty(&value))
+            .unwrap_or_else(|_| text.to_owned()),
+        _ => text.to_owned(),
+    }
+}
+
+/// Lines of schema text to print: pretty-printed, and highlighted (with ANSI escape sequences) if colorizing.
+pub fn schema_lines(text: &str, schema_type: &SchemaType) -> Vec<String> {
+    if enabled() {
+        highlight(text, schema_type).iter().map(ansi).collect()
+    } else {
+        pretty(text, schema_type)
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
+/// Renders a highlighted line with ANSI escape sequences (foreground colors and some modifiers only).
+pub fn ansi(line: &Line) -> String {
+    let mut out = String::new();
+    for span in &line.spans {
+        let mut codes = Vec::new();
+        if let Some(color) = span.style.fg {
+            codes.push(fg_code(color));
+        }
+
+        for (modifier, code) in [
+            (Modifier::BOLD, "1"),
+            (Modifier::DIM, "2"),
+            (Modifier::ITALIC, "3"),
+            (Modifier::UNDERLINED, "4"),
+        ] {
+            if span.style.add_modifier.contains(modifier) {
+                codes.push(code.to_owned());
+            }
+        }
+
+        if codes.is_empty() {
+            out.push_str(&span.content);
+        } else {
+            out.push_str(&format!("\x1b[{}m{}\x1b[0m", codes.join(";"), span.content));
+        }
+    }
+
+    out
+}
+
+fn fg_code(color: Color) -> String {
+    let code = match color {
+        Color::Reset => 39,
+        Color::Black => 30,
+        Color::Red => 31,
+        Color::Green => 32,
+        Color::Yellow => 33,
+        Color::Blue => 34,
+        Color::Magenta => 35,
+        Color::Cyan => 36,
+        Color::Gray => 37,
+        Color::DarkGray => 90,
+        Color::LightRed => 91,
+        Color::LightGreen => 92,
+        Color::LightYellow => 93,
+        Color::LightBlue => 94,
+        Color::LightMagenta => 95,
+        Color::LightCyan => 96,
+        Color::White => 97,
+        Color::Indexed(i) => return format!("38;5;{}", i),
+        Color::Rgb(r, g, b) => return format!("38;2;{};{};{}", r, g, b),
+    };
+
+    code.to_string()
+}
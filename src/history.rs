@@ -0,0 +1,632 @@
+use crate::{
+    avro,
+    breaking,
+    diff,
+    registry::{
+        Client,
+        Schema,
+    },
+    search,
+};
+
+use gumdrop::Options;
+use log::*;
+use prost_types::{
+    field_descriptor_proto::Label,
+    DescriptorProto,
+};
+
+use schema_registry_converter::schema_registry_common::{
+    get_subject,
+    SchemaType,
+};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use std::{
+    collections::{
+        BTreeMap,
+        HashSet,
+    },
+    fmt,
+    fs,
+};
+
+/// Name of the file a protobuf schema is written to for compilation.
+const PROTO_ROOT: &str = "ksrt-history-root.proto";
+
+/// Print the changelog of a subject: for each version, the fields added, removed, or renamed,
+/// and those whose type changed since the previous version, along with the reference changes.
+/// Renames are recognized by field number in protobuf, and by aliases in Avro.
+#[derive(Debug, Options)]
+pub struct HistorySettings {
+    /// print usage and exit
+    help: bool,
+
+    /// topic name (required unless `--record' is specified)
+    #[options(meta = "NAME")]
+    topic: Option<String>,
+
+    /// whether the subject is for the topic key (vs. value; only with `--topic' alone)
+    #[options(short = "k")]
+    topic_key: bool,
+
+    /// record name (required unless `--topic' is specified)
+    #[options(meta = "NAME")]
+    record: Option<String>,
+
+    /// print as JSON
+    #[options(no_short)]
+    json: bool,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    pub schema_registry_url: Vec<String>,
+}
+
+/// Field of a schema, as found at its path (e.g., `customer.address.city').
+#[derive(Debug)]
+struct Field {
+    type_name: String,
+    /// field number (protobuf only)
+    number: Option<i32>,
+    /// names the field was known by before (Avro only)
+    aliases: Vec<String>,
+}
+
+type Fields = BTreeMap<String, Field>;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase", tag = "change")]
+enum Change {
+    Added {
+        path: String,
+        #[serde(rename = "type")]
+        type_name: String,
+    },
+    Removed {
+        path: String,
+        #[serde(rename = "type")]
+        type_name: String,
+    },
+    Renamed {
+        from: String,
+        to: String,
+    },
+    TypeChanged {
+        path: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added { path, type_name } => write!(f, "added {} ({})", path, type_name),
+            Self::Removed { path, type_name } => write!(f, "removed {} ({})", path, type_name),
+            Self::Renamed { from, to } => write!(f, "renamed {} to {}", from, to),
+            Self::TypeChanged { path, from, to } => {
+                write!(f, "changed type of {} from {} to {}", path, from, to)
+            }
+        }
+    }
+}
+
+/// Changes made in one version of a subject.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    version: u32,
+    id: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date: Option<String>,
+    changes: Vec<Change>,
+    references: Vec<String>,
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('.').map_or("", |(parent, _)| parent)
+}
+
+fn leaf(path: &str) -> &str {
+    path.rsplit_once('.').map_or(path, |(_, leaf)| leaf)
+}
+
+fn avro_type_name(schema: &Value, namespace: &str) -> String {
+    match schema {
+        Value::String(name) if avro::PRIMITIVES.contains(&name.as_str()) => name.clone(),
+        Value::String(name) => avro::full_name(name, namespace),
+        Value::Array(schemas) => schemas
+            .iter()
+            .map(|schema| avro_type_name(schema, namespace))
+            .collect::<Vec<_>>()
+            .join(" | "),
+        Value::Object(object) => {
+            if let Some(logical) = object.get("logicalType").and_then(Value::as_str) {
+                return logical.to_owned();
+            }
+
+            match object.get("type") {
+                Some(Value::String(kind))
+                    if ["record", "error", "enum", "fixed"].contains(&kind.as_str()) =>
+                {
+                    avro::defined_name(object, namespace)
+                }
+                Some(Value::String(kind)) if kind == "array" => format!(
+                    "array<{}>",
+                    avro_type_name(object.get("items").unwrap_or(&Value::Null), namespace)
+                ),
+                Some(Value::String(kind)) if kind == "map" => format!(
+                    "map<{}>",
+                    avro_type_name(object.get("values").unwrap_or(&Value::Null), namespace)
+                ),
+                Some(schema) => avro_type_name(schema, namespace),
+                None => "(invalid)".to_owned(),
+            }
+        }
+        _ => "(invalid)".to_owned(),
+    }
+}
+
+/// Collects the fields of the records defined in an Avro schema, each under the path of the field it's defined in.
+fn avro_fields(
+    schema: &Value,
+    namespace: &str,
+    path: &str,
+    seen: &mut HashSet<String>,
+    fields: &mut Fields,
+) {
+    match schema {
+        Value::Array(schemas) => {
+            for schema in schemas {
+                avro_fields(schema, namespace, path, seen, fields);
+            }
+        }
+        Value::Object(object) => match object.get("type") {
+            Some(Value::String(kind)) if kind == "record" || kind == "error" => {
+                let name = avro::defined_name(object, namespace);
+                if !seen.insert(name.clone()) {
+                    return;
+                }
+
+                let namespace = avro::namespace_of(&name);
+                let defined = object.get("fields").and_then(Value::as_array);
+                for field in defined.into_iter().flatten() {
+                    let name = field
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default();
+                    let schema = field.get("type").unwrap_or(&Value::Null);
+                    let path = join(path, name);
+                    let aliases = field
+                        .get("aliases")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .map(str::to_owned)
+                        .collect();
+
+                    fields.insert(
+                        path.clone(),
+                        Field {
+                            type_name: avro_type_name(schema, namespace),
+                            number: None,
+                            aliases,
+                        },
+                    );
+
+                    avro_fields(schema, namespace, &path, seen, fields);
+                }
+            }
+            Some(Value::String(kind)) if kind == "array" => {
+                if let Some(items) = object.get("items") {
+                    avro_fields(items, namespace, path, seen, fields);
+                }
+            }
+            Some(Value::String(kind)) if kind == "map" => {
+                if let Some(values) = object.get("values") {
+                    avro_fields(values, namespace, path, seen, fields);
+                }
+            }
+            Some(schema @ Value::Object(_)) => avro_fields(schema, namespace, path, seen, fields),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+fn protobuf_fields(message: &DescriptorProto, path: &str, fields: &mut Fields) {
+    let path = join(path, message.name());
+    for field in &message.field {
+        let type_name = breaking::protobuf_type_name(field);
+        let type_name = match field.label() {
+            Label::Repeated => format!("repeated {}", type_name),
+            _ => type_name,
+        };
+
+        fields.insert(
+            join(&path, field.name()),
+            Field {
+                type_name,
+                number: Some(field.number()),
+                aliases: Vec::new(),
+            },
+        );
+    }
+
+    for nested in &message.nested_type {
+        protobuf_fields(nested, &path, fields);
+    }
+}
+
+fn json_type_name(schema: &Value) -> String {
+    let type_name = match schema.get("type") {
+        Some(Value::String(name)) => name.clone(),
+        Some(Value::Array(names)) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        _ => match schema.get("$ref").and_then(Value::as_str) {
+            Some(reference) => reference.to_owned(),
+            None => "any".to_owned(),
+        },
+    };
+
+    match schema.get("format").and_then(Value::as_str) {
+        Some(format) => format!("{} ({})", type_name, format),
+        None => type_name,
+    }
+}
+
+/// Collects the properties of a JSON schema and of the objects nested in it (including array items).
+fn json_fields(schema: &Value, path: &str, fields: &mut Fields) {
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (name, property) in properties.into_iter().flatten() {
+        let path = join(path, name);
+        fields.insert(
+            path.clone(),
+            Field {
+                type_name: json_type_name(property),
+                number: None,
+                aliases: Vec::new(),
+            },
+        );
+
+        json_fields(property, &path, fields);
+    }
+
+    if let Some(items) = schema.get("items") {
+        json_fields(items, path, fields);
+    }
+}
+
+/// Collects the fields of a version of a schema; protobuf schemas are compiled along with their references.
+fn fields(client: &Client, schema: &Schema) -> anyhow::Result<Fields> {
+    let mut fields = Fields::new();
+    match schema.schema_type() {
+        SchemaType::Avro => {
+            let value = serde_json::from_str(&schema.schema)
+                .map_err(|e| anyhow::format_err!("invalid Avro schema: {}", e))?;
+
+            avro_fields(&value, "", "", &mut HashSet::new(), &mut fields);
+        }
+        SchemaType::Json => {
+            let value = serde_json::from_str(&schema.schema)
+                .map_err(|e| anyhow::format_err!("invalid JSON schema: {}", e))?;
+
+            json_fields(&value, "", &mut fields);
+        }
+        SchemaType::Protobuf => {
+            let tmp = tempfile::Builder::new().prefix("ksrt-history").tempdir()?;
+            fs::write(tmp.path().join(PROTO_ROOT), &schema.schema)?;
+            search::write_references(client, &schema.references, tmp.path(), &mut HashSet::new())?;
+
+            let fds =
+                crate::parse_protos(&[tmp.path().join(PROTO_ROOT)], &[tmp.path().to_path_buf()])?;
+            let fd = fds
+                .file
+                .iter()
+                .find(|fd| fd.name() == PROTO_ROOT)
+                .ok_or_else(|| {
+                    anyhow::format_err!("failed to locate file descriptor for schema")
+                })?;
+
+            for message in &fd.message_type {
+                protobuf_fields(message, "", &mut fields);
+            }
+        }
+        SchemaType::Other(other) => anyhow::bail!("unsupported schema type: {}", other),
+    }
+
+    Ok(fields)
+}
+
+/// Whether a removed field was renamed to an added one: it keeps its number (protobuf),
+/// or the added field has the removed one's name as an alias (Avro).
+fn is_rename(from: &str, old: &Field, to: &str, new: &Field) -> bool {
+    parent(from) == parent(to)
+        && (old.number.is_some() && old.number == new.number
+            || new.aliases.iter().any(|alias| alias == leaf(from)))
+}
+
+/// Compares the fields of consecutive versions. The fields nested in a renamed one are matched up by their
+/// renamed paths, so that they aren't reported as removed and added again.
+fn changes(old: &Fields, new: &Fields) -> Vec<Change> {
+    let mut renamed: BTreeMap<&str, &str> = BTreeMap::new();
+    let mut matched = BTreeMap::new();
+    for (from, old_field) in old.iter().filter(|(path, _)| !new.contains_key(*path)) {
+        // Rename the path as its closest renamed ancestor was.
+        let moved = renamed
+            .iter()
+            .filter(|(prefix, _)| from.starts_with(&format!("{}.", prefix)))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(prefix, to)| format!("{}{}", to, &from[prefix.len()..]));
+
+        if let Some(to) = moved.filter(|to| new.contains_key(to) && !old.contains_key(to)) {
+            matched.insert(from.as_str(), to);
+            continue;
+        }
+
+        let rename = new.iter().find(|(to, new_field)| {
+            !old.contains_key(*to) && is_rename(from, old_field, to, new_field)
+        });
+
+        if let Some((to, _)) = rename {
+            renamed.insert(from, to);
+            matched.insert(from.as_str(), to.clone());
+        }
+    }
+
+    let mut changes = Vec::new();
+    for (from, to) in &matched {
+        if renamed.contains_key(from) {
+            changes.push(Change::Renamed {
+                from: (*from).to_owned(),
+                to: to.clone(),
+            });
+        }
+    }
+
+    for (path, field) in new {
+        if !old.contains_key(path) && !matched.values().any(|to| to == path) {
+            changes.push(Change::Added {
+                path: path.clone(),
+                type_name: field.type_name.clone(),
+            });
+        }
+    }
+
+    for (path, field) in old {
+        if !new.contains_key(path) && !matched.contains_key(path.as_str()) {
+            changes.push(Change::Removed {
+                path: path.clone(),
+                type_name: field.type_name.clone(),
+            });
+        }
+    }
+
+    for (path, old_field) in old {
+        let to = match matched.get(path.as_str()) {
+            Some(to) => to,
+            None => path,
+        };
+
+        if let Some(new_field) = new.get(to) {
+            if new_field.type_name != old_field.type_name {
+                changes.push(Change::TypeChanged {
+                    path: to.clone(),
+                    from: old_field.type_name.clone(),
+                    to: new_field.type_name.clone(),
+                });
+            }
+        }
+    }
+
+    changes
+}
+
+fn date(ts: Option<i64>) -> Option<String> {
+    let date = chrono::DateTime::from_timestamp_millis(ts?)?;
+    Some(date.format("%Y-%m-%d").to_string())
+}
+
+/// Prints the changes made in a version; those of the first one are all there is to its schema.
+fn print_entry(entry: &Entry, first: bool) {
+    out!("version {} (id {}", entry.version, entry.id);
+    if let Some(date) = &entry.date {
+        out!(", {}", date);
+    }
+
+    outln!(")");
+    if first {
+        outln!("\tinitial version");
+        return;
+    }
+
+    if entry.changes.is_empty() && entry.references.is_empty() {
+        outln!("\tno changes to fields or references");
+    }
+
+    for change in &entry.changes {
+        outln!("\t{}", change);
+    }
+
+    for reference in &entry.references {
+        outln!("\treference {}", reference);
+    }
+}
+
+pub fn run_history(client: Client, settings: &HistorySettings) -> anyhow::Result<()> {
+    let sns = crate::subject_name_strategy_from_settings(
+        settings.topic.clone(),
+        settings.record.clone(),
+        settings.topic_key,
+    )?;
+
+    let subject =
+        get_subject(&sns).map_err(|e| anyhow::format_err!("error determining subject: {:?}", e))?;
+
+    let versions = client.get_versions(&subject)?;
+    anyhow::ensure!(!versions.is_empty(), "subject not found: {}", subject);
+
+    let mut entries = Vec::with_capacity(versions.len());
+    let mut previous: Option<(Schema, Fields)> = None;
+    for version in versions {
+        debug!("retrieving {} version {}", subject, version);
+        let schema = client
+            .get_schema(&subject, &version.to_string())
+            .map_err(|e| e.context(format!("error retrieving version {}", version)))?;
+
+        let fields = fields(&client, &schema)
+            .map_err(|e| e.context(format!("error reading version {}", version)))?;
+
+        let (changes, references) = match &previous {
+            Some((old, old_fields)) => (
+                changes(old_fields, &fields),
+                diff::reference_changes(old, &schema),
+            ),
+            None => (Vec::new(), Vec::new()),
+        };
+
+        entries.push(Entry {
+            version,
+            id: schema.id,
+            date: date(schema.ts),
+            changes,
+            references,
+        });
+
+        previous = Some((schema, fields));
+    }
+
+    if settings.json {
+        outln!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    for (i, entry) in entries.iter().enumerate() {
+        print_entry(entry, i == 0);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(type_name: &str, number: Option<i32>, aliases: &[&str]) -> Field {
+        Field {
+            type_name: type_name.to_owned(),
+            number,
+            aliases: aliases.iter().map(|alias| (*alias).to_owned()).collect(),
+        }
+    }
+
+    fn fields(fields: Vec<(&str, Field)>) -> Fields {
+        fields
+            .into_iter()
+            .map(|(path, field)| (path.to_owned(), field))
+            .collect()
+    }
+
+    fn described(old: &Fields, new: &Fields) -> Vec<String> {
+        changes(old, new).iter().map(Change::to_string).collect()
+    }
+
+    #[test]
+    fn reports_fields_added_and_removed() {
+        let old = fields(vec![
+            ("id", field("string", None, &[])),
+            ("note", field("string", None, &[])),
+        ]);
+
+        let new = fields(vec![
+            ("id", field("string", None, &[])),
+            ("total", field("long", None, &[])),
+        ]);
+
+        assert_eq!(
+            described(&old, &new),
+            ["added total (long)", "removed note (string)"]
+        );
+        assert!(described(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn recognizes_protobuf_renames_by_number() {
+        let old = fields(vec![
+            ("Order.id", field("string", Some(1), &[])),
+            ("Order.name", field("string", Some(2), &[])),
+        ]);
+
+        let new = fields(vec![
+            ("Order.id", field("string", Some(1), &[])),
+            ("Order.title", field("int64", Some(2), &[])),
+        ]);
+
+        assert_eq!(
+            described(&old, &new),
+            [
+                "renamed Order.name to Order.title",
+                "changed type of Order.title from string to int64"
+            ]
+        );
+    }
+
+    #[test]
+    fn carries_avro_alias_renames_to_nested_fields() {
+        let old = fields(vec![
+            ("customer", field("com.acme.Customer", None, &[])),
+            ("customer.address", field("com.acme.Address", None, &[])),
+            ("customer.address.city", field("string", None, &[])),
+            ("customer.name", field("string", None, &[])),
+        ]);
+
+        let new = fields(vec![
+            ("client", field("com.acme.Customer", None, &["customer"])),
+            ("client.address", field("com.acme.Address", None, &[])),
+            ("client.address.city", field("string", None, &[])),
+            ("client.email", field("string", None, &[])),
+            ("client.name", field("string", None, &[])),
+        ]);
+
+        assert_eq!(
+            described(&old, &new),
+            ["renamed customer to client", "added client.email (string)"]
+        );
+    }
+
+    #[test]
+    fn moves_between_parents_are_not_renames() {
+        let old = fields(vec![
+            ("Order.Line.sku", field("string", Some(1), &[])),
+            ("Order.id", field("string", Some(2), &[])),
+        ]);
+
+        let new = fields(vec![
+            ("Order.id", field("string", Some(2), &[])),
+            ("Order.sku", field("string", Some(1), &[])),
+        ]);
+
+        assert_eq!(
+            described(&old, &new),
+            [
+                "added Order.sku (string)",
+                "removed Order.Line.sku (string)"
+            ]
+        );
+    }
+}
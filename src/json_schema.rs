@@ -0,0 +1,224 @@
+use serde_json::{
+    Map,
+    Value,
+};
+
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+/// Keywords whose values are instance data rather than schemas (so any `$ref' in them is not a reference).
+const INSTANCE_KEYWORDS: &[&str] = &["const", "default", "enum", "examples"];
+
+/// Keywords whose values map names of the schema author's choosing to schemas (so the names are not keywords).
+const NAMED_SCHEMA_KEYWORDS: &[&str] = &[
+    "$defs",
+    "definitions",
+    "dependentSchemas",
+    "patternProperties",
+    "properties",
+];
+
+fn load(path: &Path) -> anyhow::Result<Value> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| anyhow::format_err!("failed to read {}: {}", path.display(), e))?;
+
+    serde_json::from_str(&text)
+        .map_err(|e| anyhow::format_err!("invalid JSON schema {}: {}", path.display(), e))
+}
+
+fn escape_pointer(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Collects referenced schema documents under `$defs' in the root schema, rewriting all references to point there.
+struct Bundler<'a> {
+    root: PathBuf,
+    includes: &'a [PathBuf],
+    /// Keys under `$defs' of the documents bundled so far, by their (canonical) paths.
+    keys: HashMap<PathBuf, String>,
+    used_keys: HashSet<String>,
+    defs: Map<String, Value>,
+}
+
+impl Bundler<'_> {
+    /// Locates a referenced document relative to the referencing one's directory, or else in the include directories.
+    fn locate(&self, doc: &str, dir: &Path) -> anyhow::Result<PathBuf> {
+        let path = Some(dir)
+            .into_iter()
+            .chain(self.includes.iter().map(PathBuf::as_path))
+            .map(|dir| dir.join(doc))
+            .find(|path| path.is_file())
+            .ok_or_else(|| anyhow::format_err!("failed to locate referenced schema: {}", doc))?;
+
+        Ok(path.canonicalize()?)
+    }
+
+    /// Returns the JSON pointer (within the bundle) of the given document, bundling it first if need be.
+    fn bundle(&mut self, path: PathBuf) -> anyhow::Result<String> {
+        if path == self.root {
+            return Ok(String::new());
+        }
+
+        if let Some(key) = self.keys.get(&path) {
+            return Ok(format!("/$defs/{}", escape_pointer(key)));
+        }
+
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut key = stem.clone();
+        let mut n = 1;
+        while self.used_keys.contains(&key) {
+            n += 1;
+            key = format!("{}_{}", stem, n);
+        }
+
+        // Register the key before rewriting the document, in case it (indirectly) refers to itself.
+        self.used_keys.insert(key.clone());
+        self.keys.insert(path.clone(), key.clone());
+
+        let pointer = format!("/$defs/{}", escape_pointer(&key));
+        let mut schema = load(&path)?;
+        if let Value::Object(schema) = &mut schema {
+            // An embedded `$id' would change what the rewritten references resolve against.
+            schema.shift_remove("$id");
+            schema.shift_remove("$schema");
+        }
+
+        let dir = path.parent().expect("file has a parent directory");
+        self.rewrite(&mut schema, dir, &pointer)?;
+        self.defs.insert(key, schema);
+
+        Ok(pointer)
+    }
+
+    /// Rewrites the references in a schema document bundled at the given pointer to point within the bundle.
+    fn rewrite(&mut self, schema: &mut Value, dir: &Path, pointer: &str) -> anyhow::Result<()> {
+        match schema {
+            Value::Object(schema) => {
+                for (key, value) in schema.iter_mut() {
+                    match (key.as_str(), value) {
+                        ("$ref", Value::String(reference)) => {
+                            let (doc, fragment) =
+                                reference.split_once('#').unwrap_or((reference, ""));
+
+                            anyhow::ensure!(
+                                !doc.contains("://"),
+                                "cannot inline remote reference: {}",
+                                reference
+                            );
+
+                            let target = if doc.is_empty() {
+                                pointer.to_owned()
+                            } else {
+                                let path = self.locate(doc, dir)?;
+                                self.bundle(path)?
+                            };
+
+                            *reference = format!("#{}{}", target, fragment);
+                        }
+                        (key, Value::Object(schemas)) if NAMED_SCHEMA_KEYWORDS.contains(&key) => {
+                            for schema in schemas.values_mut() {
+                                self.rewrite(schema, dir, pointer)?;
+                            }
+                        }
+                        (key, _) if INSTANCE_KEYWORDS.contains(&key) => {}
+                        (_, value) => self.rewrite(value, dir, pointer)?,
+                    }
+                }
+            }
+            Value::Array(schemas) => {
+                for schema in schemas {
+                    self.rewrite(schema, dir, pointer)?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Bundles a JSON schema file with all the schema documents it references (directly or not) into one self-contained schema.
+/// Referenced documents are looked up relative to the referencing one, or else in the include directories,
+/// and end up under `$defs' in the root schema, named after their files.
+pub fn bundle(file: &Path, includes: &[PathBuf]) -> anyhow::Result<String> {
+    let root = file.canonicalize()?;
+    let mut schema = load(&root)?;
+
+    let used_keys = schema
+        .get("$defs")
+        .and_then(Value::as_object)
+        .map(|defs| defs.keys().cloned().collect())
+        .unwrap_or_default();
+
+    let mut bundler = Bundler {
+        root: root.clone(),
+        includes,
+        keys: HashMap::new(),
+        used_keys,
+        defs: Map::new(),
+    };
+
+    let dir = root.parent().expect("file has a parent directory");
+    bundler.rewrite(&mut schema, dir, "")?;
+
+    if !bundler.defs.is_empty() {
+        let schema = schema
+            .as_object_mut()
+            .ok_or_else(|| anyhow::format_err!("cannot bundle references into a boolean schema"))?;
+
+        let defs = schema
+            .entry("$defs")
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(|| anyhow::format_err!("invalid $defs in {}", file.display()))?;
+
+        defs.extend(bundler.defs);
+    }
+
+    Ok(serde_json::to_string_pretty(&schema)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundles_properties_named_like_instance_keywords() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("other.json"), r#"{"type": "string"}"#).unwrap();
+        fs::write(
+            dir.path().join("root.json"),
+            r#"{
+                "type": "object",
+                "properties": {
+                    "default": {"$ref": "other.json"},
+                    "name": {"type": "string", "default": {"$ref": "not-a-reference"}}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let bundled: Value =
+            serde_json::from_str(&bundle(&dir.path().join("root.json"), &[]).unwrap()).unwrap();
+
+        assert_eq!(bundled["properties"]["default"]["$ref"], "#/$defs/other");
+        assert_eq!(
+            bundled["properties"]["name"]["default"]["$ref"],
+            "not-a-reference"
+        );
+        assert_eq!(bundled["$defs"]["other"]["type"], "string");
+    }
+}
@@ -0,0 +1,226 @@
+use crate::registry::{
+    Client,
+    Metadata,
+};
+
+use gumdrop::Options;
+use log::*;
+use reqwest::Url;
+use schema_registry_converter::schema_registry_common::get_subject;
+
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+};
+
+/// Prefix of the schema metadata properties that hold links.
+const LINK_PREFIX: &str = "link.";
+
+/// Show or change the links of a subject to external resources (e.g., data catalog entries or ownership pages).
+/// Links are kept as `link.<name>' properties in the metadata of the subject's latest version,
+/// so changing them registers a new version with the same schema.
+#[derive(Debug, Options)]
+pub struct LinksSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// command
+    #[options(command, required)]
+    command: Option<LinksCmd>,
+}
+
+#[derive(Debug, Options)]
+enum LinksCmd {
+    /// show the links of a subject
+    Show(ShowSettings),
+
+    /// add, change, or remove links of a subject
+    Set(SetSettings),
+}
+
+/// Show the links of a subject.
+#[derive(Debug, Options)]
+struct ShowSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// topic name (required unless `--record' is specified)
+    #[options(meta = "NAME")]
+    topic: Option<String>,
+
+    /// whether the subject is for the topic key (vs. value; only with `--topic' alone)
+    #[options(short = "k")]
+    topic_key: bool,
+
+    /// record name (required unless `--topic' is specified)
+    #[options(meta = "NAME")]
+    record: Option<String>,
+
+    /// print as JSON
+    #[options(no_short)]
+    json: bool,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    schema_registry_url: Vec<String>,
+}
+
+/// Add, change, or remove links of a subject.
+#[derive(Debug, Options)]
+struct SetSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// topic name (required unless `--record' is specified)
+    #[options(meta = "NAME")]
+    topic: Option<String>,
+
+    /// whether the subject is for the topic key (vs. value; only with `--topic' alone)
+    #[options(short = "k")]
+    topic_key: bool,
+
+    /// record name (required unless `--topic' is specified)
+    #[options(meta = "NAME")]
+    record: Option<String>,
+
+    /// link to add or change (could be multiple)
+    #[options(meta = "NAME=URL")]
+    link: Vec<Link>,
+
+    /// name of a link to remove (could be multiple)
+    #[options(no_short, meta = "NAME")]
+    remove: Vec<String>,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    schema_registry_url: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Link {
+    name: String,
+    url: Url,
+}
+
+impl FromStr for Link {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, url) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow::format_err!("expected NAME=URL: {}", s))?;
+
+        anyhow::ensure!(!name.is_empty(), "missing link name: {}", s);
+
+        let url = Url::parse(url).map_err(|e| anyhow::format_err!("invalid URL {}: {}", url, e))?;
+
+        Ok(Self {
+            name: name.to_owned(),
+            url,
+        })
+    }
+}
+
+fn subject(
+    topic: Option<String>,
+    record: Option<String>,
+    topic_key: bool,
+) -> anyhow::Result<String> {
+    let sns = crate::subject_name_strategy_from_settings(topic, record, topic_key)?;
+    get_subject(&sns).map_err(|e| anyhow::format_err!("error determining subject: {:?}", e))
+}
+
+/// Extracts the links, keyed by name, from schema metadata.
+fn links(metadata: Option<&Metadata>) -> BTreeMap<String, String> {
+    metadata
+        .into_iter()
+        .flat_map(|metadata| &metadata.properties)
+        .filter_map(|(key, value)| {
+            key.strip_prefix(LINK_PREFIX)
+                .map(|name| (name.to_owned(), value.clone()))
+        })
+        .collect()
+}
+
+fn run_show(client: Client, settings: ShowSettings) -> anyhow::Result<()> {
+    let subject = subject(settings.topic, settings.record, settings.topic_key)?;
+    let schema = client
+        .get_schema(&subject, "latest")
+        .map_err(|e| e.context("error retrieving schema"))?;
+
+    let links = links(schema.metadata.as_ref());
+    if settings.json {
+        outln!("{}", serde_json::to_string_pretty(&links)?);
+        return Ok(());
+    }
+
+    for (name, url) in links {
+        outln!("{}: {}", name, url);
+    }
+
+    Ok(())
+}
+
+fn run_set(client: Client, settings: SetSettings) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !settings.link.is_empty() || !settings.remove.is_empty(),
+        "at least one `--link' or `--remove' is required"
+    );
+
+    let subject = subject(settings.topic, settings.record, settings.topic_key)?;
+    let mut schema = client
+        .get_schema(&subject, "latest")
+        .map_err(|e| e.context("error retrieving schema"))?;
+
+    let mut metadata = schema.metadata.clone().unwrap_or_default();
+    for name in &settings.remove {
+        if metadata
+            .properties
+            .remove(&format!("{}{}", LINK_PREFIX, name))
+            .is_none()
+        {
+            warn!("{} has no link named {}", subject, name);
+        }
+    }
+
+    for link in &settings.link {
+        metadata.properties.insert(
+            format!("{}{}", LINK_PREFIX, link.name),
+            link.url.to_string(),
+        );
+    }
+
+    if schema.metadata.clone().unwrap_or_default() == metadata {
+        outln!("links of {} unchanged", subject);
+        return Ok(());
+    }
+
+    schema.metadata = Some(metadata);
+    let id = client
+        .register_schema(&subject, &schema)
+        .map_err(|e| e.context(format!("error updating links of {}", subject)))?;
+
+    outln!("updated links of {} (id {})", subject, id);
+    for (name, url) in links(schema.metadata.as_ref()) {
+        outln!("\t{}: {}", name, url);
+    }
+
+    Ok(())
+}
+
+impl LinksSettings {
+    /// The Schema Registry URL(s) given to the subcommand.
+    pub fn schema_registry_url(&self) -> &[String] {
+        match self.command.as_ref().expect("command") {
+            LinksCmd::Show(settings) => &settings.schema_registry_url,
+            LinksCmd::Set(settings) => &settings.schema_registry_url,
+        }
+    }
+}
+
+pub fn run_links(client: Client, settings: LinksSettings) -> anyhow::Result<()> {
+    match settings.command.expect("command") {
+        LinksCmd::Show(settings) => run_show(client, settings),
+        LinksCmd::Set(settings) => run_set(client, settings),
+    }
+}
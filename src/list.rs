@@ -0,0 +1,118 @@
+use crate::registry::Client;
+
+use gumdrop::Options;
+use log::*;
+
+use std::{
+    cmp::Reverse,
+    fmt,
+    str::FromStr,
+};
+
+/// List the subjects in the Kafka Schema Registry along with their number of versions
+/// and the size of their latest schema (in bytes), one per line, separated by tabs.
+/// The order is always deterministic: ties are broken by subject name, which sorts bytewise.
+#[derive(Debug, Options)]
+pub struct ListSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// sort key (one of `name', `versions', or `size'; counts and sizes sort largest first)
+    #[options(meta = "KEY", default = "name")]
+    sort: SortKey,
+
+    /// include soft-deleted subjects and versions
+    #[options(no_short)]
+    deleted: bool,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    pub schema_registry_url: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum SortKey {
+    Name,
+    Versions,
+    Size,
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name => write!(f, "name"),
+            Self::Versions => write!(f, "versions"),
+            Self::Size => write!(f, "size"),
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let key = match s {
+            "name" => Self::Name,
+            "versions" => Self::Versions,
+            "size" => Self::Size,
+            _ => anyhow::bail!("unsupported sort key: {}", s),
+        };
+
+        Ok(key)
+    }
+}
+
+#[derive(Debug)]
+struct SubjectSummary {
+    subject: String,
+    versions: usize,
+    size: usize,
+}
+
+fn sort(summaries: &mut [SubjectSummary], key: SortKey) {
+    match key {
+        SortKey::Name => summaries.sort_by(|a, b| a.subject.cmp(&b.subject)),
+        SortKey::Versions => summaries.sort_by(|a, b| {
+            (Reverse(a.versions), &a.subject).cmp(&(Reverse(b.versions), &b.subject))
+        }),
+        SortKey::Size => summaries
+            .sort_by(|a, b| (Reverse(a.size), &a.subject).cmp(&(Reverse(b.size), &b.subject))),
+    }
+}
+
+pub fn run_list(client: Client, settings: &ListSettings) -> anyhow::Result<()> {
+    let client = if settings.deleted {
+        client.with_deleted()
+    } else {
+        client
+    };
+
+    let mut summaries = client
+        .list_subjects()?
+        .into_iter()
+        .map(|subject| {
+            let versions = client.get_versions(&subject)?.len();
+            let latest = client.get_schema(&subject, "latest")?;
+
+            Ok(SubjectSummary {
+                subject,
+                versions,
+                size: latest.schema.len(),
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    debug!("{} subject(s)", summaries.len());
+
+    sort(&mut summaries, settings.sort);
+    for summary in summaries {
+        outln!(
+            "{}\t{}\t{}",
+            summary.subject,
+            summary.versions,
+            summary.size
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use crate::registry::{
+    self,
+    Compatibility,
+};
+
+use schema_registry_converter::schema_registry_common::{
+    SuppliedReference,
+    SuppliedSchema,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use sha2::{
+    Digest,
+    Sha256,
+};
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io,
+    path::Path,
+};
+
+const HEADER: &str =
+    "# Generated by `ksrt apply'; records what was applied to each registry. Do not edit.\n\n";
+
+/// Subject as last applied to a registry.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Locked {
+    /// hash of the schema (including its references) and the compatibility level applied
+    pub hash: String,
+    /// global id of the schema
+    pub id: u32,
+}
+
+/// What `apply' last applied, by registry and subject, so that subjects that haven't changed since
+/// can be skipped without asking the registry (`ksrt.lock').
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Lock {
+    #[serde(default)]
+    registries: BTreeMap<String, BTreeMap<String, Locked>>,
+}
+
+impl Lock {
+    /// Loads the lock file, if there is one.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => anyhow::bail!("failed to read {}: {}", path.display(), e),
+        };
+
+        toml::from_str(&text)
+            .map_err(|e| anyhow::format_err!("invalid lock file {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let text = toml::to_string_pretty(self)?;
+        fs::write(path, format!("{}{}", HEADER, text))
+            .map_err(|e| anyhow::format_err!("failed to write {}: {}", path.display(), e))
+    }
+
+    /// The subjects last applied to the given registry.
+    pub fn registry(&self, registry: &str) -> Option<&BTreeMap<String, Locked>> {
+        self.registries.get(registry)
+    }
+
+    /// Replaces what was applied to the given registry.
+    pub fn set_registry(&mut self, registry: String, subjects: BTreeMap<String, Locked>) {
+        self.registries.insert(registry, subjects);
+    }
+}
+
+fn hash_references(hasher: &mut Sha256, references: &[SuppliedReference]) {
+    for reference in references {
+        hasher.update([0]);
+        hasher.update(format!("{}:{}", reference.name, reference.subject));
+        hasher.update([0]);
+        hasher.update(&reference.schema);
+        hash_references(hasher, &reference.references);
+    }
+}
+
+/// Computes the hash of a resolved schema (its type, text, and references, including theirs)
+/// along with the compatibility level to apply.
+pub fn hash(schema: &SuppliedSchema, compatibility: Option<Compatibility>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(registry::schema_type_name(&schema.schema_type));
+    hasher.update([0]);
+    hasher.update(&schema.schema);
+    hash_references(&mut hasher, &schema.references);
+    if let Some(compatibility) = compatibility {
+        hasher.update([0]);
+        hasher.update(compatibility.to_string());
+    }
+
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema_registry_converter::schema_registry_common::SchemaType;
+
+    fn reference(
+        name: &str,
+        schema: &str,
+        references: Vec<SuppliedReference>,
+    ) -> SuppliedReference {
+        SuppliedReference {
+            name: name.to_owned(),
+            subject: name.to_owned(),
+            schema: schema.to_owned(),
+            references,
+        }
+    }
+
+    fn schema() -> SuppliedSchema {
+        SuppliedSchema {
+            name: None,
+            schema_type: SchemaType::Protobuf,
+            schema: "syntax = \"proto3\";".to_owned(),
+            references: vec![reference(
+                "b.proto",
+                "// b",
+                vec![reference("c.proto", "// c", Vec::new())],
+            )],
+        }
+    }
+
+    #[test]
+    fn hash_is_stable() {
+        // Lock files written by earlier runs must keep matching unchanged schemas.
+        assert_eq!(
+            hash(&schema(), Some(Compatibility::Backward)),
+            "accfdf11ea70b11cd74c5975480db98c5a1635fa680243b19ef27235e7445313"
+        );
+        assert_eq!(hash(&schema(), None), hash(&schema(), None));
+    }
+
+    #[test]
+    fn hash_covers_schema_references_and_compatibility() {
+        let base = hash(&schema(), None);
+
+        let mut changed = schema();
+        changed.schema_type = SchemaType::Avro;
+        assert_ne!(hash(&changed, None), base);
+
+        let mut changed = schema();
+        changed.schema.push('\n');
+        assert_ne!(hash(&changed, None), base);
+
+        let mut changed = schema();
+        changed.references[0].subject = "other-b".to_owned();
+        assert_ne!(hash(&changed, None), base);
+
+        let mut changed = schema();
+        changed.references[0].references[0].schema = "// c, changed".to_owned();
+        assert_ne!(hash(&changed, None), base);
+
+        let mut changed = schema();
+        changed.references[0].references.clear();
+        assert_ne!(hash(&changed, None), base);
+
+        assert_ne!(hash(&schema(), Some(Compatibility::Full)), base);
+        assert_ne!(
+            hash(&schema(), Some(Compatibility::Full)),
+            hash(&schema(), Some(Compatibility::FullTransitive))
+        );
+    }
+
+    #[test]
+    fn round_trips_lock_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ksrt.lock");
+        assert!(Lock::load(&path).unwrap().registry("http://a/").is_none());
+
+        let locked = Locked {
+            hash: hash(&schema(), None),
+            id: 42,
+        };
+
+        let mut lock = Lock::default();
+        lock.set_registry(
+            "http://a/".to_owned(),
+            [("orders-value".to_owned(), locked.clone())].into(),
+        );
+        lock.save(&path).unwrap();
+
+        let lock = Lock::load(&path).unwrap();
+        assert_eq!(lock.registry("http://a/").unwrap()["orders-value"], locked);
+        assert!(lock.registry("http://b/").is_none());
+    }
+}
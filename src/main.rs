@@ -11,66 +11,587 @@ use prost_types::{
     FileDescriptorSet,
 };
 
-use regex::{
-    Regex,
-    RegexBuilder,
+use reqwest::header::{
+    HeaderMap,
+    HeaderName,
+    HeaderValue,
 };
 
-use schema_registry_converter::{
-    blocking::schema_registry::{
-        get_schema_by_subject,
-        post_schema,
-        SrSettings,
-    },
-    schema_registry_common::{
-        get_subject,
-        RegisteredReference,
-        RegisteredSchema,
-        SchemaType,
-        SubjectNameStrategy,
-        SuppliedReference,
-        SuppliedSchema,
-    },
+use schema_registry_converter::schema_registry_common::{
+    get_subject,
+    RegisteredReference,
+    RegisteredSchema,
+    SchemaType,
+    SubjectNameStrategy,
+    SuppliedReference,
+    SuppliedSchema,
+};
+
+use serde::{
+    de::DeserializeOwned,
+    Deserialize,
+    Serialize,
 };
 
 use std::{
-    collections::HashMap,
+    borrow::Cow,
+    collections::{
+        BTreeMap,
+        BTreeSet,
+        HashMap,
+        HashSet,
+    },
+    env,
     fmt,
     fs,
+    io::{
+        self,
+        Read,
+    },
     path::{
         Path,
         PathBuf,
     },
-    process::Command,
+    process::{
+        self,
+        Command,
+    },
     str::FromStr,
+    sync::atomic::{
+        AtomicBool,
+        Ordering,
+    },
+    time::Duration,
 };
 
-use tracing_subscriber::{
-    fmt::Subscriber as TracingSubscriber,
-    EnvFilter as TracingEnvFilter,
-};
+/// Whether to suppress all output but errors (`--quiet').
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Prints command output to standard output, unless suppressed with `--quiet'.
+macro_rules! out {
+    ($($arg:tt)*) => {
+        if !crate::QUIET.load(std::sync::atomic::Ordering::Relaxed) {
+            print!($($arg)*);
+        }
+    };
+}
+
+/// Prints a line of command output to standard output, unless suppressed with `--quiet'.
+macro_rules! outln {
+    ($($arg:tt)*) => {
+        if !crate::QUIET.load(std::sync::atomic::Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Defines the settings of a command that reads a schema file the way `post' does: the options that control
+/// how the schema is read (and the subject it's for) come first, followed by the command's own options
+/// and the Schema Registry URLs. The settings then make up the `SchemaSource' to read the schema from.
+macro_rules! schema_file_settings {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            file: $file_doc:literal,
+            $($(#[$field_attr:meta])* $field:ident: $ty:ident $(<$arg:path>)?,)*
+        }
+    ) => {
+        $(#[$attr])*
+        $vis struct $name {
+            /// print usage and exit
+            help: bool,
+
+            /// schema type (required; one of `avro', `json', or `protobuf')
+            #[options(long = "type", meta = "TYPE", required, short = "T")]
+            schema_type: crate::SchemaTypeOpt,
+
+            /// topic name (required unless `--record' is specified)
+            #[options(meta = "NAME")]
+            topic: Option<String>,
+
+            /// whether the schema is for the topic key (vs. value; only with `--topic' alone)
+            #[options(short = "k")]
+            topic_key: bool,
+
+            /// record name (required unless `--topic' is specified)
+            #[options(meta = "NAME")]
+            record: Option<String>,
+
+            #[doc = $file_doc]
+            #[options(required)]
+            file: std::path::PathBuf,
+
+            /// path the schema read from standard input would have (for resolving protobuf imports)
+            #[options(meta = "PATH")]
+            name: Option<std::path::PathBuf>,
+
+            /// include directory for any references (optional; could be multiple)
+            #[options(meta = "DIR")]
+            include: Vec<std::path::PathBuf>,
+
+            /// strip comments (including `doc' in Avro and `description' in JSON schemas)
+            #[options(no_short)]
+            strip_comments: bool,
+
+            /// strip options, including custom ones, except those given with `--keep-option' (protobuf only)
+            #[options(no_short)]
+            strip_options: bool,
+
+            /// option to keep when stripping options (e.g., `deprecated' or `(acme.codegen)'; could be multiple)
+            #[options(no_short, meta = "NAME")]
+            keep_option: Vec<String>,
+
+            /// bundle all files the schema references (`$ref') into it instead of posting them separately (JSON only)
+            #[options(no_short)]
+            inline_refs: bool,
+
+            /// local directory to resolve protobuf imports under a registry prefix from (e.g., `vendor/common=company/common'; could be multiple)
+            #[options(no_short, meta = "DIR=PREFIX")]
+            include_map: Vec<crate::IncludeMap>,
+
+            /// shell command to pipe the schema text (and that of each reference) through once read (could be multiple)
+            #[options(no_short, meta = "CMD")]
+            transform: Vec<String>,
+
+            /// how to derive the subjects of referenced protobuf files (one of `record', `topic-record', or `filename')
+            #[options(no_short, meta = "STRATEGY", default = "record")]
+            ref_strategy: crate::RefStrategy,
+
+            /// topic to prefix the subjects of referenced protobuf files with (requires `--ref-strategy topic-record')
+            #[options(no_short, meta = "NAME")]
+            ref_topic: Option<String>,
+
+            /// how to name references to Avro types defined in other files (`full-name', `path', or a template such as `{namespace}/{name}.avsc')
+            #[options(no_short, meta = "NAMING", default = "full-name")]
+            ref_name: crate::avro::RefName,
+
+            // gumdrop tells `Option' and `Vec' options by their type names, so types are passed on as tokens.
+            $($(#[$field_attr])* $field: $ty $(<$arg>)?,)*
+
+            /// Schema Registry URL(s), or the name of a configured profile (required)
+            #[options(free, required)]
+            pub schema_registry_url: Vec<String>,
+        }
+
+        impl From<&$name> for crate::SchemaSource {
+            fn from(settings: &$name) -> Self {
+                Self {
+                    schema_type: settings.schema_type,
+                    file: settings.file.clone(),
+                    name: settings.name.clone(),
+                    include: settings.include.clone(),
+                    strip_comments: settings.strip_comments,
+                    strip_options: settings.strip_options,
+                    keep_options: settings.keep_option.clone(),
+                    inline_refs: settings.inline_refs,
+                    reference_subjects: std::collections::BTreeMap::new(),
+                    ref_strategy: settings.ref_strategy,
+                    ref_topic: settings.ref_topic.clone(),
+                    ref_name: settings.ref_name.clone(),
+                    include_map: settings.include_map.clone(),
+                    transform: settings.transform.clone(),
+                }
+            }
+        }
+    };
+}
+
+mod apply;
+mod avro;
+mod bench;
+mod breaking;
+mod browse;
 
 #[allow(dead_code)]
 mod built_info;
 
+mod cache;
+mod ci_check;
+mod compare;
+mod config;
+mod context;
+mod diff;
+mod exit;
+mod exporter;
+mod fingerprint;
+mod highlight;
+mod history;
+mod json_schema;
+mod links;
+mod list;
+mod lock;
+mod manifest;
+mod meta;
+mod normalize;
+mod oauth;
+mod plan;
+mod refs_to;
+mod registry;
+mod replace;
+mod sample;
+mod search;
+mod stats;
+mod telemetry;
+mod throttle;
+mod transform;
+mod watch;
+mod workspace;
+
 /// Manage schemas in the Kafka Schema Registry.
 #[derive(Debug, Options)]
 struct Settings {
     /// print usage and exit
     help: bool,
 
+    /// schema context to qualify all subjects with (e.g., `tenant' for `:.tenant:subject')
+    #[options(no_short, meta = "NAME")]
+    context: Option<String>,
+
+    /// API key for HTTP basic authentication (e.g., with Confluent Cloud)
+    #[options(no_short, meta = "KEY")]
+    api_key: Option<String>,
+
+    /// API secret (required with `--api-key' unless KSRT_API_SECRET is set)
+    #[options(no_short, meta = "SECRET")]
+    api_secret: Option<Secret>,
+
+    /// OAuth 2.0 token endpoint to obtain bearer tokens from (client credentials flow)
+    #[options(no_short, meta = "URL")]
+    oauth_token_url: Option<String>,
+
+    /// OAuth 2.0 client id (required with `--oauth-token-url')
+    #[options(no_short, meta = "ID")]
+    oauth_client_id: Option<String>,
+
+    /// OAuth 2.0 client secret (required with `--oauth-token-url' unless KSRT_OAUTH_CLIENT_SECRET is set)
+    #[options(no_short, meta = "SECRET")]
+    oauth_client_secret: Option<Secret>,
+
+    /// OAuth 2.0 scope to request (optional)
+    #[options(no_short, meta = "SCOPE")]
+    oauth_scope: Option<String>,
+
+    /// flavor of Schema Registry (one of `confluent' or `apicurio'; default: confluent)
+    #[options(no_short, meta = "FLAVOR")]
+    registry_flavor: Option<registry::Flavor>,
+
+    /// Apicurio artifact group to map subjects to (default: Apicurio's default group)
+    #[options(no_short, meta = "GROUP")]
+    apicurio_group: Option<String>,
+
+    /// maximum number of requests per second to send to the Schema Registry (e.g., to avoid being throttled)
+    #[options(no_short, meta = "N")]
+    rate_limit: Option<u32>,
+
+    /// HTTP header to send with every request to the Schema Registry (could be multiple)
+    #[options(no_short, meta = "NAME:VALUE")]
+    header: Vec<HeaderOpt>,
+
+    /// directory to cache schemas in, so that they carry over to later runs (in addition to memory)
+    #[options(no_short, meta = "DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// how long cached schemas remain valid (e.g., `30m' or `12h'; default: 24h)
+    #[options(no_short, meta = "DURATION")]
+    cache_ttl: Option<DurationOpt>,
+
+    /// don't cache schemas, not even in memory
+    #[options(no_short)]
+    no_cache: bool,
+
+    /// suppress all output but errors
+    quiet: bool,
+
+    /// when to colorize output (one of `auto', `always', or `never'; auto: on a terminal, unless NO_COLOR is set)
+    #[options(no_short, meta = "WHEN", default = "auto")]
+    color: highlight::ColorChoice,
+
+    /// log output format (one of `text' or `json'; spans of registry requests are also exported
+    /// over OTLP/HTTP if OTEL_EXPORTER_OTLP_ENDPOINT or OTEL_EXPORTER_OTLP_TRACES_ENDPOINT is set)
+    #[options(no_short, meta = "FORMAT", default = "text")]
+    log_format: telemetry::LogFormat,
+
     /// command
     #[options(command, required)]
     command: Option<Cmd>,
 }
 
+impl Settings {
+    /// Creates a Schema Registry client for the given URLs, configured according to the global options.
+    /// A single URL that isn't one may name a profile in the configuration file instead,
+    /// which supplies the actual URL and the credentials (unless given on the command line).
+    fn client(&self, urls: Vec<String>) -> anyhow::Result<registry::Client> {
+        let (urls, profile) = match urls.as_slice() {
+            [name] if !name.contains("://") => {
+                let mut config = config::Config::load()?;
+                let profile = config
+                    .profiles
+                    .remove(name)
+                    .ok_or_else(|| anyhow::format_err!("no such URL or profile: {}", name))?;
+
+                debug!("profile {}: {:?}", name, profile);
+
+                (vec![profile.url.clone()], Some(profile))
+            }
+            _ => (urls, None),
+        };
+
+        let flavor = self
+            .registry_flavor
+            .or_else(|| profile.as_ref().and_then(|profile| profile.registry_flavor))
+            .unwrap_or_default();
+
+        let group = self.apicurio_group.clone().or_else(|| {
+            profile
+                .as_ref()
+                .and_then(|profile| profile.apicurio_group.clone())
+        });
+
+        let mut client = registry::Client::new(urls)?.with_flavor(flavor);
+
+        // Headers given on the command line override those of the same name in the profile.
+        let mut headers = HeaderMap::new();
+        for (name, value) in profile.iter().flat_map(|profile| &profile.headers) {
+            let HeaderOpt(name, value) = HeaderOpt::new(name, value)?;
+            headers.append(name, value);
+        }
+
+        for HeaderOpt(name, _) in &self.header {
+            headers.remove(name);
+        }
+
+        for HeaderOpt(name, value) in &self.header {
+            headers.append(name.clone(), value.clone());
+        }
+
+        if !headers.is_empty() {
+            client = client.with_headers(headers);
+        }
+
+        if let Some(rate_limit) = self.rate_limit {
+            anyhow::ensure!(rate_limit > 0, "`--rate-limit' must be at least 1");
+            client = client.with_rate_limit(rate_limit);
+        }
+
+        if self.no_cache {
+            anyhow::ensure!(
+                self.cache_dir.is_none() && self.cache_ttl.is_none(),
+                "`--no-cache' cannot be combined with `--cache-dir' or `--cache-ttl'"
+            );
+        } else {
+            let ttl = self.cache_ttl.map_or(cache::DEFAULT_TTL, |ttl| ttl.0);
+            client = client.with_cache(cache::Cache::new(self.cache_dir.clone(), ttl)?);
+        }
+
+        if let Some(group) = group {
+            anyhow::ensure!(
+                flavor == registry::Flavor::Apicurio,
+                "an Apicurio group requires `--registry-flavor apicurio'"
+            );
+
+            client = client.with_group(group);
+        }
+
+        if let Some(context) = &self.context {
+            anyhow::ensure!(
+                flavor != registry::Flavor::Apicurio,
+                "Apicurio does not support schema contexts"
+            );
+
+            client = client.with_context(context);
+        }
+
+        let api_key = self
+            .api_key
+            .clone()
+            .or_else(|| profile.as_ref().and_then(|profile| profile.api_key.clone()));
+
+        if let Some(api_key) = api_key {
+            anyhow::ensure!(
+                self.oauth_token_url.is_none(),
+                "`--oauth-token-url' cannot be combined with an API key"
+            );
+
+            let api_secret = match &self.api_secret {
+                Some(secret) => secret.0.clone(),
+                None => env::var(API_SECRET_VAR)
+                    .ok()
+                    .or_else(|| profile.and_then(|profile| profile.api_secret))
+                    .ok_or_else(|| {
+                        anyhow::format_err!(
+                            "an API key requires `--api-secret' or {}",
+                            API_SECRET_VAR
+                        )
+                    })?,
+            };
+
+            return Ok(client.with_basic_auth(api_key, api_secret));
+        }
+
+        anyhow::ensure!(
+            self.api_secret.is_none(),
+            "`--api-secret' requires `--api-key'"
+        );
+
+        let token_url = match &self.oauth_token_url {
+            Some(token_url) => token_url,
+            None => {
+                anyhow::ensure!(
+                    self.oauth_client_id.is_none()
+                        && self.oauth_client_secret.is_none()
+                        && self.oauth_scope.is_none(),
+                    "OAuth options require `--oauth-token-url'"
+                );
+
+                return Ok(client);
+            }
+        };
+
+        let client_id = self.oauth_client_id.clone().ok_or_else(|| {
+            anyhow::Error::msg("`--oauth-token-url' requires `--oauth-client-id'")
+        })?;
+
+        let client_secret = match &self.oauth_client_secret {
+            Some(secret) => secret.0.clone(),
+            None => env::var(OAUTH_CLIENT_SECRET_VAR).map_err(|_| {
+                anyhow::format_err!(
+                    "`--oauth-token-url' requires `--oauth-client-secret' or {}",
+                    OAUTH_CLIENT_SECRET_VAR
+                )
+            })?,
+        };
+
+        let oauth = oauth::OAuth::new(
+            token_url,
+            client_id,
+            client_secret,
+            self.oauth_scope.clone(),
+        )?;
+
+        Ok(client.with_oauth(oauth))
+    }
+}
+
+/// Environment variable to take the API secret from, to keep it off the command line.
+const API_SECRET_VAR: &str = "KSRT_API_SECRET";
+
+/// Environment variable to take the OAuth client secret from, to keep it off the command line.
+const OAUTH_CLIENT_SECRET_VAR: &str = "KSRT_OAUTH_CLIENT_SECRET";
+
+/// Command-line value that is kept out of debug output.
+struct Secret(String);
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret(***)")
+    }
+}
+
+impl FromStr for Secret {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_owned()))
+    }
+}
+
+/// HTTP header given on the command line as `NAME:VALUE'.
+/// Its value is marked sensitive so that it's kept out of debug output, as it may well be a credential.
+#[derive(Debug)]
+struct HeaderOpt(HeaderName, HeaderValue);
+
+impl HeaderOpt {
+    fn new(name: &str, value: &str) -> anyhow::Result<Self> {
+        let name = HeaderName::from_str(name.trim())
+            .map_err(|e| anyhow::format_err!("invalid header name {}: {}", name, e))?;
+
+        let mut value = HeaderValue::from_str(value.trim())
+            .map_err(|e| anyhow::format_err!("invalid value for header {}: {}", name, e))?;
+
+        value.set_sensitive(true);
+        Ok(Self(name, value))
+    }
+}
+
+impl FromStr for HeaderOpt {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow::format_err!("expected NAME:VALUE: {}", s))?;
+
+        Self::new(name, value)
+    }
+}
+
 #[derive(Debug, Options)]
 enum Cmd {
+    /// reconcile the Kafka Schema Registry with a manifest
+    Apply(apply::ApplySettings),
+
+    /// benchmark schema registration and retrieval
+    Bench(bench::BenchSettings),
+
+    /// report the changes between two versions of a schema that break compatibility
+    Breaking(breaking::BreakingSettings),
+
+    /// browse the Kafka Schema Registry interactively
+    Browse(browse::BrowseSettings),
+
+    /// verify a schema against the Kafka Schema Registry (for CI)
+    CiCheck(ci_check::CiCheckSettings),
+
+    /// compare the subjects of two Kafka Schema Registries
+    Compare(compare::CompareSettings),
+
+    /// list schema contexts
+    Context(context::ContextSettings),
+
+    /// show the differences between two versions of a subject
+    Diff(diff::DiffSettings),
+
+    /// manage schema exporters (schema linking)
+    Exporter(exporter::ExporterSettings),
+
+    /// compute the fingerprint of a schema file
+    Fingerprint(fingerprint::FingerprintSettings),
+
     /// retrieve an existing schema
     Get(GetSettings),
 
+    /// print the changelog of a subject's fields across its versions
+    History(history::HistorySettings),
+
+    /// show or change the external links of a subject
+    Links(links::LinksSettings),
+
+    /// list subjects
+    List(list::ListSettings),
+
+    /// show, change, or search by the metadata of subjects
+    Meta(meta::MetaSettings),
+
     /// post a schema to the Kafka Schema Registry
     Post(PostSettings),
+
+    /// list the subjects that reference a given subject
+    RefsTo(refs_to::RefsToSettings),
+
+    /// replace the latest version of a subject with a new schema
+    Replace(replace::ReplaceSettings),
+
+    /// generate random example records for a subject's schema
+    Sample(sample::SampleSettings),
+
+    /// search all subjects for a message type or field
+    Search(search::SearchSettings),
+
+    /// summarize the contents of the Kafka Schema Registry
+    Stats(stats::StatsSettings),
+
+    /// watch subjects for new versions
+    Watch(watch::WatchSettings),
 }
 
 /// Retrieve an existing schema from the Kafka Schema Registry.
@@ -83,7 +604,7 @@ struct GetSettings {
     #[options(meta = "NAME")]
     topic: Option<String>,
 
-    /// whether the schema is for the topic key (vs. value)
+    /// whether the schema is for the topic key (vs. value; only with `--topic' alone)
     #[options(short = "k")]
     topic_key: bool,
 
@@ -91,66 +612,133 @@ struct GetSettings {
     #[options(meta = "NAME")]
     record: Option<String>,
 
-    /// Schema Registry URL(s) (required)
-    #[options(free, required)]
-    schema_registry_url: Vec<String>,
-}
-
-/// Post a schema to the Kafka Schema Registry.
-/// This will create a new schema version for the given subject *unless*
-/// there is already an existing version with the equivalent schema.
-#[derive(Debug, Options)]
-struct PostSettings {
-    /// print usage and exit
-    help: bool,
+    /// recursively retrieve all references and print the full reference tree
+    #[options(no_short)]
+    resolve_refs: bool,
 
-    /// schema type (required; one of `avro', `json', or `protobuf')
-    #[options(long = "type", meta = "TYPE", required, short = "T")]
-    schema_type: SchemaTypeOpt,
+    /// include the schema of each reference (requires `--resolve-refs')
+    #[options(no_short)]
+    with_schemas: bool,
 
-    /// topic name (required unless `--record' is specified)
-    #[options(meta = "NAME")]
-    topic: Option<String>,
+    /// print as JSON (requires `--resolve-refs')
+    #[options(no_short)]
+    json: bool,
 
-    /// whether the schema is for the topic key (vs. value)
-    #[options(short = "k")]
-    topic_key: bool,
+    /// print nothing but the schema text (e.g., to redirect it to a file)
+    #[options(no_short)]
+    raw: bool,
 
-    /// record name (required unless `--topic' is specified)
-    #[options(meta = "NAME")]
-    record: Option<String>,
+    /// version to retrieve (a number, `latest', or `latest~N' for N versions before the latest; default: latest)
+    #[options(no_short, meta = "VERSION")]
+    version: Option<diff::VersionSpec>,
 
-    /// schema file (required)
-    #[options(required)]
-    file: PathBuf,
+    /// include soft-deleted versions (e.g., to recover one deleted by accident)
+    #[options(no_short)]
+    deleted: bool,
 
-    /// include directory for any references (optional; could be multiple)
-    #[options(meta = "DIR")]
-    include: Vec<PathBuf>,
+    /// retrieve the latest version with this metadata property instead (could be multiple)
+    #[options(no_short, meta = "KEY=VALUE")]
+    with_metadata: Vec<MetadataProperty>,
 
-    /// strip comments
+    /// write the schema and all its references to an include tree, and print a protoc command line for it (protobuf only)
     #[options(no_short)]
-    strip_comments: bool,
+    references_as_includes: bool,
+
+    /// directory to write the include tree to (requires `--references-as-includes'; default: a new temporary one)
+    #[options(no_short, meta = "DIR")]
+    include_dir: Option<PathBuf>,
 
-    /// Schema Registry URL(s) (required)
+    /// compile the include tree into a descriptor set with all imports instead (requires `--references-as-includes')
+    #[options(no_short, meta = "FILE")]
+    descriptor_out: Option<PathBuf>,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
     #[options(free, required)]
     schema_registry_url: Vec<String>,
 }
 
-#[derive(Debug)]
+schema_file_settings! {
+    /// Post a schema to the Kafka Schema Registry.
+    /// This will create a new schema version for the given subject *unless*
+    /// there is already an existing version with the equivalent schema.
+    #[derive(Debug, Options)]
+    struct PostSettings {
+        file: "schema file, or a directory of them to post all at once (required; `-' to read from standard input)",
+
+        /// only print the order the schemas in the directory would be registered in, and their subjects
+        #[options(no_short)]
+        plan_only: bool,
+
+        /// register each (transitive) dependency under its own subject first, unless already registered as is
+        #[options(no_short)]
+        register_deps: bool,
+
+        /// global id to register the schema with (requires IMPORT mode and `--version')
+        #[options(no_short, meta = "ID")]
+        id: Option<u32>,
+
+        /// version to register the schema as (requires IMPORT mode and `--id')
+        #[options(no_short, meta = "VERSION")]
+        version: Option<u32>,
+
+        /// abort unless the compatibility level in effect for the subject is at least this strict
+        #[options(no_short, meta = "LEVEL")]
+        require_compat: Option<registry::Compatibility>,
+
+        /// set the subject's compatibility level to the required one instead of aborting (with `--require-compat')
+        #[options(no_short)]
+        set_compat: bool,
+
+        /// JSON file with metadata (tags, properties, sensitive) to register along with the schema
+        #[options(no_short, meta = "FILE")]
+        metadata: Option<PathBuf>,
+
+        /// JSON file with the rule set (domain and migration rules) to register along with the schema
+        #[options(no_short, meta = "FILE")]
+        rule_set: Option<PathBuf>,
+    }
+}
+
+impl PostSettings {
+    /// Loads the metadata and rule set files, if any.
+    fn contract(&self) -> anyhow::Result<registry::Contract> {
+        fn load<T: DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+            let text = fs::read_to_string(path)
+                .map_err(|e| anyhow::format_err!("failed to read {}: {}", path.display(), e))?;
+
+            serde_json::from_str(&text)
+                .map_err(|e| anyhow::format_err!("invalid {}: {}", path.display(), e))
+        }
+
+        let metadata = self.metadata.as_deref().map(load).transpose()?;
+        let rule_set = match &self.rule_set {
+            Some(path) => {
+                let rule_set: serde_json::Value = load(path)?;
+                anyhow::ensure!(
+                    rule_set.is_object(),
+                    "invalid {}: rule set must be a JSON object",
+                    path.display()
+                );
+
+                Some(rule_set)
+            }
+            None => None,
+        };
+
+        Ok(registry::Contract { metadata, rule_set })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "lowercase")]
 #[non_exhaustive]
 enum SchemaTypeOpt {
+    #[default]
     Avro,
     Json,
     Protobuf,
 }
 
-impl Default for SchemaTypeOpt {
-    fn default() -> Self {
-        Self::Avro
-    }
-}
-
 impl fmt::Display for SchemaTypeOpt {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -186,6 +774,132 @@ impl From<SchemaTypeOpt> for SchemaType {
     }
 }
 
+/// File name that stands for standard input.
+const STDIN: &str = "-";
+
+/// Schema file along with the options that control how it is posted.
+#[derive(Debug, Eq, Hash, PartialEq)]
+struct SchemaSource {
+    schema_type: SchemaTypeOpt,
+    file: PathBuf,
+    /// path the schema would have if it's read from standard input
+    name: Option<PathBuf>,
+    include: Vec<PathBuf>,
+    strip_comments: bool,
+    /// strip protobuf options, except those named in `keep_options'
+    strip_options: bool,
+    keep_options: Vec<String>,
+    inline_refs: bool,
+    /// subjects to post referenced files under, keyed by import name (instead of the derived default)
+    reference_subjects: BTreeMap<String, String>,
+    /// how the subjects of referenced protobuf files are derived
+    ref_strategy: RefStrategy,
+    /// topic to derive the subjects of referenced protobuf files from (with the `topic-record' strategy)
+    ref_topic: Option<String>,
+    /// how references to Avro types defined in other files are named
+    ref_name: avro::RefName,
+    /// local directories protobuf imports under a registry prefix are resolved from
+    include_map: Vec<IncludeMap>,
+    /// shell commands the schema text (and that of each reference) is piped through, in order
+    transform: Vec<String>,
+}
+
+impl SchemaSource {
+    fn normalize_options(&self) -> normalize::Options {
+        normalize::Options {
+            strip_comments: self.strip_comments,
+            strip_options: self.strip_options,
+            keep_options: self.keep_options.clone(),
+        }
+    }
+}
+
+/// Local directory that protobuf imports starting with a prefix are resolved from (e.g., `vendor/common=company/common'),
+/// so that they keep the import paths (and reference names) the registry expects.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct IncludeMap {
+    dir: PathBuf,
+    prefix: String,
+}
+
+impl IncludeMap {
+    /// The protoc include (`-I') argument mapping the prefix onto the directory.
+    fn proto_path(&self) -> anyhow::Result<PathBuf> {
+        let dir = self
+            .dir
+            .canonicalize()
+            .map_err(|e| anyhow::format_err!("error mapping {}: {}", self.dir.display(), e))?;
+
+        Ok(PathBuf::from(format!("{}={}", self.prefix, dir.display())))
+    }
+
+    /// The local path of an import, if it's under the prefix.
+    fn resolve(&self, name: &str) -> Option<PathBuf> {
+        name.strip_prefix(&self.prefix)
+            .and_then(|rest| rest.strip_prefix('/'))
+            .map(|rest| self.dir.join(rest))
+    }
+}
+
+impl FromStr for IncludeMap {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (dir, prefix) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow::format_err!("expected DIR=PREFIX: {}", s))?;
+
+        let prefix = prefix.trim_matches('/');
+        anyhow::ensure!(
+            !dir.is_empty() && !prefix.is_empty(),
+            "missing directory or prefix: {}",
+            s
+        );
+
+        Ok(Self {
+            dir: PathBuf::from(dir),
+            prefix: prefix.to_owned(),
+        })
+    }
+}
+
+/// How the subject of a referenced protobuf file is derived (unless given explicitly).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+enum RefStrategy {
+    /// The full name of the file's first message (e.g., `shared.Address').
+    #[default]
+    Record,
+    /// The topic followed by the full name of the file's first message (e.g., `orders-shared.Address').
+    TopicRecord,
+    /// The file's import path (e.g., `shared/address.proto').
+    Filename,
+}
+
+impl fmt::Display for RefStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Record => write!(f, "record"),
+            Self::TopicRecord => write!(f, "topic-record"),
+            Self::Filename => write!(f, "filename"),
+        }
+    }
+}
+
+impl FromStr for RefStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let strategy = match s {
+            "record" => Self::Record,
+            "topic-record" => Self::TopicRecord,
+            "filename" => Self::Filename,
+            _ => anyhow::bail!("unsupported reference subject strategy: {}", s),
+        };
+
+        Ok(strategy)
+    }
+}
+
 fn parse_protos<P>(protos: &[P], includes: &[P]) -> anyhow::Result<FileDescriptorSet>
 where
     P: AsRef<Path>,
@@ -225,95 +939,209 @@ where
     Ok(descriptor_set)
 }
 
-fn get_protobuf_references(
-    fd: &FileDescriptorProto,
-    fds: &[FileDescriptorProto],
-    schemas: &HashMap<String, String>,
-) -> anyhow::Result<Vec<SuppliedReference>> {
-    fd.dependency
-        .iter()
-        .try_fold(Vec::with_capacity(fd.dependency.len()), |mut refs, name| {
-            let fd = fds
-                .iter()
-                .find(|&dep| dep.name.as_deref() == Some(name))
-                .ok_or_else(|| {
-                    anyhow::format_err!("failed to locate file for dependency: {}", name)
-                })?;
+/// Builds the reference graph of protobuf files, resolving each file only once however many files import it.
+/// References are ordered by name, so that the same files always make for the same graph.
+struct ProtobufReferences<'a> {
+    fds: &'a [FileDescriptorProto],
+    schemas: &'a HashMap<String, String>,
+    source: &'a SchemaSource,
+    resolved: HashMap<String, SuppliedReference>,
+}
 
-            let mt = fd.message_type.first().ok_or_else(|| {
-                anyhow::format_err!("failed to locate a top-level message type in: {}", name)
-            })?;
+impl ProtobufReferences<'_> {
+    fn of(&mut self, fd: &FileDescriptorProto) -> anyhow::Result<Vec<SuppliedReference>> {
+        let names: BTreeSet<_> = fd.dependency.iter().collect();
+        names.into_iter().map(|name| self.resolve(name)).collect()
+    }
 
-            let subject: Vec<_> = fd.package.iter().cloned().chain(mt.name.clone()).collect();
-            let subject = subject.join(".");
-            let schema = schemas
-                .get(name)
-                .ok_or_else(|| anyhow::format_err!("failed to locate schema for: {}", name))?;
+    fn resolve(&mut self, name: &str) -> anyhow::Result<SuppliedReference> {
+        if let Some(reference) = self.resolved.get(name) {
+            return Ok(reference.clone());
+        }
 
-            let sup_ref = SuppliedReference {
-                name: name.clone(),
-                subject,
-                schema: schema.clone(),
-                references: get_protobuf_references(fd, fds, schemas)?,
-            };
+        let fds = self.fds;
+        let fd = fds
+            .iter()
+            .find(|&dep| dep.name.as_deref() == Some(name))
+            .ok_or_else(|| anyhow::format_err!("failed to locate file for dependency: {}", name))?;
 
-            refs.push(sup_ref);
-            Ok(refs)
-        })
-}
+        let subject = protobuf_subject(self.source, name, fd)?;
 
-fn strip_comments(schema: String, ml_comment: &Regex, sl_comment: &Regex) -> String {
-    let mut buf = None;
-    let mut ml_start = 0;
+        let schema = self
+            .schemas
+            .get(name)
+            .ok_or_else(|| anyhow::format_err!("failed to locate schema for: {}", name))?;
 
-    for ml_match in ml_comment.find_iter(&schema) {
-        let lines = &schema[ml_start..ml_match.start()];
-        ml_start = ml_match.end();
+        let reference = SuppliedReference {
+            name: name.to_owned(),
+            subject,
+            schema: schema.clone(),
+            references: self.of(fd)?,
+        };
 
-        let buf = buf.get_or_insert_with(|| String::with_capacity(schema.len()));
+        self.resolved.insert(name.to_owned(), reference.clone());
+        Ok(reference)
+    }
+}
 
-        let mut sl_start = 0;
-        for sl_match in sl_comment.find_iter(lines) {
-            buf.push_str(&lines[sl_start..sl_match.start()]);
-            sl_start = sl_match.end();
-        }
+/// Subject a protobuf file is posted under as a reference, per the source's explicit subjects and strategy.
+fn protobuf_subject(
+    source: &SchemaSource,
+    name: &str,
+    fd: &FileDescriptorProto,
+) -> anyhow::Result<String> {
+    let subject = match (source.reference_subjects.get(name), source.ref_strategy) {
+        (Some(subject), _) => subject.clone(),
+        (None, RefStrategy::Filename) => name.to_owned(),
+        (None, strategy) => {
+            let mt = fd.message_type.first().ok_or_else(|| {
+                anyhow::format_err!("failed to locate a top-level message type in: {}", name)
+            })?;
 
-        buf.push_str(&lines[sl_start..]);
-    }
+            let record: Vec<_> = fd.package.iter().cloned().chain(mt.name.clone()).collect();
+            match (strategy, &source.ref_topic) {
+                (RefStrategy::TopicRecord, Some(topic)) => {
+                    format!("{}-{}", topic, record.join("."))
+                }
+                _ => record.join("."),
+            }
+        }
+    };
 
-    let lines = &schema[ml_start..];
-    let mut sl_start = 0;
-    for sl_match in sl_comment.find_iter(lines) {
-        buf.get_or_insert_with(|| String::with_capacity(lines.len()))
-            .push_str(&lines[sl_start..sl_match.start()]);
-        sl_start = sl_match.end();
-    }
+    Ok(subject)
+}
 
-    if let Some(mut buf) = buf {
-        buf.push_str(&lines[sl_start..]);
-        buf
-    } else {
-        schema
+fn get_protobuf_references(
+    fd: &FileDescriptorProto,
+    fds: &[FileDescriptorProto],
+    schemas: &HashMap<String, String>,
+    source: &SchemaSource,
+) -> anyhow::Result<Vec<SuppliedReference>> {
+    ProtobufReferences {
+        fds,
+        schemas,
+        source,
+        resolved: HashMap::new(),
     }
+    .of(fd)
 }
 
-fn post_avro_schema(_settings: &PostSettings) -> anyhow::Result<SuppliedSchema> {
-    unimplemented!("avro schema not yet supported")
-}
+fn post_schema_file(
+    source: &SchemaSource,
+    schema_type: SchemaType,
+) -> anyhow::Result<SuppliedSchema> {
+    anyhow::ensure!(
+        source.reference_subjects.is_empty(),
+        "references are not yet supported for {} schemas",
+        source.schema_type
+    );
 
-fn post_json_schema(_settings: &PostSettings) -> anyhow::Result<SuppliedSchema> {
-    unimplemented!("json schema not yet supported")
-}
+    let options = source.normalize_options();
 
-fn post_protobuf_schema(settings: &PostSettings) -> anyhow::Result<SuppliedSchema> {
-    let file = settings.file.canonicalize()?;
+    let schema = fs::read_to_string(&source.file)?;
+    let schema = SuppliedSchema {
+        name: None,
+        schema: normalize::normalize(&schema, &schema_type, &options),
+        schema_type,
+        references: Vec::new(),
+    };
+
+    Ok(schema)
+}
+
+fn post_avro_schema(source: &SchemaSource) -> anyhow::Result<SuppliedSchema> {
+    let options = source.normalize_options();
+
+    let schema = fs::read_to_string(&source.file)?;
+    let schema = SuppliedSchema {
+        name: None,
+        schema: normalize::normalize(&schema, &SchemaType::Avro, &options),
+        schema_type: SchemaType::Avro,
+        references: avro::get_references(
+            &source.file,
+            &source.include,
+            &source.reference_subjects,
+            &source.ref_name,
+            &options,
+        )?,
+    };
+
+    Ok(schema)
+}
+
+fn post_json_schema(source: &SchemaSource) -> anyhow::Result<SuppliedSchema> {
+    if !source.inline_refs {
+        return post_schema_file(source, SchemaType::Json);
+    }
+
+    let options = source.normalize_options();
+
+    let schema = json_schema::bundle(&source.file, &source.include)?;
+    let schema = SuppliedSchema {
+        name: None,
+        schema: normalize::normalize(&schema, &SchemaType::Json, &options),
+        schema_type: SchemaType::Json,
+        references: Vec::new(),
+    };
+
+    Ok(schema)
+}
+
+/// Removes the root descriptor from a set: the one whose name resolves to the given (canonical) file
+/// at one of its candidate locations (i.e., against the includes, as protoc does).
+fn root_descriptor<F>(
+    files: &mut Vec<FileDescriptorProto>,
+    file: &Path,
+    locations: F,
+) -> anyhow::Result<FileDescriptorProto>
+where
+    F: Fn(&str) -> Vec<PathBuf>,
+{
+    let candidates: Vec<_> = files
+        .iter()
+        .enumerate()
+        .filter(|(_, fd)| {
+            locations(fd.name())
+                .iter()
+                .any(|path| path.canonicalize().ok().as_deref() == Some(file))
+        })
+        .map(|(i, _)| i)
+        .collect();
+
+    let root_fd = match candidates.as_slice() {
+        [i] => files.remove(*i),
+        [] => anyhow::bail!(
+            "failed to locate file descriptor for {} among: {}",
+            file.display(),
+            files
+                .iter()
+                .map(|fd| fd.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        _ => anyhow::bail!(
+            "ambiguous file descriptor for {}; candidates: {}",
+            file.display(),
+            candidates
+                .iter()
+                .map(|i| files[*i].name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    Ok(root_fd)
+}
+
+fn post_protobuf_schema(settings: &SchemaSource) -> anyhow::Result<SuppliedSchema> {
+    let file = settings.file.canonicalize()?;
     let mut includes = Vec::with_capacity(settings.include.len() + 1);
 
     if let Some(dir) = file.parent() {
         includes.push(dir.canonicalize()?);
     }
 
-    let mut includes = settings
+    let includes = settings
         .include
         .iter()
         .try_fold(includes, |mut includes, path| {
@@ -322,16 +1150,36 @@ fn post_protobuf_schema(settings: &PostSettings) -> anyhow::Result<SuppliedSchem
             Ok::<_, anyhow::Error>(includes)
         })?;
 
-    let mut fd_set = parse_protos(&[file.clone()], &includes)?;
+    let proto_paths = settings
+        .include_map
+        .iter()
+        .map(IncludeMap::proto_path)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut fd_set = parse_protos(
+        std::slice::from_ref(&file),
+        &[includes.as_slice(), &proto_paths].concat(),
+    )?;
 
     trace!("fd set: {:#?}", fd_set);
 
-    includes.push(protoc_include().canonicalize()?);
+    // Candidate locations of an imported file, in the order protoc searches them.
+    let protoc_include = protoc_include().canonicalize()?;
+    let locations = |name: &str| -> Vec<PathBuf> {
+        includes
+            .iter()
+            .map(|path| path.join(name))
+            .chain(
+                settings
+                    .include_map
+                    .iter()
+                    .filter_map(|map| map.resolve(name)),
+            )
+            .chain(Some(protoc_include.join(name)))
+            .collect()
+    };
 
-    let ml_comment = RegexBuilder::new(r"/\*.*?\*/")
-        .dot_matches_new_line(true)
-        .build()?;
-    let sl_comment = RegexBuilder::new(r"//.*$").multi_line(true).build()?;
+    let options = settings.normalize_options();
 
     let schemas = fd_set.file.iter().try_fold(
         HashMap::with_capacity(fd_set.file.len()),
@@ -341,17 +1189,14 @@ fn post_protobuf_schema(settings: &PostSettings) -> anyhow::Result<SuppliedSchem
                 .clone()
                 .ok_or_else(|| anyhow::Error::msg("missing name in file descriptor"))?;
 
-            let path = includes
-                .iter()
-                .find(|&path| path.join(&name).is_file())
+            let path = locations(&name)
+                .into_iter()
+                .find(|path| path.is_file())
                 .ok_or_else(|| anyhow::format_err!("failed to locate file for: {}", name))?;
 
-            let mut schema = fs::read_to_string(path.join(&name))?;
-
-            if settings.strip_comments {
-                // As of now, the Schema Registry doesn't exclude comments when comparing versions!
-                schema = strip_comments(schema, &ml_comment, &sl_comment);
-            }
+            // As of now, the Schema Registry doesn't exclude comments when comparing versions!
+            let schema = fs::read_to_string(path)?;
+            let schema = normalize::normalize(&schema, &SchemaType::Protobuf, &options);
 
             schemas.insert(name, schema);
             Ok::<_, anyhow::Error>(schemas)
@@ -360,111 +1205,686 @@ fn post_protobuf_schema(settings: &PostSettings) -> anyhow::Result<SuppliedSchem
 
     trace!("schemas: {:#?}", schemas);
 
-    let root_fd = fd_set
-        .file
-        .pop()
-        .expect("at least one file descriptor in the set");
-
-    assert_eq!(
-        file.file_name()
-            .expect("supplied filename refers to a regular file")
-            .to_string_lossy(),
-        root_fd.name.as_deref().expect("file descriptor has a name")
-    );
+    let root_fd = root_descriptor(&mut fd_set.file, &file, locations)?;
 
     let schema = SuppliedSchema {
         name: None,
         schema_type: SchemaType::Protobuf,
-        schema: fs::read_to_string(file)?,
-        references: get_protobuf_references(&root_fd, &fd_set.file, &schemas)?,
+        schema: normalize::normalize(&fs::read_to_string(file)?, &SchemaType::Protobuf, &options),
+        references: get_protobuf_references(&root_fd, &fd_set.file, &schemas, settings)?,
     };
 
     Ok(schema)
 }
 
+fn supplied_schema(source: &SchemaSource) -> anyhow::Result<SuppliedSchema> {
+    if source.file == Path::new(STDIN) {
+        return supplied_stdin_schema(source);
+    }
+
+    anyhow::ensure!(
+        !source.inline_refs || source.schema_type == SchemaTypeOpt::Json,
+        "inlining references is only supported for JSON schemas"
+    );
+
+    anyhow::ensure!(
+        source.ref_strategy == RefStrategy::default()
+            || source.schema_type == SchemaTypeOpt::Protobuf,
+        "reference subject strategies are only supported for protobuf schemas"
+    );
+
+    anyhow::ensure!(
+        source.ref_name == avro::RefName::default() || source.schema_type == SchemaTypeOpt::Avro,
+        "reference naming is only supported for Avro schemas"
+    );
+
+    anyhow::ensure!(
+        !source.strip_options || source.schema_type == SchemaTypeOpt::Protobuf,
+        "stripping options is only supported for protobuf schemas"
+    );
+
+    anyhow::ensure!(
+        source.keep_options.is_empty() || source.strip_options,
+        "`--keep-option' requires `--strip-options'"
+    );
+
+    anyhow::ensure!(
+        source.include_map.is_empty() || source.schema_type == SchemaTypeOpt::Protobuf,
+        "include maps are only supported for protobuf schemas"
+    );
+
+    anyhow::ensure!(
+        (source.ref_strategy == RefStrategy::TopicRecord) == source.ref_topic.is_some(),
+        "`--ref-strategy topic-record' and `--ref-topic' must be given together"
+    );
+
+    let schema = match source.schema_type {
+        SchemaTypeOpt::Avro => post_avro_schema(source)?,
+        SchemaTypeOpt::Json => post_json_schema(source)?,
+        SchemaTypeOpt::Protobuf => post_protobuf_schema(source)?,
+    };
+
+    let name = source
+        .file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    transform::transform(&source.transform, &name, schema)
+}
+
+/// Copies the schema from standard input into a temporary file named after `--name' and posts that,
+/// with the directory the schema would have been in added to the includes so that imports resolve.
+fn supplied_stdin_schema(source: &SchemaSource) -> anyhow::Result<SuppliedSchema> {
+    let name = match (&source.name, source.schema_type) {
+        (Some(name), _) => name.clone(),
+        (None, SchemaTypeOpt::Protobuf) => PathBuf::from("stdin.proto"),
+        (None, _) => PathBuf::from("stdin"),
+    };
+
+    let file_name = name
+        .file_name()
+        .ok_or_else(|| anyhow::format_err!("invalid schema name: {}", name.display()))?;
+
+    let mut schema = String::new();
+    io::stdin()
+        .read_to_string(&mut schema)
+        .map_err(|e| anyhow::format_err!("error reading schema from standard input: {}", e))?;
+
+    let tmp = tempfile::Builder::new().prefix("ksrt-stdin").tempdir()?;
+    let file = tmp.path().join(file_name);
+    fs::write(&file, schema)?;
+
+    let include = name
+        .parent()
+        .filter(|dir| dir.is_dir())
+        .map(Path::to_path_buf)
+        .into_iter()
+        .chain(source.include.iter().cloned())
+        .collect();
+
+    supplied_schema(&SchemaSource {
+        schema_type: source.schema_type,
+        file,
+        name: None,
+        include,
+        strip_comments: source.strip_comments,
+        strip_options: source.strip_options,
+        keep_options: source.keep_options.clone(),
+        inline_refs: source.inline_refs,
+        reference_subjects: source.reference_subjects.clone(),
+        ref_strategy: source.ref_strategy,
+        ref_topic: source.ref_topic.clone(),
+        ref_name: source.ref_name.clone(),
+        include_map: source.include_map.clone(),
+        transform: source.transform.clone(),
+    })
+}
+
+/// Finds the files with the given extension in a directory and its subdirectories, in order of their paths.
+fn find_files(dir: &Path, extension: &str, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            find_files(&path, extension, files)?;
+        } else if path.extension().is_some_and(|ext| ext == extension) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Duration given on the command line as a number with a unit (e.g., `500ms', `30s', `5m', or `1h').
+#[derive(Clone, Copy, Debug)]
+struct DurationOpt(Duration);
+
+impl fmt::Display for DurationOpt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}ms", self.0.as_millis())
+    }
+}
+
+impl FromStr for DurationOpt {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pos = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+
+        let (value, unit) = s.split_at(pos);
+        let value: u64 = value
+            .parse()
+            .map_err(|_| anyhow::format_err!("invalid duration: {}", s))?;
+
+        let duration = match unit {
+            "ms" => Duration::from_millis(value),
+            "s" => Duration::from_secs(value),
+            "m" => Duration::from_secs(value * 60),
+            "h" => Duration::from_secs(value * 60 * 60),
+            _ => anyhow::bail!(
+                "invalid duration (expected a unit of ms, s, m, or h): {}",
+                s
+            ),
+        };
+
+        anyhow::ensure!(!duration.is_zero(), "duration must not be zero");
+        Ok(Self(duration))
+    }
+}
+
+/// Schema metadata property given on the command line.
+#[derive(Debug)]
+struct MetadataProperty(String, String);
+
+impl FromStr for MetadataProperty {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow::format_err!("expected KEY=VALUE: {}", s))?;
+
+        Ok(Self(key.to_owned(), value.to_owned()))
+    }
+}
+
+/// Schema retrieved along with all its (transitive) references.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResolvedSchema {
+    subject: String,
+    version: Option<u32>,
+    id: u32,
+    schema_type: String,
+    schema: String,
+    references: Vec<ResolvedReference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<registry::Metadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_set: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct ResolvedReference {
+    name: String,
+    subject: String,
+    version: u32,
+    id: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema: Option<String>,
+    references: Vec<ResolvedReference>,
+}
+
+fn resolve_references(
+    client: &registry::Client,
+    references: &[RegisteredReference],
+    with_schemas: bool,
+    path: &mut Vec<String>,
+) -> anyhow::Result<Vec<ResolvedReference>> {
+    references
+        .iter()
+        .map(|reference| {
+            let key = format!("{}/{}", reference.subject, reference.version);
+            anyhow::ensure!(
+                !path.contains(&key),
+                "circular reference: {} -> {}",
+                path.join(" -> "),
+                key
+            );
+
+            let schema = client
+                .get_schema(&reference.subject, &reference.version.to_string())
+                .map_err(|e| e.context(format!("error retrieving reference {}", key)))?;
+
+            path.push(key);
+            let references = resolve_references(client, &schema.references, with_schemas, path)?;
+            path.pop();
+
+            Ok(ResolvedReference {
+                name: reference.name.clone(),
+                subject: reference.subject.clone(),
+                version: reference.version,
+                id: schema.id,
+                schema: if with_schemas {
+                    Some(schema.schema)
+                } else {
+                    None
+                },
+                references,
+            })
+        })
+        .collect()
+}
+
+fn print_resolved_reference(reference: &ResolvedReference, depth: usize) {
+    let indent = "\t".repeat(depth);
+    outln!("{}name: {}", indent, reference.name);
+    outln!("{}subject: {}", indent, reference.subject);
+    outln!("{}version: {}", indent, reference.version);
+    outln!("{}id: {}", indent, reference.id);
+
+    if let Some(schema) = &reference.schema {
+        outln!("{}schema:", indent);
+        for line in schema.lines() {
+            outln!("{}\t{}", indent, line);
+        }
+    }
+
+    if !reference.references.is_empty() {
+        outln!("{}references:", indent);
+        for reference in &reference.references {
+            print_resolved_reference(reference, depth + 1);
+        }
+    }
+}
+
 fn print_reference(reference: RegisteredReference) {
-    println!("\tname: {}", reference.name);
-    println!("\tsubject: {}", reference.subject);
-    println!("\tversion: {}", reference.version);
+    outln!("\tname: {}", reference.name);
+    outln!("\tsubject: {}", reference.subject);
+    outln!("\tversion: {}", reference.version);
 }
 
 fn print_schema(schema: RegisteredSchema) {
-    println!("id: {}", schema.id);
-    match schema.schema_type {
-        SchemaType::Avro => println!("type: avro"),
-        SchemaType::Json => println!("type: json"),
-        SchemaType::Protobuf => println!("type: protobuf"),
-        SchemaType::Other(value) => println!("type: {}", value),
+    outln!("id: {}", schema.id);
+    match &schema.schema_type {
+        SchemaType::Avro => outln!("type: avro"),
+        SchemaType::Json => outln!("type: json"),
+        SchemaType::Protobuf => outln!("type: protobuf"),
+        SchemaType::Other(value) => outln!("type: {}", value),
     }
 
-    println!("schema:");
-    for line in schema.schema.lines() {
-        println!("\t{}", line);
+    outln!("schema:");
+    for line in highlight::schema_lines(&schema.schema, &schema.schema_type) {
+        outln!("\t{}", line);
     }
 
     if !schema.references.is_empty() {
-        println!("references:");
+        outln!("references:");
         for reference in schema.references {
             print_reference(reference);
         }
     }
 }
 
-fn run_get(sr_settings: SrSettings, subject: SubjectNameStrategy) -> anyhow::Result<()> {
-    let reg = get_schema_by_subject(&sr_settings, &subject)
-        .map_err(|e| anyhow::format_err!("error retrieving schema: {}", e))?;
+fn print_contract(contract: &registry::Contract) {
+    if let Some(metadata) = &contract.metadata {
+        outln!("metadata:");
+        if !metadata.properties.is_empty() {
+            outln!("\tproperties:");
+            for (key, value) in &metadata.properties {
+                outln!("\t\t{}: {}", key, value);
+            }
+        }
+
+        if !metadata.tags.is_empty() {
+            outln!("\ttags:");
+            for (path, tags) in &metadata.tags {
+                outln!("\t\t{}: {}", path, tags.join(", "));
+            }
+        }
+
+        if !metadata.sensitive.is_empty() {
+            outln!("\tsensitive: {}", metadata.sensitive.join(", "));
+        }
+    }
+
+    if let Some(rule_set) = &contract.rule_set {
+        outln!("rule set:");
+        let rule_set = serde_json::to_string_pretty(rule_set).expect("JSON value");
+        for line in rule_set.lines() {
+            outln!("\t{}", line);
+        }
+    }
+}
+
+fn run_get(
+    client: registry::Client,
+    subject: String,
+    settings: &GetSettings,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        settings.resolve_refs || !(settings.with_schemas || settings.json),
+        "`--with-schemas' and `--json' require `--resolve-refs'"
+    );
+
+    anyhow::ensure!(
+        !(settings.raw && settings.resolve_refs),
+        "`--raw' cannot be combined with `--resolve-refs'"
+    );
+
+    anyhow::ensure!(
+        settings.version.is_none() || settings.with_metadata.is_empty(),
+        "`--version' cannot be combined with `--with-metadata'"
+    );
+
+    anyhow::ensure!(
+        settings.references_as_includes
+            || (settings.include_dir.is_none() && settings.descriptor_out.is_none()),
+        "`--include-dir' and `--descriptor-out' require `--references-as-includes'"
+    );
+
+    anyhow::ensure!(
+        !(settings.references_as_includes && (settings.raw || settings.resolve_refs)),
+        "`--references-as-includes' cannot be combined with `--raw' or `--resolve-refs'"
+    );
+
+    let client = if settings.deleted {
+        client.with_deleted()
+    } else {
+        client
+    };
+
+    let version = match settings.version {
+        Some(spec) => {
+            let versions = client
+                .get_versions(&subject)
+                .map_err(|e| e.context("error retrieving versions"))?;
+
+            anyhow::ensure!(!versions.is_empty(), "subject not found: {}", subject);
+            spec.resolve(&versions)?.to_string()
+        }
+        None => "latest".to_owned(),
+    };
+
+    let reg = if settings.with_metadata.is_empty() {
+        client.get_schema(&subject, &version)
+    } else {
+        let properties: Vec<_> = settings
+            .with_metadata
+            .iter()
+            .map(|MetadataProperty(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        client.get_schema_with_metadata(&subject, &properties)
+    }
+    .map_err(|e| e.context("error retrieving schema"))?;
 
     debug!("registered schema: {:#?}", reg);
 
+    if settings.references_as_includes {
+        return write_include_tree(&client, &subject, &reg, settings);
+    }
+
+    if settings.raw {
+        out!("{}", reg.schema);
+        if !reg.schema.ends_with('\n') {
+            outln!();
+        }
+
+        return Ok(());
+    }
+
+    let contract = reg.contract();
+
+    if !settings.resolve_refs {
+        print_schema(reg.into());
+        print_contract(&contract);
+        return Ok(());
+    }
+
+    let references = resolve_references(
+        &client,
+        &reg.references,
+        settings.with_schemas,
+        &mut vec![subject.clone()],
+    )?;
+
+    let version = reg.version;
+    let reg = RegisteredSchema {
+        references: Vec::new(),
+        ..RegisteredSchema::from(reg)
+    };
+
+    if settings.json {
+        let resolved = ResolvedSchema {
+            subject,
+            version,
+            id: reg.id,
+            schema_type: registry::schema_type_name(&reg.schema_type).to_owned(),
+            schema: reg.schema,
+            references,
+            metadata: contract.metadata,
+            rule_set: contract.rule_set,
+        };
+
+        outln!("{}", serde_json::to_string_pretty(&resolved)?);
+        return Ok(());
+    }
+
     print_schema(reg);
 
+    if !references.is_empty() {
+        outln!("references:");
+        for reference in &references {
+            print_resolved_reference(reference, 1);
+        }
+    }
+
+    print_contract(&contract);
+
+    Ok(())
+}
+
+/// Quotes a command line argument for the shell, unless it's safe as is.
+fn shell_quote(arg: &str) -> Cow<'_, str> {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "/._-+=:,@%".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        Cow::Borrowed(arg)
+    } else {
+        Cow::Owned(format!("'{}'", arg.replace('\'', r"'\''")))
+    }
+}
+
+/// Writes a protobuf schema and its (transitive) references to a directory, each under its import name
+/// (and the schema itself under its subject's name), so that protoc can compile it with that as the include.
+/// Then either prints the protoc command line for it, or runs protoc to write a descriptor set.
+fn write_include_tree(
+    client: &registry::Client,
+    subject: &str,
+    reg: &registry::Schema,
+    settings: &GetSettings,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        reg.schema_type() == SchemaType::Protobuf,
+        "`--references-as-includes' is only supported for protobuf schemas"
+    );
+
+    let name = if subject.ends_with(".proto") {
+        subject.to_owned()
+    } else {
+        format!("{}.proto", subject)
+    };
+
+    let name = search::relative_path(&name)?;
+
+    // Without an explicit directory, the tree is only kept once it's complete, and if it's needed after this.
+    let mut tmp = None;
+    let dir = match &settings.include_dir {
+        Some(dir) => {
+            fs::create_dir_all(dir)?;
+            dir.clone()
+        }
+        None => {
+            let dir = tempfile::Builder::new().prefix("ksrt-includes").tempdir()?;
+            tmp.insert(dir).path().to_path_buf()
+        }
+    };
+
+    let file = dir.join(name);
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(&file, &reg.schema)?;
+    search::write_references(client, &reg.references, &dir, &mut HashSet::new())?;
+
+    let out = match &settings.descriptor_out {
+        Some(out) => out,
+        None => {
+            outln!(
+                "protoc -I {} {}",
+                shell_quote(&dir.to_string_lossy()),
+                shell_quote(&file.to_string_lossy())
+            );
+
+            if let Some(tmp) = tmp {
+                let _ = tmp.keep();
+            }
+
+            return Ok(());
+        }
+    };
+
+    let output = Command::new(protoc())
+        .arg("--include_imports")
+        .arg("--include_source_info")
+        .arg("-o")
+        .arg(out)
+        .arg("-I")
+        .arg(&dir)
+        .arg("-I")
+        .arg(protoc_include())
+        .arg(&file)
+        .output()?;
+
+    anyhow::ensure!(
+        output.status.success(),
+        "protoc failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    outln!("wrote descriptor set for {} to {}", subject, out.display());
+    Ok(())
+}
+
+/// Ensures the compatibility level in effect for the subject is at least as strict as required,
+/// setting the subject's own level to the required one if so asked.
+fn require_compatibility(
+    client: &registry::Client,
+    subject: &str,
+    required: registry::Compatibility,
+    set: bool,
+) -> anyhow::Result<()> {
+    let effective = client
+        .get_effective_compatibility(subject)
+        .map_err(|e| e.context("error retrieving compatibility level"))?;
+
+    if effective.satisfies(required) {
+        return Ok(());
+    }
+
+    anyhow::ensure!(
+        set,
+        "compatibility level of {} is {}, which is weaker than the required {}\n(use `--set-compat' to set it to {})",
+        subject,
+        effective,
+        required,
+        required
+    );
+
+    client
+        .set_compatibility(subject, required)
+        .map_err(|e| e.context("error setting compatibility level"))?;
+
+    info!(
+        "changed compatibility level of {} from {} to {}",
+        subject, effective, required
+    );
+
     Ok(())
 }
 
 fn run_post(
-    sr_settings: SrSettings,
+    client: registry::Client,
     subject: String,
     schema: SuppliedSchema,
+    contract: registry::Contract,
+    register_deps: bool,
+    import: Option<registry::Import>,
 ) -> anyhow::Result<()> {
-    let reg = post_schema(&sr_settings, subject, schema)
-        .map_err(|e| anyhow::format_err!("error posting schema: {}", e))?;
+    let (id, references) = if let Some(import) = import {
+        client
+            .register_imported(&subject, &schema, &contract, import)
+            .map_err(|e| e.context("error importing schema"))?
+    } else if register_deps {
+        let (id, references, dependencies) = client
+            .register_with_dependencies(&subject, &schema, &contract)
+            .map_err(|e| e.context("error posting schema"))?;
+
+        for dependency in dependencies {
+            outln!(
+                "{} {} (version {})",
+                if dependency.registered {
+                    "registered"
+                } else {
+                    "unchanged"
+                },
+                dependency.subject,
+                dependency.version
+            );
+        }
+
+        (id, references)
+    } else {
+        client
+            .register(&subject, &schema, &contract)
+            .map_err(|e| e.context("error posting schema"))?
+    };
+
+    let reg = RegisteredSchema {
+        id,
+        schema_type: schema.schema_type,
+        schema: schema.schema,
+        references,
+    };
 
     debug!("registered schema: {:#?}", reg);
 
     print_schema(reg);
+    print_contract(&contract);
 
     Ok(())
 }
 
-fn schema_registry_settings_from_settings(
-    urls: impl IntoIterator<Item = String>,
-) -> anyhow::Result<SrSettings> {
-    let mut urls = urls.into_iter();
-    let mut builder = SrSettings::new_builder(urls.next().expect("at least one item"));
-    urls.for_each(|url| {
-        builder.add_url(url);
-    });
-
-    builder
-        .build()
-        .map_err(|e| anyhow::format_err!("error configuring schema registry client: {:?}", e))
-}
-
+/// Determines the subject name strategy from the topic and/or record name:
+///
+/// * topic only: `<topic>-value', or `<topic>-key' with `--topic-key'
+/// * record only: `<record>'
+/// * topic and record: `<topic>-<record>'
+///
+/// The key flag only makes sense with a topic alone, so any other use of it is rejected
+/// rather than silently resolving to an unexpected subject.
 fn subject_name_strategy_from_settings(
     topic: Option<String>,
     record: Option<String>,
     topic_key: bool,
 ) -> anyhow::Result<SubjectNameStrategy> {
-    let sns = if let Some(topic) = topic {
-        if let Some(record) = record {
+    let sns = match (topic, record) {
+        (Some(topic), None) => SubjectNameStrategy::TopicNameStrategy(topic, topic_key),
+        (Some(topic), Some(record)) => {
+            anyhow::ensure!(
+                !topic_key,
+                "`--topic-key' cannot be combined with `--record'; the subject is `{}-{}' either way",
+                topic,
+                record
+            );
+
             SubjectNameStrategy::TopicRecordNameStrategy(topic, record)
-        } else {
-            SubjectNameStrategy::TopicNameStrategy(topic, topic_key)
         }
-    } else if let Some(record) = record {
-        SubjectNameStrategy::RecordNameStrategy(record)
-    } else {
-        anyhow::bail!("either `--topic' or `--record' are required");
+        (None, Some(record)) => {
+            anyhow::ensure!(
+                !topic_key,
+                "`--topic-key' requires `--topic'; the subject of `--record' alone is `{}'",
+                record
+            );
+
+            SubjectNameStrategy::RecordNameStrategy(record)
+        }
+        (None, None) => anyhow::bail!("either `--topic' or `--record' are required"),
     };
 
     Ok(sns)
@@ -483,39 +1903,158 @@ fn version() -> String {
     )
 }
 
-fn main() -> anyhow::Result<()> {
-    TracingSubscriber::builder()
-        .with_env_filter(TracingEnvFilter::from_default_env())
-        .init();
+/// Exits with one of the codes in `exit' on failure (e.g., 2 if a subject is not found).
+fn main() {
+    let settings: Settings = Options::parse_args_default_or_exit();
+    QUIET.store(settings.quiet, Ordering::Relaxed);
+    highlight::init(settings.color);
 
-    info!("{}", version());
+    let log_format = settings.log_format;
+    let res = telemetry::init(log_format, settings.quiet).and_then(|()| run(settings));
+    throttle::print_summary();
+    telemetry::shutdown();
 
-    let settings: Settings = Options::parse_args_default_or_exit();
+    if let Err(e) = res {
+        exit::report(&e, log_format);
+        process::exit(exit::code(&e));
+    }
+}
+
+fn run(mut settings: Settings) -> anyhow::Result<()> {
+    info!("{}", version());
 
     debug!("args: {:#?}", settings);
 
-    let cmd = settings.command.expect("command");
+    let cmd = settings.command.take().expect("command");
+    let connect = |urls| settings.client(urls);
     match cmd {
+        Cmd::Apply(settings) => {
+            let client = connect(settings.schema_registry_url.clone())?;
+
+            apply::run_apply(client, &settings)
+        }
+
+        Cmd::Bench(settings) => {
+            let client = connect(settings.schema_registry_url.clone())?;
+
+            bench::run_bench(client, &settings)
+        }
+
+        Cmd::Breaking(settings) => {
+            let client = match settings.old_from_subject {
+                Some(_) => Some(connect(settings.schema_registry_url.clone())?),
+                None => None,
+            };
+
+            breaking::run_breaking(client, &settings)
+        }
+
+        Cmd::Browse(settings) => {
+            let client = connect(settings.schema_registry_url.clone())?;
+
+            browse::run_browse(client, &settings)
+        }
+
+        Cmd::CiCheck(settings) => {
+            let client = connect(settings.schema_registry_url.clone())?;
+
+            ci_check::run_ci_check(client, &settings)
+        }
+
+        Cmd::Compare(settings) => {
+            let (url_a, url_b) = settings.urls()?;
+            let client_a = connect(vec![url_a.to_owned()])?;
+            let client_b = connect(vec![url_b.to_owned()])?;
+
+            compare::run_compare(client_a, client_b, &settings)
+        }
+
+        Cmd::Context(settings) => {
+            let client = connect(settings.schema_registry_url().to_vec())?;
+
+            context::run_context(client, settings)
+        }
+
+        Cmd::Diff(settings) => {
+            let client = connect(settings.schema_registry_url.clone())?;
+
+            diff::run_diff(client, &settings)
+        }
+
+        Cmd::Exporter(settings) => {
+            let client = connect(settings.schema_registry_url().to_vec())?;
+
+            exporter::run_exporter(client, settings)
+        }
+
+        Cmd::Fingerprint(settings) => {
+            let client = match settings.verify_against_subject {
+                Some(_) => Some(connect(settings.schema_registry_url.clone())?),
+                None => None,
+            };
+
+            fingerprint::run_fingerprint(client, &settings)
+        }
+
         Cmd::Get(settings) => {
-            let sr_settings = schema_registry_settings_from_settings(settings.schema_registry_url)?;
+            let client = connect(settings.schema_registry_url.clone())?;
 
             let sns = subject_name_strategy_from_settings(
-                settings.topic,
-                settings.record,
+                settings.topic.clone(),
+                settings.record.clone(),
                 settings.topic_key,
             )?;
 
-            run_get(sr_settings, sns)
+            let subject = get_subject(&sns)
+                .map_err(|e| anyhow::format_err!("error determining subject: {:?}", e))?;
+
+            run_get(client, subject, &settings)
+        }
+
+        Cmd::History(settings) => {
+            let client = connect(settings.schema_registry_url.clone())?;
+
+            history::run_history(client, &settings)
+        }
+
+        Cmd::Links(settings) => {
+            let client = connect(settings.schema_registry_url().to_vec())?;
+
+            links::run_links(client, settings)
+        }
+
+        Cmd::List(settings) => {
+            let client = connect(settings.schema_registry_url.clone())?;
+
+            list::run_list(client, &settings)
+        }
+
+        Cmd::Meta(settings) => {
+            let client = connect(settings.schema_registry_url().to_vec())?;
+
+            meta::run_meta(client, settings)
         }
 
         Cmd::Post(settings) => {
-            let schema = match settings.schema_type {
-                SchemaTypeOpt::Avro => post_avro_schema(&settings)?,
-                SchemaTypeOpt::Json => post_json_schema(&settings)?,
-                SchemaTypeOpt::Protobuf => post_protobuf_schema(&settings)?,
-            };
+            if settings.file.is_dir() {
+                let client = if settings.plan_only {
+                    None
+                } else {
+                    Some(connect(settings.schema_registry_url.clone())?)
+                };
+
+                return plan::run_post_dir(client, &settings);
+            }
 
-            let sr_settings = schema_registry_settings_from_settings(settings.schema_registry_url)?;
+            anyhow::ensure!(
+                !settings.plan_only,
+                "`--plan-only' requires a directory of schemas (`--file')"
+            );
+
+            let schema = supplied_schema(&SchemaSource::from(&settings))?;
+            let contract = settings.contract()?;
+
+            let client = connect(settings.schema_registry_url)?;
 
             let sns = subject_name_strategy_from_settings(
                 settings.topic,
@@ -526,7 +2065,273 @@ fn main() -> anyhow::Result<()> {
             let subject = get_subject(&sns)
                 .map_err(|e| anyhow::format_err!("error determining subject: {:?}", e))?;
 
-            run_post(sr_settings, subject, schema)
+            let import = match (settings.id, settings.version) {
+                (Some(id), Some(version)) => Some(registry::Import { id, version }),
+                (None, None) => None,
+                _ => anyhow::bail!("`--id' and `--version' must be given together"),
+            };
+
+            anyhow::ensure!(
+                import.is_none() || !settings.register_deps,
+                "`--id' and `--version' cannot be combined with `--register-deps'"
+            );
+
+            if let Some(required) = settings.require_compat {
+                require_compatibility(&client, &subject, required, settings.set_compat)?;
+            } else {
+                anyhow::ensure!(
+                    !settings.set_compat,
+                    "`--set-compat' requires `--require-compat'"
+                );
+            }
+
+            run_post(
+                client,
+                subject,
+                schema,
+                contract,
+                settings.register_deps,
+                import,
+            )
+        }
+
+        Cmd::RefsTo(settings) => {
+            let client = connect(settings.schema_registry_url.clone())?;
+
+            refs_to::run_refs_to(client, &settings)
+        }
+
+        Cmd::Replace(settings) => {
+            let client = connect(settings.schema_registry_url.clone())?;
+
+            replace::run_replace(client, &settings)
+        }
+
+        Cmd::Sample(settings) => {
+            let client = connect(settings.schema_registry_url.clone())?;
+
+            sample::run_sample(client, &settings)
+        }
+
+        Cmd::Search(settings) => {
+            let client = connect(settings.schema_registry_url.clone())?;
+
+            search::run_search(client, &settings)
+        }
+
+        Cmd::Stats(settings) => {
+            let client = connect(settings.schema_registry_url.clone())?;
+
+            stats::run_stats(client, &settings)
+        }
+
+        Cmd::Watch(settings) => {
+            let client = connect(settings.schema_registry_url.clone())?;
+
+            watch::run_watch(client, &settings)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(file: &Path) -> SchemaSource {
+        SchemaSource {
+            schema_type: SchemaTypeOpt::Protobuf,
+            file: file.to_path_buf(),
+            name: None,
+            include: Vec::new(),
+            strip_comments: false,
+            strip_options: false,
+            keep_options: Vec::new(),
+            inline_refs: false,
+            reference_subjects: BTreeMap::new(),
+            ref_strategy: RefStrategy::Filename,
+            ref_topic: None,
+            ref_name: avro::RefName::default(),
+            include_map: Vec::new(),
+            transform: Vec::new(),
+        }
+    }
+
+    fn fd(name: &str, dependency: &[&str]) -> FileDescriptorProto {
+        FileDescriptorProto {
+            name: Some(name.to_owned()),
+            dependency: dependency.iter().map(|&dep| dep.to_owned()).collect(),
+            ..Default::default()
         }
     }
+
+    /// Renders the reference tree as it would be posted: names, subjects, and schemas, in order.
+    fn describe(references: &[SuppliedReference]) -> String {
+        references
+            .iter()
+            .map(|r| {
+                format!(
+                    "{}={}:{}[{}]",
+                    r.name,
+                    r.subject,
+                    r.schema,
+                    describe(&r.references)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    #[test]
+    fn orders_and_dedups_diamond_references() {
+        let schemas: HashMap<_, _> = ["b.proto", "c.proto", "d.proto"]
+            .iter()
+            .map(|&name| (name.to_owned(), format!("// {}", name)))
+            .collect();
+
+        let source = source(Path::new("a.proto"));
+        let references = |root_deps: &[&str]| {
+            let fds = [
+                fd("d.proto", &[]),
+                fd("c.proto", &["d.proto"]),
+                fd("b.proto", &["d.proto"]),
+            ];
+
+            let mut graph = ProtobufReferences {
+                fds: &fds,
+                schemas: &schemas,
+                source: &source,
+                resolved: HashMap::new(),
+            };
+
+            let references = graph.of(&fd("a.proto", root_deps)).unwrap();
+            assert_eq!(graph.resolved.len(), 3);
+            describe(&references)
+        };
+
+        let expected = "b.proto=b.proto:// b.proto[d.proto=d.proto:// d.proto[]],\
+                        c.proto=c.proto:// c.proto[d.proto=d.proto:// d.proto[]]";
+
+        assert_eq!(references(&["c.proto", "b.proto"]), expected);
+        assert_eq!(references(&["b.proto", "c.proto"]), expected);
+    }
+
+    #[test]
+    fn parses_include_maps() {
+        let map: IncludeMap = "vendor/common=/company/common/".parse().unwrap();
+        assert_eq!(
+            map,
+            IncludeMap {
+                dir: PathBuf::from("vendor/common"),
+                prefix: "company/common".to_owned(),
+            }
+        );
+
+        for invalid in ["vendor/common", "=company", "vendor=", "vendor=/"] {
+            assert!(invalid.parse::<IncludeMap>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn resolves_imports_under_the_prefix_only() {
+        let map: IncludeMap = "vendor/common=company/common".parse().unwrap();
+        assert_eq!(
+            map.resolve("company/common/money.proto"),
+            Some(PathBuf::from("vendor/common/money.proto"))
+        );
+        assert_eq!(map.resolve("company/commonwealth/money.proto"), None);
+        assert_eq!(map.resolve("other/money.proto"), None);
+    }
+
+    #[test]
+    fn selects_root_descriptor_by_path() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("orders")).unwrap();
+        fs::create_dir_all(dir.path().join("shared")).unwrap();
+
+        // The root shares its file name with the file it imports.
+        let root = "syntax = \"proto3\";\npackage orders;\nimport \"shared/event.proto\";\nmessage Order { shared.Event event = 1; }\n";
+        let shared = "syntax = \"proto3\";\npackage shared;\nmessage Event {}\n";
+        fs::write(dir.path().join("orders/event.proto"), root).unwrap();
+        fs::write(dir.path().join("shared/event.proto"), shared).unwrap();
+
+        let mut source = source(&dir.path().join("orders/event.proto"));
+        source.include.push(dir.path().to_path_buf());
+
+        let schema = post_protobuf_schema(&source).unwrap();
+        assert_eq!(schema.schema, root);
+        assert_eq!(
+            describe(&schema.references),
+            format!("shared/event.proto=shared/event.proto:{}[]", shared)
+        );
+    }
+
+    #[test]
+    fn selects_root_descriptor_wherever_it_is_in_the_set() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path().canonicalize().unwrap();
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::write(dir.join("a/x.proto"), "").unwrap();
+        fs::write(dir.join("dep.proto"), "").unwrap();
+
+        let file = dir.join("a/x.proto");
+        let includes = [dir.join("a"), dir.clone()];
+        let locations = |name: &str| -> Vec<PathBuf> {
+            includes.iter().map(|include| include.join(name)).collect()
+        };
+
+        let mut files = vec![fd("x.proto", &["dep.proto"]), fd("dep.proto", &[])];
+        let root = root_descriptor(&mut files, &file, locations).unwrap();
+        assert_eq!(root.name(), "x.proto");
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name(), "dep.proto");
+
+        let mut files = vec![fd("dep.proto", &[])];
+        let err = root_descriptor(&mut files, &file, locations).unwrap_err();
+        assert!(err.to_string().contains("failed to locate"), "{}", err);
+
+        // Both names resolve to the file, one through each include.
+        let mut files = vec![fd("a/x.proto", &[]), fd("x.proto", &[])];
+        let err = root_descriptor(&mut files, &file, locations).unwrap_err();
+        assert!(err.to_string().contains("ambiguous"), "{}", err);
+        assert_eq!(files.len(), 2);
+    }
+
+    #[test]
+    fn validates_subject_name_strategy_settings() {
+        let subject = |topic: Option<&str>, record: Option<&str>, topic_key| {
+            subject_name_strategy_from_settings(
+                topic.map(str::to_owned),
+                record.map(str::to_owned),
+                topic_key,
+            )
+            .map(|sns| get_subject(&sns).unwrap())
+        };
+
+        assert_eq!(
+            subject(Some("orders"), None, false).unwrap(),
+            "orders-value"
+        );
+        assert_eq!(subject(Some("orders"), None, true).unwrap(), "orders-key");
+        assert_eq!(
+            subject(Some("orders"), Some("com.example.Order"), false).unwrap(),
+            "orders-com.example.Order"
+        );
+        assert_eq!(
+            subject(None, Some("com.example.Order"), false).unwrap(),
+            "com.example.Order"
+        );
+
+        let err = subject(Some("orders"), Some("com.example.Order"), true).unwrap_err();
+        assert!(
+            err.to_string().contains("`orders-com.example.Order'"),
+            "{}",
+            err
+        );
+
+        let err = subject(None, Some("com.example.Order"), true).unwrap_err();
+        assert!(err.to_string().contains("requires `--topic'"), "{}", err);
+
+        assert!(subject(None, None, false).is_err());
+        assert!(subject(None, None, true).is_err());
+    }
 }
@@ -0,0 +1,99 @@
+use crate::{
+    registry::Compatibility,
+    SchemaTypeOpt,
+};
+
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+/// Declarative description of the subjects that should exist in the Schema Registry.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub subjects: Vec<Entry>,
+}
+
+/// A single subject in the manifest.
+/// The subject name is either given explicitly or derived from the topic and/or record name,
+/// just like with the `post' command.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Entry {
+    pub subject: Option<String>,
+    pub topic: Option<String>,
+    #[serde(default)]
+    pub topic_key: bool,
+    pub record: Option<String>,
+    #[serde(rename = "type")]
+    pub schema_type: SchemaTypeOpt,
+    pub file: PathBuf,
+    #[serde(default)]
+    pub include: Vec<PathBuf>,
+    #[serde(default)]
+    pub strip_comments: bool,
+    /// Strip options, except those named in `keep-options' (protobuf only).
+    #[serde(default)]
+    pub strip_options: bool,
+    #[serde(default)]
+    pub keep_options: Vec<String>,
+    /// Bundle referenced files into the schema (JSON only).
+    #[serde(default)]
+    pub inline_refs: bool,
+    /// Subjects to register referenced files under, keyed by import name (protobuf) or type name (Avro).
+    #[serde(default)]
+    pub references: BTreeMap<String, String>,
+    pub compatibility: Option<Compatibility>,
+    /// Prefix of the subject name (set by the workspace package, if any).
+    #[serde(skip)]
+    pub subject_prefix: String,
+}
+
+impl Manifest {
+    /// Loads the manifest from the given file.
+    /// Relative paths in the manifest are resolved against the manifest's directory.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        Self::load_with_type(path, None)
+    }
+
+    /// Loads the manifest from the given file, with a default for subjects that don't specify their schema type.
+    pub fn load_with_type(
+        path: &Path,
+        default_type: Option<SchemaTypeOpt>,
+    ) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| anyhow::format_err!("failed to read {}: {}", path.display(), e))?;
+
+        let invalid = |e| anyhow::format_err!("invalid manifest {}: {}", path.display(), e);
+        let mut value: Value = serde_yaml::from_str(&text).map_err(invalid)?;
+        if let (Some(default_type), Some(Value::Sequence(subjects))) =
+            (default_type, value.get_mut("subjects"))
+        {
+            for entry in subjects.iter_mut().filter_map(Value::as_mapping_mut) {
+                if !entry.contains_key("type") {
+                    entry.insert("type".into(), default_type.to_string().into());
+                }
+            }
+        }
+
+        let mut manifest: Self = serde_yaml::from_value(value).map_err(invalid)?;
+
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        for entry in &mut manifest.subjects {
+            entry.file = base.join(&entry.file);
+            for include in &mut entry.include {
+                *include = base.join(&*include);
+            }
+        }
+
+        Ok(manifest)
+    }
+}
@@ -0,0 +1,324 @@
+use crate::{
+    registry::{
+        self,
+        Client,
+        Contract,
+        Error,
+    },
+    MetadataProperty,
+};
+
+use gumdrop::Options;
+use log::*;
+use schema_registry_converter::schema_registry_common::get_subject;
+use serde::Serialize;
+
+use std::str::FromStr;
+
+/// Show or change the metadata (properties and tags) of a subject's latest version,
+/// or find the subjects with versions that have given metadata properties.
+/// Changing the metadata registers a new version with the same schema.
+#[derive(Debug, Options)]
+pub struct MetaSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// command
+    #[options(command, required)]
+    command: Option<MetaCmd>,
+}
+
+#[derive(Debug, Options)]
+enum MetaCmd {
+    /// show the metadata of a subject
+    Get(GetSettings),
+
+    /// add, change, or remove metadata properties and tags of a subject
+    Set(SetSettings),
+
+    /// list the subjects with versions that have the given metadata properties
+    Find(FindSettings),
+}
+
+/// Show the metadata of a subject's latest version.
+#[derive(Debug, Options)]
+struct GetSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// topic name (required unless `--record' is specified)
+    #[options(meta = "NAME")]
+    topic: Option<String>,
+
+    /// whether the subject is for the topic key (vs. value; only with `--topic' alone)
+    #[options(short = "k")]
+    topic_key: bool,
+
+    /// record name (required unless `--topic' is specified)
+    #[options(meta = "NAME")]
+    record: Option<String>,
+
+    /// print as JSON
+    #[options(no_short)]
+    json: bool,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    schema_registry_url: Vec<String>,
+}
+
+/// Add, change, or remove metadata properties and tags of a subject's latest version.
+#[derive(Debug, Options)]
+struct SetSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// topic name (required unless `--record' is specified)
+    #[options(meta = "NAME")]
+    topic: Option<String>,
+
+    /// whether the subject is for the topic key (vs. value; only with `--topic' alone)
+    #[options(short = "k")]
+    topic_key: bool,
+
+    /// record name (required unless `--topic' is specified)
+    #[options(meta = "NAME")]
+    record: Option<String>,
+
+    /// property to add or change (could be multiple)
+    #[options(short = "p", meta = "KEY=VALUE")]
+    property: Vec<MetadataProperty>,
+
+    /// key of a property to remove (could be multiple)
+    #[options(no_short, meta = "KEY")]
+    remove: Vec<String>,
+
+    /// tag to add to a field path (e.g., `**.ssn=PII'; could be multiple)
+    #[options(no_short, meta = "PATH=TAG")]
+    tag: Vec<Tag>,
+
+    /// tag to remove from a field path (could be multiple)
+    #[options(no_short, meta = "PATH=TAG")]
+    untag: Vec<Tag>,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    schema_registry_url: Vec<String>,
+}
+
+/// List the subjects with versions that have the given metadata properties, along with the latest such version.
+#[derive(Debug, Options)]
+struct FindSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// property the version must have (required; could be multiple)
+    #[options(short = "p", meta = "KEY=VALUE")]
+    property: Vec<MetadataProperty>,
+
+    /// print as JSON
+    #[options(no_short)]
+    json: bool,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    schema_registry_url: Vec<String>,
+}
+
+/// Tag of a field path given on the command line.
+#[derive(Debug)]
+struct Tag {
+    path: String,
+    tag: String,
+}
+
+impl FromStr for Tag {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, tag) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow::format_err!("expected PATH=TAG: {}", s))?;
+
+        anyhow::ensure!(
+            !path.is_empty() && !tag.is_empty(),
+            "missing path or tag: {}",
+            s
+        );
+
+        Ok(Self {
+            path: path.to_owned(),
+            tag: tag.to_owned(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Match {
+    subject: String,
+    version: Option<u32>,
+    id: u32,
+}
+
+fn subject(
+    topic: Option<String>,
+    record: Option<String>,
+    topic_key: bool,
+) -> anyhow::Result<String> {
+    let sns = crate::subject_name_strategy_from_settings(topic, record, topic_key)?;
+    get_subject(&sns).map_err(|e| anyhow::format_err!("error determining subject: {:?}", e))
+}
+
+fn run_get(client: Client, settings: GetSettings) -> anyhow::Result<()> {
+    let subject = subject(settings.topic, settings.record, settings.topic_key)?;
+    let schema = client
+        .get_schema(&subject, "latest")
+        .map_err(|e| e.context("error retrieving schema"))?;
+
+    let metadata = schema.metadata.unwrap_or_default();
+    if settings.json {
+        outln!("{}", serde_json::to_string_pretty(&metadata)?);
+        return Ok(());
+    }
+
+    crate::print_contract(&Contract {
+        metadata: Some(metadata),
+        rule_set: None,
+    });
+
+    Ok(())
+}
+
+fn run_set(client: Client, settings: SetSettings) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !(settings.property.is_empty()
+            && settings.remove.is_empty()
+            && settings.tag.is_empty()
+            && settings.untag.is_empty()),
+        "at least one `--property', `--remove', `--tag', or `--untag' is required"
+    );
+
+    let subject = subject(settings.topic, settings.record, settings.topic_key)?;
+    let mut schema = client
+        .get_schema(&subject, "latest")
+        .map_err(|e| e.context("error retrieving schema"))?;
+
+    let mut metadata = schema.metadata.clone().unwrap_or_default();
+    for key in &settings.remove {
+        if metadata.properties.remove(key).is_none() {
+            warn!("{} has no metadata property {}", subject, key);
+        }
+    }
+
+    for MetadataProperty(key, value) in settings.property {
+        metadata.properties.insert(key, value);
+    }
+
+    for Tag { path, tag } in &settings.untag {
+        let tags = metadata.tags.get_mut(path);
+        let removed = tags.is_some_and(|tags| {
+            let len = tags.len();
+            tags.retain(|t| t != tag);
+            tags.len() < len
+        });
+
+        if !removed {
+            warn!("{} has no tag {} on {}", subject, tag, path);
+        }
+
+        if metadata.tags.get(path).is_some_and(Vec::is_empty) {
+            metadata.tags.remove(path);
+        }
+    }
+
+    for Tag { path, tag } in settings.tag {
+        let tags = metadata.tags.entry(path).or_default();
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    if schema.metadata.clone().unwrap_or_default() == metadata {
+        outln!("metadata of {} unchanged", subject);
+        return Ok(());
+    }
+
+    schema.metadata = Some(metadata);
+    let id = client
+        .register_schema(&subject, &schema)
+        .map_err(|e| e.context(format!("error updating metadata of {}", subject)))?;
+
+    outln!("updated metadata of {} (id {})", subject, id);
+    crate::print_contract(&Contract {
+        metadata: schema.metadata,
+        rule_set: None,
+    });
+
+    Ok(())
+}
+
+fn run_find(client: Client, settings: FindSettings) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !settings.property.is_empty(),
+        "at least one `--property' is required"
+    );
+
+    let properties: Vec<_> = settings
+        .property
+        .into_iter()
+        .map(|MetadataProperty(key, value)| (key, value))
+        .collect();
+
+    let mut matches = Vec::new();
+    for subject in client.list_subjects()? {
+        match client.get_schema_with_metadata(&subject, &properties) {
+            Ok(schema) => matches.push(Match {
+                subject,
+                version: schema.version,
+                id: schema.id,
+            }),
+            Err(e)
+                if Error::has_code(
+                    &e,
+                    &[registry::SUBJECT_NOT_FOUND, registry::SCHEMA_NOT_FOUND],
+                ) =>
+            {
+                debug!("no version of {} has the metadata", subject);
+            }
+            Err(e) => return Err(e.context(format!("error searching {}", subject))),
+        }
+    }
+
+    if settings.json {
+        outln!("{}", serde_json::to_string_pretty(&matches)?);
+        return Ok(());
+    }
+
+    for m in matches {
+        match m.version {
+            Some(version) => outln!("{}\t{}\t{}", m.subject, version, m.id),
+            None => outln!("{}\t-\t{}", m.subject, m.id),
+        }
+    }
+
+    Ok(())
+}
+
+impl MetaSettings {
+    /// The Schema Registry URL(s) given to the subcommand.
+    pub fn schema_registry_url(&self) -> &[String] {
+        match self.command.as_ref().expect("command") {
+            MetaCmd::Get(settings) => &settings.schema_registry_url,
+            MetaCmd::Set(settings) => &settings.schema_registry_url,
+            MetaCmd::Find(settings) => &settings.schema_registry_url,
+        }
+    }
+}
+
+pub fn run_meta(client: Client, settings: MetaSettings) -> anyhow::Result<()> {
+    match settings.command.expect("command") {
+        MetaCmd::Get(settings) => run_get(client, settings),
+        MetaCmd::Set(settings) => run_set(client, settings),
+        MetaCmd::Find(settings) => run_find(client, settings),
+    }
+}
@@ -0,0 +1,501 @@
+use schema_registry_converter::schema_registry_common::SchemaType;
+use serde_json::Value;
+
+/// Options that control schema normalization.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Remove comments, including Avro `doc' fields and JSON Schema `description' and `$comment' keywords.
+    pub strip_comments: bool,
+    /// Remove protobuf options, including custom ones (i.e., extensions), except those in `keep_options'.
+    pub strip_options: bool,
+    /// Names of the protobuf options to keep when stripping them (e.g., `deprecated' or `(acme.codegen)').
+    pub keep_options: Vec<String>,
+}
+
+/// Normalizes schema text of the given type according to the options.
+/// Normalization never changes the meaning of a schema, only its text; this matters because
+/// the Schema Registry compares schemas (e.g., when looking up existing versions) by their text.
+/// Normalizing already normalized text is a no-op.
+pub fn normalize(schema: &str, schema_type: &SchemaType, options: &Options) -> String {
+    match schema_type {
+        SchemaType::Protobuf if options.strip_comments || options.strip_options => {
+            let mut schema = schema.to_owned();
+            if options.strip_comments {
+                schema = strip_protobuf_comments(&schema);
+            }
+
+            if options.strip_options {
+                schema = strip_protobuf_options(&schema, &options.keep_options);
+            }
+
+            schema
+        }
+        SchemaType::Avro if options.strip_comments => strip_json(schema, strip_avro_docs),
+        SchemaType::Json if options.strip_comments => {
+            strip_json(schema, strip_json_schema_comments)
+        }
+        _ => schema.to_owned(),
+    }
+}
+
+/// Applies the given stripper to the parsed schema and serializes the result compactly.
+/// Text that doesn't parse is left alone for the registry to reject.
+fn strip_json(schema: &str, strip: fn(&mut Value)) -> String {
+    match serde_json::from_str(schema) {
+        Ok(mut value) => {
+            strip(&mut value);
+            value.to_string()
+        }
+        Err(_) => schema.to_owned(),
+    }
+}
+
+/// Removes `doc' from named types and fields of an Avro schema, leaving default values intact.
+fn strip_avro_docs(schema: &mut Value) {
+    match schema {
+        Value::Array(union) => union.iter_mut().for_each(strip_avro_docs),
+        Value::Object(schema) => {
+            schema.shift_remove("doc");
+            for key in &["type", "items", "values"] {
+                if let Some(schema) = schema.get_mut(*key) {
+                    strip_avro_docs(schema);
+                }
+            }
+
+            if let Some(Value::Array(fields)) = schema.get_mut("fields") {
+                for field in fields.iter_mut().filter_map(Value::as_object_mut) {
+                    field.shift_remove("doc");
+                    if let Some(schema) = field.get_mut("type") {
+                        strip_avro_docs(schema);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// JSON Schema keywords whose value is a subschema.
+const JSON_SCHEMA_KEYWORDS: &[&str] = &[
+    "additionalItems",
+    "additionalProperties",
+    "contains",
+    "else",
+    "if",
+    "items",
+    "not",
+    "propertyNames",
+    "then",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+
+/// JSON Schema keywords whose value is an array of subschemas.
+const JSON_SCHEMA_ARRAY_KEYWORDS: &[&str] = &["allOf", "anyOf", "items", "oneOf", "prefixItems"];
+
+/// JSON Schema keywords whose value maps names to subschemas.
+const JSON_SCHEMA_MAP_KEYWORDS: &[&str] = &[
+    "$defs",
+    "definitions",
+    "dependencies",
+    "dependentSchemas",
+    "patternProperties",
+    "properties",
+];
+
+/// Removes `description' and `$comment' from a JSON Schema and its subschemas.
+/// Property names and values such as `default', `const', `enum', and `examples' are left intact.
+fn strip_json_schema_comments(schema: &mut Value) {
+    let schema = match schema.as_object_mut() {
+        Some(schema) => schema,
+        None => return,
+    };
+
+    schema.shift_remove("description");
+    schema.shift_remove("$comment");
+
+    for (key, value) in schema.iter_mut() {
+        match value {
+            Value::Object(_) if JSON_SCHEMA_KEYWORDS.contains(&key.as_str()) => {
+                strip_json_schema_comments(value)
+            }
+            Value::Array(schemas) if JSON_SCHEMA_ARRAY_KEYWORDS.contains(&key.as_str()) => {
+                schemas.iter_mut().for_each(strip_json_schema_comments)
+            }
+            Value::Object(schemas) if JSON_SCHEMA_MAP_KEYWORDS.contains(&key.as_str()) => {
+                schemas.values_mut().for_each(strip_json_schema_comments)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Removes line and block comments from protobuf schema text, leaving string literals intact.
+/// The line breaks that end line comments are kept, as is any unterminated block comment.
+fn strip_protobuf_comments(schema: &str) -> String {
+    let mut buf = String::with_capacity(schema.len());
+    let mut rest = schema;
+
+    while let Some(pos) = rest.find(['/', '"', '\'']) {
+        let (text, tail) = rest.split_at(pos);
+        buf.push_str(text);
+
+        if tail.starts_with("//") {
+            rest = &tail[tail.find('\n').unwrap_or(tail.len())..];
+        } else if let Some(comment) = tail.strip_prefix("/*") {
+            match comment.find("*/") {
+                Some(end) => rest = &comment[end + 2..],
+                None => {
+                    buf.push_str(tail);
+                    rest = "";
+                }
+            }
+        } else if let Some(tail) = tail.strip_prefix('/') {
+            buf.push('/');
+            rest = tail;
+        } else {
+            let end = string_literal_len(tail);
+            buf.push_str(&tail[..end]);
+            rest = &tail[end..];
+        }
+    }
+
+    buf.push_str(rest);
+    buf
+}
+
+/// Length of the comment at the start of the given text; a line comment ends before its line break.
+fn comment_len(text: &str) -> usize {
+    if text.starts_with("//") {
+        text.find('\n').unwrap_or(text.len())
+    } else {
+        text[2..].find("*/").map_or(text.len(), |end| end + 4)
+    }
+}
+
+/// Positions of the characters of protobuf text that are outside comments and string literals.
+fn significant_chars(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut i = 0;
+    std::iter::from_fn(move || loop {
+        let rest = &text[i..];
+        let c = rest.chars().next()?;
+        if rest.starts_with("//") || rest.starts_with("/*") {
+            i += comment_len(rest);
+        } else if c == '"' || c == '\'' {
+            i += string_literal_len(rest);
+        } else {
+            i += c.len_utf8();
+            return Some((i - c.len_utf8(), c));
+        }
+    })
+}
+
+/// Length of a statement up to and including its `;', or of a bracketed group (given its opening bracket)
+/// up to and including the closing one; nested brackets (e.g., in aggregate values) are skipped over.
+fn balanced_len(text: &str) -> usize {
+    let mut depth = 0usize;
+    for (i, c) in significant_chars(text) {
+        match c {
+            '{' | '[' | '(' => depth += 1,
+            '}' | ']' | ')' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 && c == ']' {
+                    return i + 1;
+                }
+            }
+            ';' if depth == 0 => return i + 1,
+            _ => {}
+        }
+    }
+
+    text.len()
+}
+
+/// Splits the contents of a bracketed group of field options into the options.
+fn split_options(text: &str) -> Vec<&str> {
+    let mut options = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    for (i, c) in significant_chars(text) {
+        match c {
+            '{' | '[' | '(' => depth += 1,
+            '}' | ']' | ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                options.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    options.push(&text[start..]);
+    options
+}
+
+/// Whether an option (e.g., `(acme.codegen).skip = true') is one of those to keep, or a field of one.
+fn keeps_option(option: &str, keep: &[String]) -> bool {
+    let name: String = option
+        .split('=')
+        .next()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect();
+
+    keep.iter().any(|kept| {
+        name == *kept
+            || name
+                .strip_prefix(kept.as_str())
+                .is_some_and(|rest| rest.starts_with('.'))
+    })
+}
+
+/// Removes option statements and field options from protobuf schema text, except those to keep,
+/// leaving comments and string literals intact. A removed statement's line goes with it, unless
+/// there's anything else on that line.
+fn strip_protobuf_options(schema: &str, keep: &[String]) -> String {
+    let mut buf = String::with_capacity(schema.len());
+    let mut copied = 0;
+    let mut statement_start = true;
+
+    for (i, c) in significant_chars(schema) {
+        if i < copied {
+            continue;
+        }
+
+        let rest = &schema[i..];
+        let is_option = statement_start
+            && rest.starts_with("option")
+            && rest["option".len()..].starts_with(|c: char| c.is_whitespace() || c == '(');
+
+        if is_option {
+            let end = i + balanced_len(rest);
+            buf.push_str(&schema[copied..i]);
+            copied = end;
+            if keeps_option(&schema[i + "option".len()..end], keep) {
+                buf.push_str(&schema[i..end]);
+                continue;
+            }
+
+            let line_start = buf.rfind('\n').map_or(0, |pos| pos + 1);
+            let after = &schema[end..];
+            let line_end = after.find('\n').map_or(after.len(), |pos| pos + 1);
+            if buf[line_start..].trim().is_empty() && after[..line_end].trim().is_empty() {
+                buf.truncate(line_start);
+                copied += line_end;
+            }
+        } else if c == '[' {
+            let end = i + balanced_len(rest);
+            let options = split_options(&schema[i + 1..end - 1]);
+            let kept: Vec<_> = options
+                .iter()
+                .map(|option| option.trim())
+                .filter(|option| keeps_option(option, keep))
+                .collect();
+
+            buf.push_str(&schema[copied..i]);
+            copied = end;
+            if kept.len() == options.len() {
+                buf.push_str(&schema[i..end]);
+            } else if kept.is_empty() {
+                buf.truncate(buf.trim_end_matches([' ', '\t']).len());
+            } else {
+                buf.push('[');
+                buf.push_str(&kept.join(", "));
+                buf.push(']');
+            }
+
+            statement_start = false;
+        } else if !c.is_whitespace() {
+            statement_start = matches!(c, ';' | '{' | '}');
+        }
+    }
+
+    buf.push_str(&schema[copied..]);
+    buf
+}
+
+/// Length of the string literal at the start of the given text, including its quotes.
+/// Literals can't span lines, so an unterminated one ends at the line break.
+pub(crate) fn string_literal_len(text: &str) -> usize {
+    let mut chars = text.char_indices();
+    let (_, quote) = chars.next().expect("opening quote");
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '\n' => return i,
+            c if c == quote => return i + 1,
+            _ => {}
+        }
+    }
+
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const STRIP: Options = Options {
+        strip_comments: true,
+        strip_options: false,
+        keep_options: Vec::new(),
+    };
+
+    fn protobuf(schema: &str) -> String {
+        normalize(schema, &SchemaType::Protobuf, &STRIP)
+    }
+
+    fn without_options(schema: &str, keep: &[&str]) -> String {
+        let options = Options {
+            strip_options: true,
+            keep_options: keep.iter().map(|&name| name.to_owned()).collect(),
+            ..Options::default()
+        };
+
+        normalize(schema, &SchemaType::Protobuf, &options)
+    }
+
+    /// Protobuf-ish text, heavy on the characters that matter to the comment stripper.
+    fn schema_text() -> impl Strategy<Value = String> {
+        prop::collection::vec(
+            prop_oneof![
+                Just("/".to_owned()),
+                Just("*".to_owned()),
+                Just("\"".to_owned()),
+                Just("'".to_owned()),
+                Just("\\".to_owned()),
+                Just("\n".to_owned()),
+                "[a-z =;{}]{1,8}",
+            ],
+            0..48,
+        )
+        .prop_map(|parts| parts.concat())
+    }
+
+    #[test]
+    fn strips_line_and_block_comments() {
+        let schema = "syntax = \"proto3\"; // trailing\n/* block\n comment */message Foo {}\n";
+        assert_eq!(protobuf(schema), "syntax = \"proto3\"; \nmessage Foo {}\n");
+    }
+
+    #[test]
+    fn keeps_comment_markers_in_string_literals() {
+        let schema = "option (url) = \"http://example.com/*\"; // comment\n";
+        assert_eq!(
+            protobuf(schema),
+            "option (url) = \"http://example.com/*\"; \n"
+        );
+    }
+
+    #[test]
+    fn strips_protobuf_options() {
+        let schema = "syntax = \"proto3\";\noption java_package = \"com.acme\";\n\
+                      message Foo {\n  option (acme.codegen) = { skip: true; names: [\"a;b\"] };\n\
+                      \x20 int32 id = 1 [deprecated = true, (acme.field).pii = true];\n\
+                      \x20 string name = 2 [(acme.field) = { mask: \"]\" }]; // option kept = true;\n}\n";
+
+        assert_eq!(
+            without_options(schema, &[]),
+            "syntax = \"proto3\";\nmessage Foo {\n  int32 id = 1;\n  string name = 2; // option kept = true;\n}\n"
+        );
+
+        assert_eq!(
+            without_options(schema, &["deprecated", "(acme.codegen)"]),
+            "syntax = \"proto3\";\nmessage Foo {\n  option (acme.codegen) = { skip: true; names: [\"a;b\"] };\n\
+             \x20 int32 id = 1 [deprecated = true];\n  string name = 2; // option kept = true;\n}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_fields_named_option() {
+        let schema = "message Foo {\n  string option = 1;\n}\n";
+        assert_eq!(without_options(schema, &[]), schema);
+    }
+
+    #[test]
+    fn strips_avro_docs() {
+        let schema = r#"{
+            "type": "record",
+            "name": "User",
+            "doc": "A user.",
+            "fields": [
+                {"name": "id", "type": "string", "doc": "The id."},
+                {"name": "tags", "type": {"type": "array", "items": {"type": "enum", "name": "Tag", "symbols": ["A"], "doc": "A tag."}}},
+                {"name": "info", "type": ["null", {"type": "record", "name": "Info", "doc": "Info.", "fields": []}], "default": {"doc": "kept"}}
+            ]
+        }"#;
+
+        assert_eq!(
+            normalize(schema, &SchemaType::Avro, &STRIP),
+            r#"{"type":"record","name":"User","fields":[{"name":"id","type":"string"},{"name":"tags","type":{"type":"array","items":{"type":"enum","name":"Tag","symbols":["A"]}}},{"name":"info","type":["null",{"type":"record","name":"Info","fields":[]}],"default":{"doc":"kept"}}]}"#
+        );
+    }
+
+    #[test]
+    fn strips_json_schema_descriptions() {
+        let schema = r#"{
+            "$comment": "Top-level comment.",
+            "type": "object",
+            "description": "A user.",
+            "properties": {
+                "description": {"type": "string", "description": "A property named description."},
+                "tags": {"type": "array", "items": {"type": "string", "$comment": "A tag."}},
+                "kind": {"enum": [{"description": "kept"}], "default": {"description": "kept"}}
+            },
+            "$defs": {"id": {"anyOf": [{"type": "string", "description": "As text."}]}}
+        }"#;
+
+        assert_eq!(
+            normalize(schema, &SchemaType::Json, &STRIP),
+            r#"{"type":"object","properties":{"description":{"type":"string"},"tags":{"type":"array","items":{"type":"string"}},"kind":{"enum":[{"description":"kept"}],"default":{"description":"kept"}}},"$defs":{"id":{"anyOf":[{"type":"string"}]}}}"#
+        );
+    }
+
+    #[test]
+    fn leaves_invalid_json_intact() {
+        let schema = "{\"type\": \"string\", // not JSON\n}";
+        assert_eq!(normalize(schema, &SchemaType::Avro, &STRIP), schema);
+        assert_eq!(normalize(schema, &SchemaType::Json, &STRIP), schema);
+    }
+
+    proptest! {
+        #[test]
+        fn is_idempotent(schema in schema_text()) {
+            let once = protobuf(&schema);
+            prop_assert_eq!(protobuf(&once), once);
+        }
+
+        #[test]
+        fn leaves_schema_intact_without_options(schema in schema_text()) {
+            for schema_type in [SchemaType::Avro, SchemaType::Json, SchemaType::Protobuf] {
+                prop_assert_eq!(normalize(&schema, &schema_type, &Options::default()), schema.clone());
+            }
+        }
+
+        #[test]
+        fn strips_options_idempotently(schema in schema_text()) {
+            let once = without_options(&schema, &[]);
+            prop_assert_eq!(without_options(&once, &[]), once);
+        }
+
+        #[test]
+        fn leaves_schema_without_comments_intact(schema in "[a-z0-9 =;{}\"\n]*") {
+            prop_assert_eq!(protobuf(&schema), schema);
+        }
+
+        #[test]
+        fn preserves_string_literals(
+            prefix in schema_text(),
+            literal in r#"[a-z/* ]*"#,
+            comment in "[a-z/* ]*",
+        ) {
+            // Terminate whatever the prefix leaves open so that the literal starts at the top level.
+            let schema = format!("{}\n*/\n\"{}\"; // {}\n", prefix, literal, comment);
+            let literal = format!("\"{}\";", literal);
+            prop_assert!(protobuf(&schema).contains(&literal));
+        }
+    }
+}
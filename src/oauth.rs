@@ -0,0 +1,133 @@
+use crate::exit::{
+    self,
+    Failure,
+};
+
+use log::*;
+use reqwest::{
+    blocking::Client as HttpClient,
+    Url,
+};
+
+use serde::Deserialize;
+
+use std::{
+    fmt,
+    sync::Mutex,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+/// How long before its expiry a token is considered expired, to allow for clock skew and request latency.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// OAuth 2.0 client credentials used to obtain bearer tokens for the Schema Registry.
+/// Tokens are fetched on first use and again whenever the current one expires or is rejected.
+pub struct OAuth {
+    token_url: Url,
+    client_id: String,
+    client_secret: String,
+    scope: Option<String>,
+    token: Mutex<Option<Token>>,
+}
+
+struct Token {
+    access_token: String,
+    expires_at: Option<Instant>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+impl fmt::Debug for OAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth")
+            .field("token_url", &self.token_url.as_str())
+            .field("client_id", &self.client_id)
+            .field("scope", &self.scope)
+            .finish()
+    }
+}
+
+impl OAuth {
+    pub fn new(
+        token_url: &str,
+        client_id: String,
+        client_secret: String,
+        scope: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let token_url = Url::parse(token_url)
+            .map_err(|e| anyhow::format_err!("invalid token URL {}: {}", token_url, e))?;
+
+        Ok(Self {
+            token_url,
+            client_id,
+            client_secret,
+            scope,
+            token: Mutex::new(None),
+        })
+    }
+
+    /// Returns the current access token, fetching a new one if there's none or it's about to expire.
+    pub fn token(&self, http: &HttpClient) -> anyhow::Result<String> {
+        let mut token = self.token.lock().expect("token lock");
+        if let Some(current) = token.as_ref() {
+            if current
+                .expires_at
+                .is_none_or(|expires_at| Instant::now() + EXPIRY_MARGIN < expires_at)
+            {
+                return Ok(current.access_token.clone());
+            }
+        }
+
+        let fetched = self.fetch(http).map_err(|e| {
+            let msg = format!("error obtaining OAuth token from {}: {}", self.token_url, e);
+            Failure::new(exit::AUTH, msg)
+        })?;
+
+        let access_token = fetched.access_token.clone();
+        *token = Some(fetched);
+        Ok(access_token)
+    }
+
+    /// Forgets the current token, e.g., after the Schema Registry rejected it.
+    pub fn invalidate(&self) {
+        *self.token.lock().expect("token lock") = None;
+    }
+
+    fn fetch(&self, http: &HttpClient) -> anyhow::Result<Token> {
+        let mut form = vec![
+            ("grant_type", "client_credentials"),
+            ("client_id", &self.client_id),
+            ("client_secret", &self.client_secret),
+        ];
+
+        if let Some(scope) = &self.scope {
+            form.push(("scope", scope));
+        }
+
+        debug!("POST {}", self.token_url);
+
+        let res = http.post(self.token_url.clone()).form(&form).send()?;
+        let status = res.status();
+        let text = res.text()?;
+        anyhow::ensure!(status.is_success(), "HTTP {}: {}", status, text);
+
+        let res: TokenResponse = serde_json::from_str(&text)
+            .map_err(|e| anyhow::format_err!("invalid token response: {}", e))?;
+
+        debug!("obtained OAuth token (expires in {:?}s)", res.expires_in);
+
+        Ok(Token {
+            access_token: res.access_token,
+            expires_at: res
+                .expires_in
+                .map(|secs| Instant::now() + Duration::from_secs(secs)),
+        })
+    }
+}
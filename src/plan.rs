@@ -0,0 +1,332 @@
+use crate::{
+    avro,
+    registry::Client,
+    PostSettings,
+    SchemaSource,
+    SchemaTypeOpt,
+};
+
+use log::*;
+use schema_registry_converter::schema_registry_common::SuppliedSchema;
+
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+/// Schema file in a directory being posted, along with the subject it's registered under.
+#[derive(Debug)]
+struct Step {
+    /// path of the file, relative to the directory
+    file: String,
+    subject: String,
+    schema: SuppliedSchema,
+    /// subjects of the other files in the directory the schema references (directly)
+    references: BTreeSet<String>,
+}
+
+/// Finds the schema files in the directory (and its subdirectories), along with the subjects derived from them:
+/// a protobuf file's subject is derived as it would be were it referenced (see `--ref-strategy'),
+/// and an Avro file's is the full name of the type it defines.
+fn steps(settings: &PostSettings, dir: &Path) -> anyhow::Result<Vec<Step>> {
+    let extension = match settings.schema_type {
+        SchemaTypeOpt::Avro => "avsc",
+        SchemaTypeOpt::Protobuf => "proto",
+        SchemaTypeOpt::Json => {
+            anyhow::bail!("posting a directory is only supported for Avro and protobuf schemas")
+        }
+    };
+
+    let mut files = Vec::new();
+    crate::find_files(dir, extension, &mut files)?;
+    anyhow::ensure!(
+        !files.is_empty(),
+        "no `.{}' files in {}",
+        extension,
+        dir.display()
+    );
+
+    // Imports are resolved against the directory first, so that files refer to each other by their relative paths.
+    let include: Vec<PathBuf> = Some(dir.to_path_buf())
+        .into_iter()
+        .chain(settings.include.iter().cloned())
+        .collect();
+
+    let source = SchemaSource {
+        include,
+        ..SchemaSource::from(settings)
+    };
+
+    let fds = match settings.schema_type {
+        SchemaTypeOpt::Protobuf => {
+            let mut includes: Vec<PathBuf> = source
+                .include
+                .iter()
+                .map(|path| path.canonicalize())
+                .collect::<Result<_, _>>()?;
+
+            for map in &source.include_map {
+                includes.push(map.proto_path()?);
+            }
+
+            crate::parse_protos(&files, &includes)?.file
+        }
+        _ => Vec::new(),
+    };
+
+    files
+        .into_iter()
+        .map(|path| {
+            let file = path
+                .strip_prefix(dir)?
+                .to_str()
+                .ok_or_else(|| anyhow::format_err!("invalid file name: {}", path.display()))?
+                .to_owned();
+
+            let subject = match settings.schema_type {
+                SchemaTypeOpt::Protobuf => {
+                    let fd = fds.iter().find(|fd| fd.name() == file).ok_or_else(|| {
+                        anyhow::format_err!("failed to locate file descriptor for: {}", file)
+                    })?;
+
+                    crate::protobuf_subject(&source, &file, fd)?
+                }
+                _ => avro::type_name(&path)?,
+            };
+
+            let schema = crate::supplied_schema(&SchemaSource {
+                file: path.clone(),
+                include: source.include.clone(),
+                ..SchemaSource::from(settings)
+            })?;
+
+            Ok(Step {
+                file,
+                subject,
+                schema,
+                references: BTreeSet::new(),
+            })
+        })
+        .collect()
+}
+
+/// Orders the files so that each comes after those it references.
+/// Among the files ready to be registered, the one with the first path goes first.
+fn plan(mut steps: Vec<Step>) -> anyhow::Result<Vec<Step>> {
+    let mut files = BTreeMap::new();
+    for step in &steps {
+        if let Some(other) = files.insert(step.subject.clone(), step.file.clone()) {
+            anyhow::bail!(
+                "both {} and {} would be registered under {}",
+                other,
+                step.file,
+                step.subject
+            );
+        }
+    }
+
+    for step in &mut steps {
+        step.references = step
+            .schema
+            .references
+            .iter()
+            .map(|reference| &reference.subject)
+            .filter(|&subject| files.contains_key(subject))
+            .cloned()
+            .collect();
+    }
+
+    let mut planned = BTreeSet::new();
+    let mut plan = Vec::with_capacity(steps.len());
+    while !steps.is_empty() {
+        let i = steps
+            .iter()
+            .position(|step| step.references.is_subset(&planned))
+            .ok_or_else(|| {
+                anyhow::format_err!(
+                    "circular references among: {}",
+                    steps
+                        .iter()
+                        .map(|step| step.file.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?;
+
+        let step = steps.remove(i);
+        planned.insert(step.subject.clone());
+        plan.push(step);
+    }
+
+    Ok(plan)
+}
+
+fn print_plan(plan: &[Step]) {
+    for (i, step) in plan.iter().enumerate() {
+        out!("{}. {} -> {}", i + 1, step.file, step.subject);
+        if !step.references.is_empty() {
+            out!(
+                " (references {})",
+                step.references
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
+        outln!();
+    }
+}
+
+/// Posts all schema files in a directory, each under its derived subject, in dependency order.
+/// The plan is printed first; without a client (i.e., with `--plan-only'), that's all.
+pub fn run_post_dir(client: Option<Client>, settings: &PostSettings) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        settings.topic.is_none() && settings.record.is_none() && !settings.topic_key,
+        "the subjects of schemas posted from a directory are derived from the files; \
+         `--topic', `--topic-key', and `--record' cannot be given"
+    );
+
+    anyhow::ensure!(
+        settings.id.is_none() && settings.version.is_none(),
+        "`--id' and `--version' cannot be combined with a directory"
+    );
+
+    anyhow::ensure!(
+        settings.require_compat.is_none() && !settings.set_compat,
+        "`--require-compat' and `--set-compat' cannot be combined with a directory"
+    );
+
+    anyhow::ensure!(
+        settings.name.is_none(),
+        "`--name' only applies to a schema read from standard input"
+    );
+
+    let dir = settings.file.canonicalize()?;
+    let contract = settings.contract()?;
+    let plan = plan(steps(settings, &dir)?)?;
+
+    print_plan(&plan);
+
+    let client = match client {
+        Some(client) => client,
+        None => return Ok(()),
+    };
+
+    for step in plan {
+        let id = if settings.register_deps {
+            let (id, _, dependencies) = client
+                .register_with_dependencies(&step.subject, &step.schema, &contract)
+                .map_err(|e| e.context(format!("error posting {}", step.file)))?;
+
+            for dependency in dependencies
+                .iter()
+                .filter(|dependency| dependency.registered)
+            {
+                debug!(
+                    "registered {} (version {})",
+                    dependency.subject, dependency.version
+                );
+            }
+
+            id
+        } else {
+            client
+                .register(&step.subject, &step.schema, &contract)
+                .map_err(|e| e.context(format!("error posting {}", step.file)))?
+                .0
+        };
+
+        outln!("registered {} (id {})", step.subject, id);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema_registry_converter::schema_registry_common::{
+        SchemaType,
+        SuppliedReference,
+    };
+
+    fn step(file: &str, subject: &str, references: &[&str]) -> Step {
+        Step {
+            file: file.to_owned(),
+            subject: subject.to_owned(),
+            schema: SuppliedSchema {
+                name: None,
+                schema_type: SchemaType::Avro,
+                schema: format!("\"{}\"", subject),
+                references: references
+                    .iter()
+                    .map(|&subject| SuppliedReference {
+                        name: subject.to_owned(),
+                        subject: subject.to_owned(),
+                        schema: format!("\"{}\"", subject),
+                        references: Vec::new(),
+                    })
+                    .collect(),
+            },
+            references: BTreeSet::new(),
+        }
+    }
+
+    fn files(plan: &[Step]) -> Vec<&str> {
+        plan.iter().map(|step| step.file.as_str()).collect()
+    }
+
+    #[test]
+    fn orders_diamonds_by_dependency_then_path() {
+        let plan = plan(vec![
+            step("a.avsc", "Order", &["Money", "Tax", "external.Type"]),
+            step("b.avsc", "Tax", &["Currency"]),
+            step("c.avsc", "Money", &["Currency"]),
+            step("d.avsc", "Currency", &[]),
+        ])
+        .unwrap();
+
+        assert_eq!(files(&plan), ["d.avsc", "b.avsc", "c.avsc", "a.avsc"]);
+
+        // References to subjects not posted from the directory don't take part.
+        assert_eq!(
+            plan[3].references.iter().collect::<Vec<_>>(),
+            ["Money", "Tax"]
+        );
+    }
+
+    #[test]
+    fn reports_circular_references() {
+        let err = plan(vec![
+            step("a.avsc", "A", &["C"]),
+            step("b.avsc", "B", &[]),
+            step("c.avsc", "C", &["A"]),
+        ])
+        .unwrap_err();
+
+        assert_eq!(err.to_string(), "circular references among: a.avsc, c.avsc");
+    }
+
+    #[test]
+    fn rejects_files_registered_under_the_same_subject() {
+        let err = plan(vec![
+            step("a.avsc", "Order", &[]),
+            step("b.avsc", "Money", &[]),
+            step("v2/a.avsc", "Order", &[]),
+        ])
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "both a.avsc and v2/a.avsc would be registered under Order"
+        );
+    }
+}
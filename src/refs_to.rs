@@ -0,0 +1,54 @@
+use crate::registry::Client;
+
+use gumdrop::Options;
+use log::*;
+
+/// Report the subjects (and versions) whose schemas reference a given subject,
+/// i.e., those affected when its schema evolves. Dependents are printed one per line as
+/// subject, version, and the referenced version and name, separated by tabs.
+#[derive(Debug, Options)]
+pub struct RefsToSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// referenced subject (required)
+    #[options(required, meta = "NAME")]
+    subject: String,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    pub schema_registry_url: Vec<String>,
+}
+
+pub fn run_refs_to(client: Client, settings: &RefsToSettings) -> anyhow::Result<()> {
+    let target = client.qualify(&settings.subject).into_owned();
+
+    let mut subjects = client.list_subjects()?;
+    subjects.sort();
+
+    let mut dependents = 0;
+    for subject in subjects {
+        for version in client.get_versions(&subject)? {
+            let schema = client.get_schema(&subject, &version.to_string())?;
+            for reference in &schema.references {
+                if client.qualify(&reference.subject) != target {
+                    continue;
+                }
+
+                outln!(
+                    "{}\t{}\t{}\t{}",
+                    subject,
+                    version,
+                    reference.version,
+                    reference.name
+                );
+
+                dependents += 1;
+            }
+        }
+    }
+
+    debug!("{} dependent version(s)", dependents);
+
+    Ok(())
+}
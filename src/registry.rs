@@ -0,0 +1,1612 @@
+use crate::{
+    cache::Cache,
+    oauth::OAuth,
+    throttle::{
+        self,
+        RateLimiter,
+    },
+};
+
+use log::*;
+use reqwest::{
+    blocking::{
+        Client as HttpClient,
+        Response,
+    },
+    header::{
+        HeaderMap,
+        ACCEPT,
+        CONTENT_TYPE,
+        RETRY_AFTER,
+    },
+    Method,
+    StatusCode,
+    Url,
+};
+
+use schema_registry_converter::schema_registry_common::{
+    RegisteredReference,
+    RegisteredSchema,
+    SchemaType,
+    SuppliedReference,
+    SuppliedSchema,
+};
+
+use serde::{
+    de::DeserializeOwned,
+    Deserialize,
+    Serialize,
+};
+
+use serde_json::{
+    json,
+    Value,
+};
+
+use std::{
+    borrow::Cow,
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
+    error,
+    fmt,
+    str::FromStr,
+    thread,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+const CONTENT_TYPE_V1: &str = "application/vnd.schemaregistry.v1+json";
+
+/// Content type of requests and responses of Apicurio's Confluent-compatible API, which rejects the one above.
+const CONTENT_TYPE_JSON: &str = "application/json";
+
+/// Path of Apicurio's Confluent-compatible API, appended to URLs that don't include it already.
+const APICURIO_CCOMPAT_PATH: &[&str] = &["apis", "ccompat", "v7"];
+
+/// Header that selects the artifact group Apicurio maps subjects to (instead of the default group).
+const APICURIO_GROUP_HEADER: &str = "X-Registry-GroupId";
+
+/// Error code returned by the Schema Registry when a subject does not exist.
+pub const SUBJECT_NOT_FOUND: i32 = 40401;
+
+/// Error code returned by the Schema Registry when a version of a subject does not exist.
+pub const VERSION_NOT_FOUND: i32 = 40402;
+
+/// Error code returned by the Schema Registry when a schema does not exist.
+pub const SCHEMA_NOT_FOUND: i32 = 40403;
+
+/// Error code returned by the Schema Registry when a subject was soft-deleted.
+pub const SUBJECT_SOFT_DELETED: i32 = 40404;
+
+/// Error code returned by the Schema Registry when a version was soft-deleted.
+pub const VERSION_SOFT_DELETED: i32 = 40406;
+
+/// Error code returned by the Schema Registry when a subject has no compatibility level of its own.
+pub const SUBJECT_COMPATIBILITY_NOT_CONFIGURED: i32 = 40408;
+
+/// Error code returned by the Schema Registry when a subject has no mode of its own.
+pub const SUBJECT_MODE_NOT_CONFIGURED: i32 = 40409;
+
+/// Error code returned by the Schema Registry when a schema is incompatible with the registered version(s).
+pub const INCOMPATIBLE_SCHEMA: i32 = 409;
+
+/// Error code returned by the Schema Registry when a schema can't be parsed (or its references resolved).
+pub const INVALID_SCHEMA: i32 = 42201;
+
+/// Error code returned by the Schema Registry when a version is neither a number nor `latest'.
+pub const INVALID_VERSION: i32 = 42202;
+
+/// Error code returned by the Schema Registry when an operation isn't permitted in the current mode.
+pub const OPERATION_NOT_PERMITTED: i32 = 42205;
+
+/// Error code returned by the Schema Registry when a schema can't be deleted because others reference it.
+pub const REFERENCE_EXISTS: i32 = 42206;
+
+/// Error response returned by the Schema Registry.
+#[derive(Debug)]
+pub struct Error {
+    pub status: u16,
+    pub error_code: Option<i32>,
+    pub message: String,
+}
+
+impl Error {
+    /// Whether the given error is a Schema Registry error with one of the given codes.
+    pub fn has_code(err: &anyhow::Error, codes: &[i32]) -> bool {
+        err.downcast_ref::<Self>()
+            .and_then(|e| e.error_code)
+            .is_some_and(|code| codes.contains(&code))
+    }
+
+    /// What to do about the error, where that's apparent from its code (or else its HTTP status).
+    pub fn hint(&self) -> Option<&'static str> {
+        let hint = match (self.error_code, self.status) {
+            (Some(SUBJECT_NOT_FOUND), _) => {
+                "check the subject name (and `--context'); `ksrt list' shows the registered subjects"
+            }
+            (Some(VERSION_NOT_FOUND), _) => {
+                "`ksrt list' shows the versions of each subject; soft-deleted ones require `--deleted'"
+            }
+            (Some(SCHEMA_NOT_FOUND), _) => "the schema isn't registered (under this subject)",
+            (Some(SUBJECT_SOFT_DELETED | VERSION_SOFT_DELETED), _) => {
+                "it was soft-deleted; `--deleted' includes soft-deleted subjects and versions"
+            }
+            (Some(INCOMPATIBLE_SCHEMA), _) | (None, 409) => {
+                "the schema breaks the subject's compatibility level; `ksrt breaking' shows the changes that do"
+            }
+            (Some(INVALID_SCHEMA), _) => {
+                "the registry couldn't parse the schema; check its syntax, its type (`--type'), and its references"
+            }
+            (Some(INVALID_VERSION), _) => "versions are positive numbers or `latest'",
+            (Some(OPERATION_NOT_PERMITTED), _) => {
+                "the subject's (or the registry's) mode doesn't permit this, e.g., registering with `--id' requires IMPORT mode"
+            }
+            (Some(REFERENCE_EXISTS), _) => {
+                "other schemas still reference it; `ksrt refs-to' lists them"
+            }
+            (_, 401 | 403) => {
+                "check the credentials (`--api-key', the OAuth options, or the profile) and their permissions"
+            }
+            (_, 500..=599) => "the registry failed to handle the request; try again later",
+            _ => return None,
+        };
+
+        Some(hint)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.error_code {
+            Some(code) => write!(
+                f,
+                "{} (HTTP {}, error code {})",
+                self.message, self.status, code
+            ),
+            None => write!(f, "{} (HTTP {})", self.message, self.status),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    error_code: Option<i32>,
+    message: Option<String>,
+}
+
+/// Implementation of the Schema Registry API that the client talks to.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Flavor {
+    /// Confluent Schema Registry (or any other implementation of its API, as is).
+    #[default]
+    Confluent,
+    /// Apicurio Registry, through its Confluent-compatible API.
+    Apicurio,
+}
+
+impl fmt::Display for Flavor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Confluent => write!(f, "confluent"),
+            Self::Apicurio => write!(f, "apicurio"),
+        }
+    }
+}
+
+impl FromStr for Flavor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let flavor = match s {
+            "confluent" => Self::Confluent,
+            "apicurio" => Self::Apicurio,
+            _ => anyhow::bail!("unsupported registry flavor: {}", s),
+        };
+
+        Ok(flavor)
+    }
+}
+
+/// Schema compatibility level.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Compatibility {
+    None,
+    Backward,
+    BackwardTransitive,
+    Forward,
+    ForwardTransitive,
+    Full,
+    FullTransitive,
+}
+
+impl fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "NONE"),
+            Self::Backward => write!(f, "BACKWARD"),
+            Self::BackwardTransitive => write!(f, "BACKWARD_TRANSITIVE"),
+            Self::Forward => write!(f, "FORWARD"),
+            Self::ForwardTransitive => write!(f, "FORWARD_TRANSITIVE"),
+            Self::Full => write!(f, "FULL"),
+            Self::FullTransitive => write!(f, "FULL_TRANSITIVE"),
+        }
+    }
+}
+
+impl Compatibility {
+    /// Whether this level requires the new schema to be able to read data written with the old one(s).
+    pub fn is_backward(self) -> bool {
+        matches!(
+            self,
+            Self::Backward | Self::BackwardTransitive | Self::Full | Self::FullTransitive
+        )
+    }
+
+    /// Whether this level requires the old schema(s) to be able to read data written with the new one.
+    pub fn is_forward(self) -> bool {
+        matches!(
+            self,
+            Self::Forward | Self::ForwardTransitive | Self::Full | Self::FullTransitive
+        )
+    }
+
+    fn is_transitive(self) -> bool {
+        matches!(
+            self,
+            Self::BackwardTransitive | Self::ForwardTransitive | Self::FullTransitive
+        )
+    }
+
+    /// Whether this level guarantees at least what the given one does (e.g., `FULL' satisfies `BACKWARD').
+    pub fn satisfies(self, required: Self) -> bool {
+        (self.is_backward() || !required.is_backward())
+            && (self.is_forward() || !required.is_forward())
+            && (self.is_transitive() || !required.is_transitive())
+    }
+}
+
+impl FromStr for Compatibility {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let compatibility = match s.to_uppercase().as_str() {
+            "NONE" => Self::None,
+            "BACKWARD" => Self::Backward,
+            "BACKWARD_TRANSITIVE" => Self::BackwardTransitive,
+            "FORWARD" => Self::Forward,
+            "FORWARD_TRANSITIVE" => Self::ForwardTransitive,
+            "FULL" => Self::Full,
+            "FULL_TRANSITIVE" => Self::FullTransitive,
+            _ => anyhow::bail!("unsupported compatibility level: {}", s),
+        };
+
+        Ok(compatibility)
+    }
+}
+
+/// Schema as registered under a subject.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    pub subject: Option<String>,
+    pub version: Option<u32>,
+    pub id: u32,
+    pub schema_type: Option<String>,
+    pub schema: String,
+    #[serde(default)]
+    pub references: Vec<RegisteredReference>,
+    pub metadata: Option<Metadata>,
+    pub rule_set: Option<Value>,
+    /// When the version was registered (in milliseconds since the epoch), if the registry reports it.
+    pub ts: Option<i64>,
+}
+
+/// Metadata attached to a schema version.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Metadata {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sensitive: Vec<String>,
+}
+
+/// Metadata and rules registered along with a schema (i.e., its data contract).
+#[derive(Clone, Debug, Default)]
+pub struct Contract {
+    pub metadata: Option<Metadata>,
+    /// Domain and migration rules, passed through as is.
+    pub rule_set: Option<Value>,
+}
+
+impl Contract {
+    fn add_to(&self, body: &mut Value) {
+        if let Some(metadata) = &self.metadata {
+            body["metadata"] = json!(metadata);
+        }
+
+        if let Some(rule_set) = &self.rule_set {
+            body["ruleSet"] = rule_set.clone();
+        }
+    }
+}
+
+impl Schema {
+    pub fn contract(&self) -> Contract {
+        Contract {
+            metadata: self.metadata.clone(),
+            rule_set: self.rule_set.clone(),
+        }
+    }
+
+    pub fn schema_type(&self) -> SchemaType {
+        match self.schema_type.as_deref() {
+            None | Some("AVRO") => SchemaType::Avro,
+            Some("JSON") => SchemaType::Json,
+            Some("PROTOBUF") => SchemaType::Protobuf,
+            Some(other) => SchemaType::Other(other.to_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SchemaId {
+    id: u32,
+}
+
+/// Global id and version to register a schema with, as when migrating it from another registry.
+/// Only permitted while the subject (or the registry as a whole) is in `IMPORT' mode.
+#[derive(Clone, Copy, Debug)]
+pub struct Import {
+    pub id: u32,
+    pub version: u32,
+}
+
+/// Schema exporter, which copies subjects to another registry (i.e., schema linking).
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Exporter {
+    pub name: String,
+    /// `AUTO', `CUSTOM', or `NONE': how the context the subjects are exported to is determined
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_type: Option<String>,
+    /// context the subjects are exported to (with the `CUSTOM' context type)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subjects: Vec<String>,
+    /// format of the exported subjects' names, in which `${subject}' stands for the original one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject_rename_format: Option<String>,
+    /// configuration of the destination registry (e.g., `schema.registry.url')
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub config: BTreeMap<String, String>,
+}
+
+/// State of a schema exporter.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ExporterStatus {
+    pub name: String,
+    /// `STARTING', `RUNNING', or `PAUSED'
+    pub state: String,
+    #[serde(default)]
+    pub offset: i64,
+    /// when the state was last updated, in milliseconds since the epoch
+    #[serde(default)]
+    pub ts: i64,
+    /// error the exporter failed with, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModeResponse {
+    mode: String,
+}
+
+/// Schema referenced (directly or not) by another one, as registered under a subject of its own.
+#[derive(Debug)]
+pub struct Dependency {
+    pub subject: String,
+    pub version: u32,
+    /// Whether it was newly registered (vs. already registered with identical content).
+    pub registered: bool,
+}
+
+/// State of registering the references of a schema.
+#[derive(Debug, Default)]
+struct ReferenceGraph {
+    /// Versions of the references registered so far, by name.
+    versions: HashMap<String, u32>,
+    /// Outcome for each reference, if tracked (in which case those already registered are not posted again).
+    dependencies: Option<Vec<Dependency>>,
+}
+
+/// Outcome of checking a schema's compatibility with a subject.
+#[derive(Debug)]
+pub enum CompatibilityCheck {
+    Compatible,
+    /// Not compatible, with the reasons given by the registry (if any).
+    Incompatible(Vec<String>),
+    /// Some of the schema's references are not registered, so the check could not be made.
+    MissingReferences,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompatibilityResponse {
+    is_compatible: bool,
+    #[serde(default)]
+    messages: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Config {
+    compatibility_level: Compatibility,
+}
+
+/// Name of the given schema type as used by the Schema Registry.
+pub fn schema_type_name(schema_type: &SchemaType) -> &str {
+    match schema_type {
+        SchemaType::Avro => "AVRO",
+        SchemaType::Json => "JSON",
+        SchemaType::Protobuf => "PROTOBUF",
+        SchemaType::Other(other) => other,
+    }
+}
+
+fn schema_body(
+    schema_type: &SchemaType,
+    schema: &str,
+    references: &[RegisteredReference],
+) -> Value {
+    let mut body = json!({
+        "schemaType": schema_type_name(schema_type),
+        "schema": schema,
+    });
+
+    if !references.is_empty() {
+        body["references"] = json!(references);
+    }
+
+    body
+}
+
+/// Tracing target of the spans of registry requests (which are exported over OTLP, if enabled).
+pub const SPAN_TARGET: &str = "ksrt::http";
+
+/// How many times a rate-limited request is retried before giving up.
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+
+/// Delay requested by a rate-limited response's `Retry-After' header, if it is given in seconds.
+fn retry_after(res: &Response) -> Option<Duration> {
+    res.headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Blocking client for the Schema Registry REST API.
+/// Requests are tried against each of the configured URLs in turn until one of them responds.
+#[derive(Debug)]
+pub struct Client {
+    urls: Vec<Url>,
+    http: HttpClient,
+    auth: Option<Auth>,
+    context: Option<String>,
+    deleted: bool,
+    flavor: Flavor,
+    /// Apicurio artifact group to map subjects to.
+    group: Option<String>,
+    /// Additional headers sent with every request (e.g., as required by a gateway in front of the registry).
+    headers: HeaderMap,
+    rate_limiter: Option<RateLimiter>,
+    cache: Option<Cache>,
+}
+
+/// How requests to the Schema Registry are authenticated.
+enum Auth {
+    /// HTTP basic authentication, e.g., with a Confluent Cloud API key and secret.
+    Basic { username: String, password: String },
+    /// Bearer tokens obtained with OAuth client credentials.
+    OAuth(OAuth),
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            Self::OAuth(oauth) => f.debug_tuple("OAuth").field(oauth).finish(),
+        }
+    }
+}
+
+impl Client {
+    pub fn new(urls: impl IntoIterator<Item = String>) -> anyhow::Result<Self> {
+        let urls = urls
+            .into_iter()
+            .map(|url| {
+                let parsed = Url::parse(&url)
+                    .map_err(|e| anyhow::format_err!("invalid URL {}: {}", url, e))?;
+
+                // E.g., `localhost:8081' parses as a URL with the scheme `localhost'.
+                anyhow::ensure!(
+                    !parsed.cannot_be_a_base(),
+                    "invalid URL {}: not an HTTP(S) URL (e.g., http://{})",
+                    url,
+                    url
+                );
+
+                Ok(parsed)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        anyhow::ensure!(
+            !urls.is_empty(),
+            "at least one Schema Registry URL is required"
+        );
+
+        let http = HttpClient::builder()
+            .build()
+            .map_err(|e| anyhow::format_err!("error configuring schema registry client: {}", e))?;
+
+        Ok(Self {
+            urls,
+            http,
+            auth: None,
+            context: None,
+            deleted: false,
+            flavor: Flavor::Confluent,
+            group: None,
+            headers: HeaderMap::new(),
+            rate_limiter: None,
+            cache: None,
+        })
+    }
+
+    /// Qualifies all subjects with the given schema context (e.g., `:.tenant:subject').
+    /// Subjects that are already qualified are left alone.
+    pub fn with_context(self, context: &str) -> Self {
+        Self {
+            context: Some(context.trim_start_matches('.').to_owned()),
+            ..self
+        }
+    }
+
+    /// Adapts requests to the given flavor of registry. For Apicurio, URLs that don't point to its
+    /// Confluent-compatible API already are extended to do so, and the content type is plain JSON.
+    pub fn with_flavor(self, flavor: Flavor) -> Self {
+        let urls = match flavor {
+            Flavor::Confluent => self.urls,
+            Flavor::Apicurio => self
+                .urls
+                .into_iter()
+                .map(|url| {
+                    if url.path().contains("/apis/ccompat/") {
+                        return url;
+                    }
+
+                    let mut url = url;
+                    url.path_segments_mut()
+                        .expect("base URL")
+                        .pop_if_empty()
+                        .extend(APICURIO_CCOMPAT_PATH);
+                    url
+                })
+                .collect(),
+        };
+
+        Self {
+            urls,
+            flavor,
+            ..self
+        }
+    }
+
+    /// Maps subjects to artifacts in the given group rather than the default one (Apicurio only).
+    pub fn with_group(self, group: String) -> Self {
+        Self {
+            group: Some(group),
+            ..self
+        }
+    }
+
+    /// Sends the given headers with every request, in addition to (or instead of) the usual ones.
+    pub fn with_headers(self, headers: HeaderMap) -> Self {
+        Self { headers, ..self }
+    }
+
+    /// Sends no more than the given number of requests per second (retries included).
+    pub fn with_rate_limit(self, per_second: u32) -> Self {
+        Self {
+            rate_limiter: Some(RateLimiter::new(per_second)),
+            ..self
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self.flavor {
+            Flavor::Confluent => CONTENT_TYPE_V1,
+            Flavor::Apicurio => CONTENT_TYPE_JSON,
+        }
+    }
+
+    /// Includes soft-deleted subjects and versions when listing subjects and versions and retrieving schemas.
+    pub fn with_deleted(self) -> Self {
+        Self {
+            deleted: true,
+            ..self
+        }
+    }
+
+    /// Query parameters that include soft-deleted subjects and versions in reads, if so configured.
+    fn deleted_query(&self) -> &'static [(&'static str, &'static str)] {
+        if self.deleted {
+            &[("deleted", "true")]
+        } else {
+            &[]
+        }
+    }
+
+    /// Caches responses that don't change once created, i.e., schemas retrieved by subject and version number or by id.
+    pub fn with_cache(self, cache: Cache) -> Self {
+        Self {
+            cache: Some(cache),
+            ..self
+        }
+    }
+
+    /// Key to cache the response to a request under, unless the response may change (e.g., the latest version).
+    fn cache_key(&self, method: &Method, path: &[&str], query: &[(&str, &str)]) -> Option<String> {
+        let immutable = match path {
+            ["subjects", _, "versions", version] => version.parse::<u32>().is_ok(),
+            ["schemas", "ids", id, ..] => id.parse::<u32>().is_ok(),
+            _ => false,
+        };
+
+        if self.cache.is_none() || *method != Method::GET || !immutable {
+            return None;
+        }
+
+        // Custom headers may select what the registry responds with (e.g., a tenant), so they're part of the key.
+        let mut headers: Vec<_> = self
+            .headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}", name, String::from_utf8_lossy(value.as_bytes())))
+            .collect();
+
+        headers.sort();
+
+        let query: Vec<_> = query.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        Some(format!(
+            "{} {} {}?{} {}",
+            self.urls[0],
+            self.group.as_deref().unwrap_or_default(),
+            path.join("/"),
+            query.join("&"),
+            headers.join(" ")
+        ))
+    }
+
+    /// Qualifies a subject name with the client's context, if any (unless already qualified).
+    pub fn qualify<'a>(&self, subject: &'a str) -> Cow<'a, str> {
+        match &self.context {
+            Some(context) if !subject.starts_with(":.") => {
+                Cow::Owned(format!(":.{}:{}", context, subject))
+            }
+            _ => Cow::Borrowed(subject),
+        }
+    }
+
+    /// Identifies the registry subjects go to, including the context or group, if any.
+    pub fn registry_key(&self) -> String {
+        let mut key = self
+            .urls
+            .iter()
+            .map(Url::as_str)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        if let Some(context) = &self.context {
+            key.push_str(&format!(" context={}", context));
+        }
+
+        if let Some(group) = &self.group {
+            key.push_str(&format!(" group={}", group));
+        }
+
+        key
+    }
+
+    /// Authenticates all requests with bearer tokens obtained using the given OAuth client credentials.
+    pub fn with_oauth(self, oauth: OAuth) -> Self {
+        Self {
+            auth: Some(Auth::OAuth(oauth)),
+            ..self
+        }
+    }
+
+    /// Authenticates all requests with the given username and password (e.g., API key and secret).
+    pub fn with_basic_auth(self, username: String, password: String) -> Self {
+        Self {
+            auth: Some(Auth::Basic { username, password }),
+            ..self
+        }
+    }
+
+    fn url(&self, base: &Url, path: &[&str]) -> Url {
+        let mut url = base.clone();
+        url.path_segments_mut()
+            .expect("base URL")
+            .pop_if_empty()
+            .extend(path);
+        url
+    }
+
+    /// Sends a single request, authenticated as configured.
+    /// Failing to obtain the token is an error in its own right, distinct from failing to send the request.
+    fn send(
+        &self,
+        method: &Method,
+        url: Url,
+        query: &[(&str, &str)],
+        body: Option<&Value>,
+    ) -> anyhow::Result<reqwest::Result<Response>> {
+        let mut req = self
+            .http
+            .request(method.clone(), url)
+            .query(query)
+            .header(ACCEPT, self.content_type());
+
+        if let Some(group) = &self.group {
+            req = req.header(APICURIO_GROUP_HEADER, group);
+        }
+
+        req = req.headers(self.headers.clone());
+
+        match &self.auth {
+            Some(Auth::Basic { username, password }) => {
+                req = req.basic_auth(username, Some(password));
+            }
+            Some(Auth::OAuth(oauth)) => {
+                req = req.bearer_auth(oauth.token(&self.http)?);
+            }
+            None => {}
+        }
+
+        if let Some(body) = body {
+            req = req
+                .header(CONTENT_TYPE, self.content_type())
+                .body(body.to_string());
+        }
+
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire();
+        }
+
+        Ok(req.send())
+    }
+
+    /// Sends a request, retrying it once with a new token if the current one is rejected,
+    /// and after a delay (as long as the registry asks for, if it does) while rate limited.
+    fn send_with_retries(
+        &self,
+        base: &Url,
+        method: &Method,
+        url: Url,
+        query: &[(&str, &str)],
+        body: Option<&Value>,
+    ) -> anyhow::Result<reqwest::Result<Response>> {
+        let mut token_refreshed = false;
+        let mut rate_limit_retries = 0;
+        loop {
+            let res = match self.send(method, url.clone(), query, body)? {
+                Ok(res) => res,
+                Err(e) => return Ok(Err(e)),
+            };
+
+            match (res.status(), &self.auth) {
+                // The token may have been revoked or expired early; get a new one and try once more.
+                (StatusCode::UNAUTHORIZED, Some(Auth::OAuth(oauth))) if !token_refreshed => {
+                    debug!("token rejected by {}; retrying with a new one", base);
+                    oauth.invalidate();
+                    token_refreshed = true;
+                }
+                (StatusCode::TOO_MANY_REQUESTS, _)
+                    if rate_limit_retries < MAX_RATE_LIMIT_RETRIES =>
+                {
+                    let delay = retry_after(&res)
+                        .unwrap_or_else(|| Duration::from_secs(1 << rate_limit_retries));
+
+                    warn!(
+                        "rate limited by {}; retrying in {}s",
+                        base,
+                        delay.as_secs_f32()
+                    );
+
+                    thread::sleep(delay);
+                    throttle::record_throttled(delay);
+                    rate_limit_retries += 1;
+                }
+                _ => return Ok(Ok(res)),
+            }
+        }
+    }
+
+    fn request<T>(&self, method: Method, path: &[&str], body: Option<&Value>) -> anyhow::Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        self.request_with_query(method, path, &[], body)
+    }
+
+    fn request_with_query<T>(
+        &self,
+        method: Method,
+        path: &[&str],
+        query: &[(&str, &str)],
+        body: Option<&Value>,
+    ) -> anyhow::Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let subject = match path {
+            ["subjects" | "config" | "mode", subject, ..] => *subject,
+            _ => "",
+        };
+
+        let cache_key = self.cache_key(&method, path, query);
+        if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+            if let Some(text) = cache.get(key) {
+                trace!("cached response: {}", text);
+                return serde_json::from_str(&text)
+                    .map_err(|e| anyhow::format_err!("invalid cached response: {}", e));
+            }
+        }
+
+        let mut last_err = None;
+        for base in &self.urls {
+            let url = self.url(base, path);
+            debug!("{} {}", method, url);
+
+            let span = tracing::info_span!(
+                target: SPAN_TARGET,
+                "registry request",
+                otel.kind = "client",
+                http.method = %method,
+                http.url = %url,
+                subject,
+                http.status_code = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+            );
+
+            let _entered = span.enter();
+            let start = Instant::now();
+            let res = self.send_with_retries(base, &method, url, query, body)?;
+            span.record("latency_ms", start.elapsed().as_millis() as u64);
+
+            let res = match res {
+                Ok(res) => res,
+                Err(e) => {
+                    warn!("error calling {}: {}", base, e);
+                    last_err = Some(e);
+                    continue;
+                }
+            };
+
+            let status = res.status();
+            span.record("http.status_code", status.as_u16());
+            let text = res.text()?;
+            trace!("response: {} {}", status, text);
+
+            if !status.is_success() {
+                let body: Option<ErrorBody> = serde_json::from_str(&text).ok();
+                let (error_code, message) = body
+                    .map(|body| (body.error_code, body.message))
+                    .unwrap_or_default();
+
+                return Err(Error {
+                    status: status.as_u16(),
+                    error_code,
+                    message: message.unwrap_or_else(|| status.to_string()),
+                }
+                .into());
+            }
+
+            let value = serde_json::from_str(&text)
+                .map_err(|e| anyhow::format_err!("invalid response from {}: {}", base, e))?;
+
+            if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+                cache.put(key, &text);
+            }
+
+            return Ok(value);
+        }
+
+        Err(anyhow::Error::new(last_err.expect("at least one URL"))
+            .context("error calling schema registry"))
+    }
+
+    /// Whether the given error means that none of the Schema Registry URLs could be reached.
+    pub fn is_unreachable(err: &anyhow::Error) -> bool {
+        err.downcast_ref::<reqwest::Error>().is_some()
+    }
+
+    /// Retrieves the given version (or `latest') of a subject's schema.
+    pub fn get_schema(&self, subject: &str, version: &str) -> anyhow::Result<Schema> {
+        let subject = self.qualify(subject);
+        self.request_with_query(
+            Method::GET,
+            &["subjects", &subject, "versions", version],
+            self.deleted_query(),
+            None,
+        )
+    }
+
+    /// Retrieves the latest version of a subject's schema whose metadata has all the given properties.
+    pub fn get_schema_with_metadata(
+        &self,
+        subject: &str,
+        properties: &[(String, String)],
+    ) -> anyhow::Result<Schema> {
+        let query: Vec<_> = properties
+            .iter()
+            .flat_map(|(key, value)| vec![("key", key.as_str()), ("value", value.as_str())])
+            .collect();
+
+        self.request_with_query(
+            Method::GET,
+            &["subjects", &self.qualify(subject), "metadata"],
+            &query,
+            None,
+        )
+    }
+
+    /// Registers the given schema under a subject along with its contract, registering any references first.
+    /// Returns the references as registered along with the schema's id.
+    pub fn register(
+        &self,
+        subject: &str,
+        schema: &SuppliedSchema,
+        contract: &Contract,
+    ) -> anyhow::Result<(u32, Vec<RegisteredReference>)> {
+        self.register_graph(
+            subject,
+            schema,
+            contract,
+            None,
+            &mut ReferenceGraph::default(),
+        )
+    }
+
+    /// Registers the given schema like `register', but with the given global id and version (references are
+    /// registered as usual). Fails unless the subject is in `IMPORT' mode.
+    pub fn register_imported(
+        &self,
+        subject: &str,
+        schema: &SuppliedSchema,
+        contract: &Contract,
+        import: Import,
+    ) -> anyhow::Result<(u32, Vec<RegisteredReference>)> {
+        anyhow::ensure!(
+            self.flavor != Flavor::Apicurio,
+            "Apicurio does not support registering schemas with a given id and version"
+        );
+
+        let mode = self.get_mode(subject)?;
+        anyhow::ensure!(
+            mode == "IMPORT",
+            "registering with a given id and version requires IMPORT mode, but {} is in {} mode",
+            subject,
+            mode
+        );
+
+        self.register_graph(
+            subject,
+            schema,
+            contract,
+            Some(import),
+            &mut ReferenceGraph::default(),
+        )
+    }
+
+    /// Registers the given schema like `register', except that references already registered with identical content
+    /// are not posted again. Also returns the outcome for each of the (transitive) references.
+    pub fn register_with_dependencies(
+        &self,
+        subject: &str,
+        schema: &SuppliedSchema,
+        contract: &Contract,
+    ) -> anyhow::Result<(u32, Vec<RegisteredReference>, Vec<Dependency>)> {
+        let mut graph = ReferenceGraph {
+            dependencies: Some(Vec::new()),
+            ..ReferenceGraph::default()
+        };
+
+        let (id, references) = self.register_graph(subject, schema, contract, None, &mut graph)?;
+        Ok((id, references, graph.dependencies.unwrap_or_default()))
+    }
+
+    fn register_graph(
+        &self,
+        subject: &str,
+        schema: &SuppliedSchema,
+        contract: &Contract,
+        import: Option<Import>,
+        graph: &mut ReferenceGraph,
+    ) -> anyhow::Result<(u32, Vec<RegisteredReference>)> {
+        let subject = self.qualify(subject);
+        let references =
+            self.register_references(&schema.schema_type, &schema.references, graph)?;
+        let mut body = schema_body(&schema.schema_type, &schema.schema, &references);
+        contract.add_to(&mut body);
+        if let Some(import) = import {
+            body["id"] = json!(import.id);
+            body["version"] = json!(import.version);
+        }
+        let res: SchemaId = self.request(
+            Method::POST,
+            &["subjects", &subject, "versions"],
+            Some(&body),
+        )?;
+
+        Ok((res.id, references))
+    }
+
+    /// Registers a schema as retrieved from the registry, including its references, metadata, and rules as they are.
+    /// This is how the metadata of a subject is changed; the registry creates a new version if it differs.
+    pub fn register_schema(&self, subject: &str, schema: &Schema) -> anyhow::Result<u32> {
+        let subject = self.qualify(subject);
+        let mut body = schema_body(&schema.schema_type(), &schema.schema, &schema.references);
+        schema.contract().add_to(&mut body);
+
+        let res: SchemaId = self.request(
+            Method::POST,
+            &["subjects", &subject, "versions"],
+            Some(&body),
+        )?;
+
+        Ok(res.id)
+    }
+
+    /// Registers the references (depth first), each only once however many schemas in the graph refer to it.
+    fn register_references(
+        &self,
+        schema_type: &SchemaType,
+        references: &[SuppliedReference],
+        graph: &mut ReferenceGraph,
+    ) -> anyhow::Result<Vec<RegisteredReference>> {
+        references
+            .iter()
+            .map(|reference| {
+                if let Some(&version) = graph.versions.get(&reference.name) {
+                    return Ok(RegisteredReference {
+                        name: reference.name.clone(),
+                        subject: reference.subject.clone(),
+                        version,
+                    });
+                }
+
+                let references =
+                    self.register_references(schema_type, &reference.references, graph)?;
+                let body = schema_body(schema_type, &reference.schema, &references);
+                let subject = self.qualify(&reference.subject);
+                let existing = match graph.dependencies {
+                    Some(_) => self.lookup_body(&subject, &body)?,
+                    None => None,
+                };
+
+                let registered = existing.is_none();
+                let res = match existing {
+                    Some(schema) => schema,
+                    None => {
+                        let _: SchemaId = self.request(
+                            Method::POST,
+                            &["subjects", &subject, "versions"],
+                            Some(&body),
+                        )?;
+
+                        self.request(Method::POST, &["subjects", &subject], Some(&body))?
+                    }
+                };
+
+                let version = res.version.ok_or_else(|| {
+                    anyhow::format_err!("missing version for {}", reference.subject)
+                })?;
+
+                graph.versions.insert(reference.name.clone(), version);
+                if let Some(dependencies) = &mut graph.dependencies {
+                    dependencies.push(Dependency {
+                        subject: reference.subject.clone(),
+                        version,
+                        registered,
+                    });
+                }
+
+                Ok(RegisteredReference {
+                    name: reference.name.clone(),
+                    subject: reference.subject.clone(),
+                    version,
+                })
+            })
+            .collect()
+    }
+
+    /// Looks up the given schema under a subject without registering anything.
+    /// Returns `None' if either the schema or any of its references is not registered.
+    pub fn lookup(&self, subject: &str, schema: &SuppliedSchema) -> anyhow::Result<Option<Schema>> {
+        let references = match self.lookup_references(
+            &schema.schema_type,
+            &schema.references,
+            &mut HashMap::new(),
+        )? {
+            Some(references) => references,
+            None => return Ok(None),
+        };
+
+        let body = schema_body(&schema.schema_type, &schema.schema, &references);
+        self.lookup_body(&self.qualify(subject), &body)
+    }
+
+    /// Looks up the references (depth first), each only once however many schemas in the graph refer to it;
+    /// `found' holds the versions of those found so far, by name.
+    fn lookup_references(
+        &self,
+        schema_type: &SchemaType,
+        references: &[SuppliedReference],
+        found: &mut HashMap<String, u32>,
+    ) -> anyhow::Result<Option<Vec<RegisteredReference>>> {
+        let mut registered = Vec::with_capacity(references.len());
+        for reference in references {
+            if let Some(&version) = found.get(&reference.name) {
+                registered.push(RegisteredReference {
+                    name: reference.name.clone(),
+                    subject: reference.subject.clone(),
+                    version,
+                });
+
+                continue;
+            }
+
+            let references =
+                match self.lookup_references(schema_type, &reference.references, found)? {
+                    Some(references) => references,
+                    None => return Ok(None),
+                };
+
+            let body = schema_body(schema_type, &reference.schema, &references);
+            match self.lookup_body(&self.qualify(&reference.subject), &body)? {
+                Some(Schema {
+                    version: Some(version),
+                    ..
+                }) => {
+                    found.insert(reference.name.clone(), version);
+                    registered.push(RegisteredReference {
+                        name: reference.name.clone(),
+                        subject: reference.subject.clone(),
+                        version,
+                    });
+                }
+                _ => return Ok(None),
+            }
+        }
+
+        Ok(Some(registered))
+    }
+
+    fn lookup_body(&self, subject: &str, body: &Value) -> anyhow::Result<Option<Schema>> {
+        match self.request(Method::POST, &["subjects", subject], Some(body)) {
+            Ok(schema) => Ok(Some(schema)),
+            Err(e) if Error::has_code(&e, &[SUBJECT_NOT_FOUND, SCHEMA_NOT_FOUND]) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Checks whether the given schema is compatible with the latest version registered under a subject.
+    /// A subject without any versions is trivially compatible.
+    pub fn check_compatibility(
+        &self,
+        subject: &str,
+        schema: &SuppliedSchema,
+    ) -> anyhow::Result<CompatibilityCheck> {
+        self.check_compatibility_with(subject, "latest", schema)
+    }
+
+    /// Checks whether the given schema is compatible with the given version (or `latest') of a subject.
+    pub fn check_compatibility_with(
+        &self,
+        subject: &str,
+        version: &str,
+        schema: &SuppliedSchema,
+    ) -> anyhow::Result<CompatibilityCheck> {
+        let references = match self.lookup_references(
+            &schema.schema_type,
+            &schema.references,
+            &mut HashMap::new(),
+        )? {
+            Some(references) => references,
+            None => return Ok(CompatibilityCheck::MissingReferences),
+        };
+
+        let body = schema_body(&schema.schema_type, &schema.schema, &references);
+        let res: CompatibilityResponse = match self.request_with_query(
+            Method::POST,
+            &[
+                "compatibility",
+                "subjects",
+                &self.qualify(subject),
+                "versions",
+                version,
+            ],
+            &[("verbose", "true")],
+            Some(&body),
+        ) {
+            Ok(res) => res,
+            Err(e) if Error::has_code(&e, &[SUBJECT_NOT_FOUND]) => {
+                return Ok(CompatibilityCheck::Compatible)
+            }
+            Err(e) => return Err(e),
+        };
+
+        if res.is_compatible {
+            Ok(CompatibilityCheck::Compatible)
+        } else {
+            Ok(CompatibilityCheck::Incompatible(res.messages))
+        }
+    }
+
+    /// Lists all schema contexts.
+    pub fn list_contexts(&self) -> anyhow::Result<Vec<String>> {
+        anyhow::ensure!(
+            self.flavor != Flavor::Apicurio,
+            "Apicurio does not support schema contexts"
+        );
+
+        self.request(Method::GET, &["contexts"], None)
+    }
+
+    fn ensure_exporters(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.flavor != Flavor::Apicurio,
+            "Apicurio does not support schema exporters"
+        );
+
+        Ok(())
+    }
+
+    /// Lists the names of all schema exporters.
+    pub fn list_exporters(&self) -> anyhow::Result<Vec<String>> {
+        self.ensure_exporters()?;
+        self.request(Method::GET, &["exporters"], None)
+    }
+
+    /// Retrieves the configuration of a schema exporter.
+    pub fn get_exporter(&self, name: &str) -> anyhow::Result<Exporter> {
+        self.ensure_exporters()?;
+        self.request(Method::GET, &["exporters", name], None)
+    }
+
+    /// Retrieves the state of a schema exporter.
+    pub fn get_exporter_status(&self, name: &str) -> anyhow::Result<ExporterStatus> {
+        self.ensure_exporters()?;
+        self.request(Method::GET, &["exporters", name, "status"], None)
+    }
+
+    /// Creates a schema exporter, which starts exporting right away.
+    pub fn create_exporter(&self, exporter: &Exporter) -> anyhow::Result<()> {
+        self.ensure_exporters()?;
+        let _: Value = self.request(Method::POST, &["exporters"], Some(&json!(exporter)))?;
+        Ok(())
+    }
+
+    /// Pauses a schema exporter.
+    pub fn pause_exporter(&self, name: &str) -> anyhow::Result<()> {
+        self.exporter_action(name, "pause")
+    }
+
+    /// Resumes a paused schema exporter.
+    pub fn resume_exporter(&self, name: &str) -> anyhow::Result<()> {
+        self.exporter_action(name, "resume")
+    }
+
+    /// Resets a schema exporter's offset, so that it exports all subjects again (once resumed, if paused).
+    pub fn reset_exporter(&self, name: &str) -> anyhow::Result<()> {
+        self.exporter_action(name, "reset")
+    }
+
+    fn exporter_action(&self, name: &str, action: &str) -> anyhow::Result<()> {
+        self.ensure_exporters()?;
+        let _: Value = self.request(Method::PUT, &["exporters", name, action], None)?;
+        Ok(())
+    }
+
+    /// Lists all subjects (in the context, if any).
+    pub fn list_subjects(&self) -> anyhow::Result<Vec<String>> {
+        let prefix = self
+            .context
+            .as_ref()
+            .map(|context| format!(":.{}:", context));
+        let mut query = self.deleted_query().to_vec();
+        if let Some(prefix) = &prefix {
+            query.push(("subjectPrefix", prefix));
+        }
+
+        self.request_with_query(Method::GET, &["subjects"], &query, None)
+    }
+
+    /// Lists the versions registered under a subject; empty if the subject does not exist.
+    pub fn get_versions(&self, subject: &str) -> anyhow::Result<Vec<u32>> {
+        match self.request_with_query(
+            Method::GET,
+            &["subjects", &self.qualify(subject), "versions"],
+            self.deleted_query(),
+            None,
+        ) {
+            Ok(versions) => Ok(versions),
+            Err(e) if Error::has_code(&e, &[SUBJECT_NOT_FOUND]) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Retrieves the compatibility level configured for a subject, if any.
+    pub fn get_compatibility(&self, subject: &str) -> anyhow::Result<Option<Compatibility>> {
+        match self.request::<Config>(Method::GET, &["config", &self.qualify(subject)], None) {
+            Ok(config) => Ok(Some(config.compatibility_level)),
+            Err(e)
+                if Error::has_code(
+                    &e,
+                    &[SUBJECT_NOT_FOUND, SUBJECT_COMPATIBILITY_NOT_CONFIGURED],
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Retrieves the compatibility level that applies to a subject: its own, or else the global one.
+    pub fn get_effective_compatibility(&self, subject: &str) -> anyhow::Result<Compatibility> {
+        match self.get_compatibility(subject)? {
+            Some(compatibility) => Ok(compatibility),
+            None => self.get_global_compatibility(),
+        }
+    }
+
+    /// Retrieves the global compatibility level (i.e., that of subjects without one of their own).
+    pub fn get_global_compatibility(&self) -> anyhow::Result<Compatibility> {
+        let config: Config = self.request(Method::GET, &["config"], None)?;
+        Ok(config.compatibility_level)
+    }
+
+    /// Retrieves the mode that applies to a subject (e.g., `READWRITE' or `IMPORT'): its own, or else the global one.
+    pub fn get_mode(&self, subject: &str) -> anyhow::Result<String> {
+        let res: ModeResponse =
+            match self.request(Method::GET, &["mode", &self.qualify(subject)], None) {
+                Ok(res) => res,
+                Err(e)
+                    if Error::has_code(
+                        &e,
+                        &[
+                            SUBJECT_NOT_FOUND,
+                            SUBJECT_COMPATIBILITY_NOT_CONFIGURED,
+                            SUBJECT_MODE_NOT_CONFIGURED,
+                        ],
+                    ) =>
+                {
+                    self.request(Method::GET, &["mode"], None)?
+                }
+                Err(e) => return Err(e),
+            };
+
+        Ok(res.mode)
+    }
+
+    /// Sets the compatibility level of a subject.
+    pub fn set_compatibility(
+        &self,
+        subject: &str,
+        compatibility: Compatibility,
+    ) -> anyhow::Result<()> {
+        let body = json!({ "compatibility": compatibility });
+        let _: Value = self.request(
+            Method::PUT,
+            &["config", &self.qualify(subject)],
+            Some(&body),
+        )?;
+        Ok(())
+    }
+
+    /// Removes the subject's own compatibility level so that the global one applies again.
+    pub fn delete_compatibility(&self, subject: &str) -> anyhow::Result<()> {
+        let _: Value = self.request(Method::DELETE, &["config", &self.qualify(subject)], None)?;
+        Ok(())
+    }
+
+    /// Deletes a subject with all its versions: soft-deletes it, and then, if so asked, deletes it permanently.
+    pub fn delete_subject(&self, subject: &str, permanent: bool) -> anyhow::Result<()> {
+        let subject = self.qualify(subject);
+        let _: Value = self.request(Method::DELETE, &["subjects", &subject], None)?;
+        if permanent {
+            let _: Value = self.request_with_query(
+                Method::DELETE,
+                &["subjects", &subject],
+                &[("permanent", "true")],
+                None,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Soft-deletes the given version of a subject.
+    pub fn delete_version(&self, subject: &str, version: u32) -> anyhow::Result<()> {
+        let _: Value = self.request(
+            Method::DELETE,
+            &[
+                "subjects",
+                &self.qualify(subject),
+                "versions",
+                &version.to_string(),
+            ],
+            None,
+        )?;
+        Ok(())
+    }
+}
+
+impl From<Schema> for RegisteredSchema {
+    fn from(schema: Schema) -> Self {
+        Self {
+            id: schema.id,
+            schema_type: schema.schema_type(),
+            schema: schema.schema,
+            references: schema.references,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Compatibility; 7] = [
+        Compatibility::None,
+        Compatibility::Backward,
+        Compatibility::BackwardTransitive,
+        Compatibility::Forward,
+        Compatibility::ForwardTransitive,
+        Compatibility::Full,
+        Compatibility::FullTransitive,
+    ];
+
+    #[test]
+    fn compatibility_satisfies_weaker_levels() {
+        use Compatibility::*;
+
+        let satisfied = |level: Compatibility| -> Vec<Compatibility> {
+            ALL.iter()
+                .copied()
+                .filter(|&required| level.satisfies(required))
+                .collect()
+        };
+
+        assert_eq!(satisfied(None), [None]);
+        assert_eq!(satisfied(Backward), [None, Backward]);
+        assert_eq!(
+            satisfied(BackwardTransitive),
+            [None, Backward, BackwardTransitive]
+        );
+        assert_eq!(satisfied(Forward), [None, Forward]);
+        assert_eq!(
+            satisfied(ForwardTransitive),
+            [None, Forward, ForwardTransitive]
+        );
+        assert_eq!(satisfied(Full), [None, Backward, Forward, Full]);
+        assert_eq!(satisfied(FullTransitive), ALL);
+    }
+
+    #[test]
+    fn compatibility_round_trips_through_strings() {
+        for level in ALL {
+            assert_eq!(level.to_string().parse::<Compatibility>().unwrap(), level);
+        }
+
+        assert_eq!(
+            "backward_transitive".parse::<Compatibility>().unwrap(),
+            Compatibility::BackwardTransitive
+        );
+        assert!("STRICT".parse::<Compatibility>().is_err());
+    }
+
+    #[test]
+    fn caches_only_immutable_responses() {
+        let client = Client::new(["http://registry:8081".to_owned()])
+            .unwrap()
+            .with_cache(Cache::new(None, crate::cache::DEFAULT_TTL).unwrap());
+
+        let key = |method: Method, path: &[&str]| client.cache_key(&method, path, &[]);
+
+        assert!(key(Method::GET, &["subjects", "orders-value", "versions", "3"]).is_some());
+        assert!(key(Method::GET, &["schemas", "ids", "7"]).is_some());
+        assert!(key(Method::GET, &["schemas", "ids", "7", "schema"]).is_some());
+
+        assert!(key(
+            Method::GET,
+            &["subjects", "orders-value", "versions", "latest"]
+        )
+        .is_none());
+        assert!(key(Method::GET, &["subjects", "orders-value", "versions", "-1"]).is_none());
+        assert!(key(Method::GET, &["subjects", "orders-value", "versions"]).is_none());
+        assert!(key(Method::GET, &["config", "orders-value"]).is_none());
+        assert!(key(
+            Method::DELETE,
+            &["subjects", "orders-value", "versions", "3"]
+        )
+        .is_none());
+
+        let uncached = Client::new(["http://registry:8081".to_owned()]).unwrap();
+        assert!(uncached
+            .cache_key(&Method::GET, &["schemas", "ids", "7"], &[])
+            .is_none());
+    }
+
+    #[test]
+    fn cache_keys_tell_registries_and_requests_apart() {
+        let client = |url: &str, headers: &[(&str, &str)]| {
+            let headers = headers
+                .iter()
+                .map(|(name, value)| (name.parse().unwrap(), value.parse().unwrap()))
+                .collect();
+
+            Client::new([url.to_owned()])
+                .unwrap()
+                .with_headers(headers)
+                .with_cache(Cache::new(None, crate::cache::DEFAULT_TTL).unwrap())
+        };
+
+        let path = ["schemas", "ids", "7"];
+        let key = |client: &Client, query: &[(&str, &str)]| {
+            client.cache_key(&Method::GET, &path, query).unwrap()
+        };
+
+        let a = client("http://a:8081", &[("x-tenant", "1"), ("x-env", "dev")]);
+        let same = client("http://a:8081", &[("x-env", "dev"), ("x-tenant", "1")]);
+        assert_eq!(key(&a, &[]), key(&same, &[]));
+
+        assert_ne!(key(&a, &[]), key(&a, &[("deleted", "true")]));
+        assert_ne!(
+            key(&a, &[]),
+            key(
+                &client("http://b:8081", &[("x-tenant", "1"), ("x-env", "dev")]),
+                &[]
+            )
+        );
+        assert_ne!(
+            key(&a, &[]),
+            key(
+                &client("http://a:8081", &[("x-tenant", "2"), ("x-env", "dev")]),
+                &[]
+            )
+        );
+
+        let group = client("http://a:8081", &[("x-tenant", "1"), ("x-env", "dev")])
+            .with_group("payments".to_owned());
+        assert_ne!(key(&a, &[]), key(&group, &[]));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{
+    exit::{
+        self,
+        Failure,
+    },
+    registry::{
+        Client,
+        Compatibility,
+        CompatibilityCheck,
+        Contract,
+    },
+    SchemaSource,
+};
+
+use gumdrop::Options;
+use log::*;
+use schema_registry_converter::schema_registry_common::get_subject;
+
+schema_file_settings! {
+    /// Replace the latest version of a subject with a new schema.
+    /// Soft-deletes the latest version and posts the schema in its place, checking first that it is compatible
+    /// with the version before; if posting fails, the deleted version is registered again.
+    /// Meant for early development, when versions churn rapidly.
+    #[derive(Debug, Options)]
+    pub struct ReplaceSettings {
+        file: "schema file (required; `-' to read from standard input)",
+
+        /// replace even if the schema is incompatible (by suspending the subject's compatibility checks)
+        #[options(no_short)]
+        force: bool,
+    }
+}
+
+pub fn run_replace(client: Client, settings: &ReplaceSettings) -> anyhow::Result<()> {
+    let schema = crate::supplied_schema(&SchemaSource::from(settings))?;
+    let sns = crate::subject_name_strategy_from_settings(
+        settings.topic.clone(),
+        settings.record.clone(),
+        settings.topic_key,
+    )?;
+
+    let subject =
+        get_subject(&sns).map_err(|e| anyhow::format_err!("error determining subject: {:?}", e))?;
+
+    let versions = client.get_versions(&subject)?;
+    let latest_version = *versions.last().ok_or_else(|| {
+        anyhow::format_err!(
+            "{} has no version to replace; post the schema instead",
+            subject
+        )
+    })?;
+
+    if let Some(registered) = client.lookup(&subject, &schema)? {
+        if registered.version == Some(latest_version) {
+            outln!("unchanged {} (version {})", subject, latest_version);
+            return Ok(());
+        }
+    }
+
+    let mut incompatible = false;
+    if let Some(previous) = versions.len().checked_sub(2).map(|i| versions[i]) {
+        match client.check_compatibility_with(&subject, &previous.to_string(), &schema)? {
+            CompatibilityCheck::Compatible => {}
+            CompatibilityCheck::Incompatible(messages) => {
+                let mut msg = format!("incompatible with version {} of {}", previous, subject);
+
+                for message in messages {
+                    msg.push_str("\n\t");
+                    msg.push_str(&message);
+                }
+
+                if !settings.force {
+                    msg.push_str("\n(use `--force' to replace anyway)");
+                    return Err(Failure::new(exit::INCOMPATIBLE, msg).into());
+                }
+
+                warn!("{}", msg);
+                incompatible = true;
+            }
+            CompatibilityCheck::MissingReferences => {
+                warn!(
+                    "cannot check {} before its new references are registered",
+                    subject
+                );
+            }
+        }
+    }
+
+    let latest = client.get_schema(&subject, &latest_version.to_string())?;
+    client.delete_version(&subject, latest_version)?;
+    outln!("deleted version {} of {}", latest_version, subject);
+
+    // The registry would reject an incompatible schema, so suspend its checks for the subject meanwhile.
+    let compatibility = if incompatible {
+        let compatibility = client.get_compatibility(&subject)?;
+        client.set_compatibility(&subject, Compatibility::None)?;
+        Some(compatibility)
+    } else {
+        None
+    };
+
+    let res = client.register(&subject, &schema, &Contract::default());
+
+    // Put the deleted version back first, so that the subject isn't left without it if anything else fails.
+    let res = match res {
+        Ok((id, _)) => {
+            outln!("registered {} (id {})", subject, id);
+            Ok(())
+        }
+        Err(e) => match client.register_schema(&subject, &latest) {
+            Ok(restored) => Err(e.context(format!(
+                "error posting schema to {}; the deleted version was registered again (id {})",
+                subject, restored
+            ))),
+            Err(restore_err) => Err(e.context(format!(
+                "error posting schema to {}; restoring the deleted version failed as well: {}",
+                subject, restore_err
+            ))),
+        },
+    };
+
+    let restored = match compatibility {
+        Some(Some(compatibility)) => client.set_compatibility(&subject, compatibility),
+        Some(None) => client.delete_compatibility(&subject),
+        None => Ok(()),
+    }
+    .map_err(|e| {
+        e.context(format!(
+            "error restoring the compatibility level of {}",
+            subject
+        ))
+    });
+
+    match (res, restored) {
+        (Err(e), Err(restore_err)) => {
+            warn!("{:#}", restore_err);
+            Err(e)
+        }
+        (res, restored) => res.and(restored),
+    }
+}
@@ -0,0 +1,1517 @@
+use crate::{
+    avro,
+    diff::VersionSpec,
+    registry::{
+        Client,
+        Schema,
+    },
+    search,
+};
+
+use base64::Engine;
+use gumdrop::Options;
+use log::*;
+
+use prost_types::{
+    field_descriptor_proto::{
+        Label,
+        Type,
+    },
+    DescriptorProto,
+    EnumDescriptorProto,
+    FieldDescriptorProto,
+    FileDescriptorProto,
+};
+
+use rand::{
+    rngs::StdRng,
+    seq::SliceRandom,
+    Rng,
+    SeedableRng,
+};
+
+use schema_registry_converter::schema_registry_common::{
+    get_subject,
+    RegisteredReference,
+    SchemaType,
+};
+
+use serde_json::{
+    Map,
+    Value,
+};
+
+use std::{
+    collections::{
+        BTreeMap,
+        HashMap,
+        HashSet,
+    },
+    fs,
+};
+
+/// Name of the file the protobuf schema is written to for compilation.
+const PROTO_ROOT: &str = "ksrt-sample-root.proto";
+
+/// How deep records (messages, objects) may nest before optional parts are left out, so that recursive types end.
+const MAX_DEPTH: usize = 5;
+
+/// Generate random example records that are valid according to a subject's schema, printed as JSON, one per line.
+/// With `--wire', each record is printed instead as the hex-encoded bytes a Confluent serializer would produce
+/// (magic byte, schema id, message indexes if protobuf, and the Avro, JSON, or protobuf encoding of the record).
+#[derive(Debug, Options)]
+pub struct SampleSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// topic name (required unless `--record' is specified)
+    #[options(meta = "NAME")]
+    topic: Option<String>,
+
+    /// whether the subject is for the topic key (vs. value; only with `--topic' alone)
+    #[options(short = "k")]
+    topic_key: bool,
+
+    /// record name (required unless `--topic' is specified)
+    #[options(meta = "NAME")]
+    record: Option<String>,
+
+    /// version to generate records for (a number, `latest', or `latest~N' for N versions before the latest)
+    #[options(no_short, meta = "VERSION", default = "latest")]
+    version: VersionSpec,
+
+    /// protobuf message to generate (default: the first one in the schema)
+    #[options(meta = "NAME")]
+    message: Option<String>,
+
+    /// number of records to generate
+    #[options(meta = "N", default = "1")]
+    count: usize,
+
+    /// seed for the random generator, to generate the same records again (default: random)
+    #[options(meta = "N")]
+    seed: Option<u64>,
+
+    /// print each record in the Confluent wire format, hex-encoded (e.g., `xxd -r -p' converts it back to binary)
+    wire: bool,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    pub schema_registry_url: Vec<String>,
+}
+
+/// Appends a variable-length integer.
+fn write_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+
+    out.push(value as u8);
+}
+
+/// Appends a zig-zag encoded, variable-length integer (as Avro longs and protobuf `sint64' are written).
+fn write_zigzag(value: i64, out: &mut Vec<u8>) {
+    write_varint(((value << 1) ^ (value >> 63)) as u64, out);
+}
+
+fn write_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    write_zigzag(bytes.len() as i64, out);
+    out.extend_from_slice(bytes);
+}
+
+/// Converts days since the Unix epoch to a (year, month, day) date in the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+struct Generator {
+    rng: StdRng,
+}
+
+impl Generator {
+    fn word(&mut self, min: usize, max: usize) -> String {
+        let len = self.rng.gen_range(min..=max.max(min));
+        (0..len)
+            .map(|_| char::from(self.rng.gen_range(b'a'..=b'z')))
+            .collect()
+    }
+
+    /// Random day between 2020-01-01 and the end of 2029, as days since the Unix epoch.
+    fn day(&mut self) -> i64 {
+        self.rng.gen_range(18_262..21_915)
+    }
+
+    fn date(&mut self) -> String {
+        let (year, month, day) = civil_from_days(self.day());
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+
+    fn time(&mut self) -> String {
+        let secs = self.rng.gen_range(0..86_400);
+        format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    }
+
+    fn uuid(&mut self) -> String {
+        let n: u128 = self.rng.gen();
+        // Version 4 (random), variant 1.
+        let n = (n & !(0xf << 76) | (0x4 << 76)) & !(0x3 << 62) | (0x2 << 62);
+        let hex = format!("{:032x}", n);
+        format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )
+    }
+
+    /// Random number rounded to two decimals.
+    fn decimal(&mut self, min: f64, max: f64) -> f64 {
+        let value = if min < max {
+            self.rng.gen_range(min..max)
+        } else {
+            min
+        };
+
+        let rounded = (value * 100.0).round() / 100.0;
+        if rounded < min || rounded > max {
+            value
+        } else {
+            rounded
+        }
+    }
+
+    /// Number of items to generate for an array or map at the given depth.
+    fn len(&mut self, depth: usize) -> usize {
+        if depth >= MAX_DEPTH {
+            0
+        } else {
+            self.rng.gen_range(0..=3)
+        }
+    }
+}
+
+/// Generates Avro data, both as JSON and in the binary encoding.
+struct AvroGenerator<'a> {
+    gen: Generator,
+    defs: HashMap<String, &'a Map<String, Value>>,
+}
+
+impl AvroGenerator<'_> {
+    fn primitive(&mut self, name: &str, logical: Option<&str>, out: &mut Vec<u8>) -> Value {
+        match (name, logical) {
+            ("null", _) => Value::Null,
+            ("boolean", _) => {
+                let value = self.gen.rng.gen_bool(0.5);
+                out.push(u8::from(value));
+                Value::from(value)
+            }
+            ("int", Some("date")) => {
+                let value = self.gen.day();
+                write_zigzag(value, out);
+                Value::from(value)
+            }
+            ("int", Some("time-millis")) => {
+                let value = self.gen.rng.gen_range(0..86_400_000);
+                write_zigzag(value, out);
+                Value::from(value)
+            }
+            ("int", _) => {
+                let value = self.gen.rng.gen_range(-1000..=1000);
+                write_zigzag(value, out);
+                Value::from(value)
+            }
+            ("long", Some(logical)) if logical.starts_with("timestamp") => {
+                let millis = self.gen.day() * 86_400_000 + self.gen.rng.gen_range(0..86_400_000);
+                let value = if logical.ends_with("micros") {
+                    millis * 1000
+                } else {
+                    millis
+                };
+
+                write_zigzag(value, out);
+                Value::from(value)
+            }
+            ("long", _) => {
+                let value = self.gen.rng.gen_range(-1_000_000..=1_000_000);
+                write_zigzag(value, out);
+                Value::from(value)
+            }
+            ("float", _) => {
+                let value = self.gen.decimal(-1000.0, 1000.0) as f32;
+                out.extend_from_slice(&value.to_le_bytes());
+                Value::from(f64::from(value))
+            }
+            ("double", _) => {
+                let value = self.gen.decimal(-1000.0, 1000.0);
+                out.extend_from_slice(&value.to_le_bytes());
+                Value::from(value)
+            }
+            ("string", Some("uuid")) => {
+                let value = self.gen.uuid();
+                write_bytes(value.as_bytes(), out);
+                Value::from(value)
+            }
+            // Bytes are ASCII letters, so that their JSON encoding (code points 0-255) reads as is.
+            (_, _) => {
+                let value = self.gen.word(3, 12);
+                write_bytes(value.as_bytes(), out);
+                Value::from(value)
+            }
+        }
+    }
+
+    fn generate(
+        &mut self,
+        schema: &Value,
+        namespace: &str,
+        depth: usize,
+        out: &mut Vec<u8>,
+    ) -> anyhow::Result<Value> {
+        let object = match schema {
+            Value::String(name) if avro::PRIMITIVES.contains(&name.as_str()) => {
+                return Ok(self.primitive(name, None, out));
+            }
+            Value::String(name) => {
+                let name = avro::full_name(name, namespace);
+                let def = self
+                    .defs
+                    .get(&name)
+                    .copied()
+                    .ok_or_else(|| anyhow::format_err!("undefined Avro type: {}", name))?;
+
+                return self.named(def, &name, depth, out);
+            }
+            Value::Array(branches) => return self.union(branches, namespace, depth, out),
+            Value::Object(object) => object,
+            _ => anyhow::bail!("invalid Avro schema: {}", schema),
+        };
+
+        let logical = object.get("logicalType").and_then(Value::as_str);
+        match object.get("type") {
+            Some(Value::String(kind)) if avro::PRIMITIVES.contains(&kind.as_str()) => {
+                Ok(self.primitive(kind, logical, out))
+            }
+            Some(Value::String(kind))
+                if ["record", "error", "enum", "fixed"].contains(&kind.as_str()) =>
+            {
+                let name = avro::defined_name(object, namespace);
+                self.named(object, &name, depth, out)
+            }
+            Some(Value::String(kind)) if kind == "array" => {
+                let items = object
+                    .get("items")
+                    .ok_or_else(|| anyhow::format_err!("array without items"))?;
+
+                let len = self.gen.len(depth);
+                let mut values = Vec::with_capacity(len);
+                if len > 0 {
+                    write_zigzag(len as i64, out);
+                }
+
+                for _ in 0..len {
+                    values.push(self.generate(items, namespace, depth + 1, out)?);
+                }
+
+                out.push(0);
+                Ok(Value::Array(values))
+            }
+            Some(Value::String(kind)) if kind == "map" => {
+                let values = object
+                    .get("values")
+                    .ok_or_else(|| anyhow::format_err!("map without values"))?;
+
+                let len = self.gen.len(depth);
+                let mut entries = Map::new();
+                let mut block = Vec::new();
+                while entries.len() < len {
+                    let key = self.gen.word(3, 8);
+                    if entries.contains_key(&key) {
+                        continue;
+                    }
+
+                    write_bytes(key.as_bytes(), &mut block);
+                    let value = self.generate(values, namespace, depth + 1, &mut block)?;
+                    entries.insert(key, value);
+                }
+
+                if len > 0 {
+                    write_zigzag(len as i64, out);
+                    out.extend(block);
+                }
+
+                out.push(0);
+                Ok(Value::Object(entries))
+            }
+            Some(schema) => self.generate(schema, namespace, depth, out),
+            None => anyhow::bail!("invalid Avro schema (no type): {}", schema),
+        }
+    }
+
+    fn union(
+        &mut self,
+        branches: &[Value],
+        namespace: &str,
+        depth: usize,
+        out: &mut Vec<u8>,
+    ) -> anyhow::Result<Value> {
+        anyhow::ensure!(!branches.is_empty(), "invalid Avro schema (empty union)");
+
+        // Nulls end recursive types.
+        let null = branches.iter().position(|branch| {
+            branch == "null" || branch.get("type") == Some(&Value::from("null"))
+        });
+
+        let index = match null {
+            Some(index) if depth >= MAX_DEPTH => index,
+            _ => self.gen.rng.gen_range(0..branches.len()),
+        };
+
+        write_zigzag(index as i64, out);
+        self.generate(&branches[index], namespace, depth, out)
+    }
+
+    fn named(
+        &mut self,
+        def: &Map<String, Value>,
+        name: &str,
+        depth: usize,
+        out: &mut Vec<u8>,
+    ) -> anyhow::Result<Value> {
+        let namespace = avro::namespace_of(name).to_owned();
+        match def.get("type").and_then(Value::as_str) {
+            Some("record") | Some("error") => {
+                let mut record = Map::new();
+                let fields = def.get("fields").and_then(Value::as_array);
+                for field in fields.into_iter().flatten() {
+                    let field_name = field
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .ok_or_else(|| anyhow::format_err!("field without a name in {}", name))?;
+
+                    let schema = field.get("type").ok_or_else(|| {
+                        anyhow::format_err!("field without a type: {}.{}", name, field_name)
+                    })?;
+
+                    let value = self.generate(schema, &namespace, depth + 1, out)?;
+                    record.insert(field_name.to_owned(), value);
+                }
+
+                Ok(Value::Object(record))
+            }
+            Some("enum") => {
+                let symbols = def
+                    .get("symbols")
+                    .and_then(Value::as_array)
+                    .filter(|symbols| !symbols.is_empty())
+                    .ok_or_else(|| anyhow::format_err!("enum without symbols: {}", name))?;
+
+                let index = self.gen.rng.gen_range(0..symbols.len());
+                write_zigzag(index as i64, out);
+                Ok(symbols[index].clone())
+            }
+            Some("fixed") => {
+                let size = def
+                    .get("size")
+                    .and_then(Value::as_u64)
+                    .ok_or_else(|| anyhow::format_err!("fixed without a size: {}", name))?;
+
+                let value = self.gen.word(size as usize, size as usize);
+                out.extend_from_slice(value.as_bytes());
+                Ok(Value::from(value))
+            }
+            _ => anyhow::bail!("invalid Avro schema for {}", name),
+        }
+    }
+}
+
+/// Generates instances of JSON schemas (only the JSON, since that is also what goes on the wire).
+struct JsonGenerator<'a> {
+    gen: Generator,
+    root: &'a Value,
+    /// Referenced schemas, by their reference names.
+    references: &'a HashMap<String, Value>,
+}
+
+impl<'a> JsonGenerator<'a> {
+    /// Resolves a reference, either within the document (`#/...') or to a referenced one (`name#/...').
+    fn resolve(&self, reference: &str, doc: &'a Value) -> anyhow::Result<(&'a Value, &'a Value)> {
+        let (name, pointer) = reference.split_once('#').unwrap_or((reference, ""));
+        let doc = if name.is_empty() {
+            doc
+        } else {
+            self.references
+                .get(name)
+                .ok_or_else(|| anyhow::format_err!("unresolved reference: {}", reference))?
+        };
+
+        let schema = doc
+            .pointer(pointer)
+            .ok_or_else(|| anyhow::format_err!("unresolved reference: {}", reference))?;
+
+        Ok((schema, doc))
+    }
+
+    fn generate(&mut self, schema: &Value, doc: &'a Value, depth: usize) -> anyhow::Result<Value> {
+        let object = match schema {
+            Value::Object(object) => object,
+            Value::Bool(true) => return Ok(Value::from(self.gen.word(3, 10))),
+            _ => anyhow::bail!("cannot generate an instance of schema: {}", schema),
+        };
+
+        if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+            let (schema, doc) = self.resolve(reference, doc)?;
+            return self.generate(schema, doc, depth);
+        }
+
+        if let Some(value) = object.get("const") {
+            return Ok(value.clone());
+        }
+
+        if let Some(values) = object.get("enum").and_then(Value::as_array) {
+            return values
+                .choose(&mut self.gen.rng)
+                .cloned()
+                .ok_or_else(|| anyhow::format_err!("empty enum"));
+        }
+
+        for keyword in ["oneOf", "anyOf"] {
+            if let Some(branches) = object.get(keyword).and_then(Value::as_array) {
+                if let Some(branch) = branches.choose(&mut self.gen.rng) {
+                    if !object.contains_key("type") && !object.contains_key("properties") {
+                        return self.generate(branch, doc, depth);
+                    }
+                }
+            }
+        }
+
+        if let Some(branches) = object.get("allOf").and_then(Value::as_array) {
+            let merged = self.merge(object, branches, doc)?;
+            return self.object(&merged, doc, depth);
+        }
+
+        let types: Vec<&str> = match object.get("type") {
+            Some(Value::String(name)) => vec![name],
+            Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+            _ if object.contains_key("properties") => vec!["object"],
+            _ if object.contains_key("items") => vec!["array"],
+            _ => vec!["string"],
+        };
+
+        // Nulls end recursive types.
+        let kind = match types.as_slice() {
+            types if depth >= MAX_DEPTH && types.contains(&"null") => "null",
+            types => types
+                .choose(&mut self.gen.rng)
+                .copied()
+                .ok_or_else(|| anyhow::format_err!("no type allowed"))?,
+        };
+
+        let bound = |keyword: &str| object.get(keyword).and_then(Value::as_f64);
+        let value = match kind {
+            "null" => Value::Null,
+            "boolean" => Value::from(self.gen.rng.gen_bool(0.5)),
+            "integer" => {
+                let (min, max) = (
+                    bound("minimum")
+                        .or_else(|| bound("exclusiveMinimum").map(|min| min.floor() + 1.0))
+                        .map(|min| min.ceil() as i64),
+                    bound("maximum")
+                        .or_else(|| bound("exclusiveMaximum").map(|max| max.ceil() - 1.0))
+                        .map(|max| max.floor() as i64),
+                );
+
+                let (min, max) = match (min, max) {
+                    (Some(min), Some(max)) => (min, max),
+                    (Some(min), None) => (min, min.max(0).saturating_add(1000)),
+                    (None, Some(max)) => (max.min(0).saturating_sub(1000), max),
+                    (None, None) => (0, 1000),
+                };
+
+                let step = bound("multipleOf").map_or(1, |step| step.max(1.0) as i64);
+
+                let (low, high) = (div_ceil(min, step), max.div_euclid(step));
+                anyhow::ensure!(low <= high, "no integer satisfies the bounds of {}", schema);
+                Value::from(self.gen.rng.gen_range(low..=high) * step)
+            }
+            "number" => {
+                let (min, max) = (
+                    bound("minimum").or_else(|| bound("exclusiveMinimum")),
+                    bound("maximum").or_else(|| bound("exclusiveMaximum")),
+                );
+
+                let (min, max) = match (min, max) {
+                    (Some(min), Some(max)) => (min, max),
+                    (Some(min), None) => (min, min.max(0.0) + 1000.0),
+                    (None, Some(max)) => (max.min(0.0) - 1000.0, max),
+                    (None, None) => (0.0, 1000.0),
+                };
+
+                let value = self.gen.decimal(min, max);
+                let excluded = (object.contains_key("exclusiveMinimum") && value <= min)
+                    || (object.contains_key("exclusiveMaximum") && value >= max);
+
+                Value::from(if excluded { (min + max) / 2.0 } else { value })
+            }
+            "string" => Value::from(self.string(object)),
+            "array" => {
+                let min = object.get("minItems").and_then(Value::as_u64).unwrap_or(0) as usize;
+                let max = object
+                    .get("maxItems")
+                    .and_then(Value::as_u64)
+                    .map_or(min + 3, |max| max as usize);
+
+                let len = if depth >= MAX_DEPTH {
+                    min
+                } else {
+                    self.gen.rng.gen_range(min..=max.max(min))
+                };
+
+                let items = object.get("items").unwrap_or(&Value::Bool(true));
+                let mut values = Vec::with_capacity(len);
+                for _ in 0..len {
+                    values.push(self.generate(items, doc, depth + 1)?);
+                }
+
+                Value::Array(values)
+            }
+            "object" => return self.object(object, doc, depth),
+            other => anyhow::bail!("unsupported type: {}", other),
+        };
+
+        Ok(value)
+    }
+
+    /// Merges the properties and required properties of `allOf' branches (and of the schema itself) into one object schema.
+    fn merge(
+        &self,
+        object: &Map<String, Value>,
+        branches: &[Value],
+        doc: &'a Value,
+    ) -> anyhow::Result<Map<String, Value>> {
+        let mut merged = object.clone();
+        merged.shift_remove("allOf");
+        for branch in branches {
+            let branch: &Value = match branch.get("$ref").and_then(Value::as_str) {
+                Some(reference) => self.resolve(reference, doc)?.0,
+                None => branch,
+            };
+
+            let branch = match branch {
+                Value::Object(branch) => branch,
+                _ => continue,
+            };
+
+            for (key, value) in branch {
+                match (key.as_str(), merged.get_mut(key)) {
+                    ("properties", Some(Value::Object(properties))) => {
+                        if let Value::Object(more) = value {
+                            properties.extend(more.clone());
+                        }
+                    }
+                    ("required", Some(Value::Array(required))) => {
+                        if let Value::Array(more) = value {
+                            required.extend(more.iter().cloned());
+                        }
+                    }
+                    (_, Some(_)) => {}
+                    (_, None) => {
+                        merged.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(merged)
+    }
+
+    /// Generates an object with all the required properties and a random selection of the others.
+    fn object(
+        &mut self,
+        object: &Map<String, Value>,
+        doc: &'a Value,
+        depth: usize,
+    ) -> anyhow::Result<Value> {
+        let required: HashSet<&str> = object
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect();
+
+        let mut instance = Map::new();
+        let properties = object.get("properties").and_then(Value::as_object);
+        for (name, schema) in properties.into_iter().flatten() {
+            if required.contains(name.as_str())
+                || (depth < MAX_DEPTH && self.gen.rng.gen_bool(0.75))
+            {
+                let value = self.generate(schema, doc, depth + 1)?;
+                instance.insert(name.clone(), value);
+            }
+        }
+
+        for name in required {
+            if !instance.contains_key(name) {
+                instance.insert(name.to_owned(), Value::from(self.gen.word(3, 10)));
+            }
+        }
+
+        Ok(Value::Object(instance))
+    }
+
+    fn string(&mut self, object: &Map<String, Value>) -> String {
+        match object.get("format").and_then(Value::as_str) {
+            Some("date-time") => format!("{}T{}Z", self.gen.date(), self.gen.time()),
+            Some("date") => self.gen.date(),
+            Some("time") => format!("{}Z", self.gen.time()),
+            Some("email") => format!("{}@example.com", self.gen.word(3, 10)),
+            Some("uuid") => self.gen.uuid(),
+            Some("uri") => format!("https://example.com/{}", self.gen.word(3, 10)),
+            Some("hostname") => format!("{}.example.com", self.gen.word(3, 10)),
+            Some("ipv4") => {
+                let octets: [u8; 4] = self.gen.rng.gen();
+                format!("{}.{}.{}.{}", octets[0], octets[1], octets[2], octets[3])
+            }
+            format => {
+                if let Some(format) = format {
+                    debug!("ignoring unsupported string format: {}", format);
+                }
+
+                if object.contains_key("pattern") {
+                    debug!("ignoring string pattern (values may not match)");
+                }
+
+                let max = object.get("maxLength").and_then(Value::as_u64);
+                let min = object
+                    .get("minLength")
+                    .and_then(Value::as_u64)
+                    .unwrap_or_else(|| max.unwrap_or(3).min(3)) as usize;
+                let max = max.map_or(min.max(12), |max| max as usize);
+
+                self.gen.word(min, max)
+            }
+        }
+    }
+}
+
+fn div_ceil(n: i64, d: i64) -> i64 {
+    -(-n).div_euclid(d)
+}
+
+/// Index of the (real, not synthetic) oneof a field belongs to, if any.
+fn oneof_index(message: &DescriptorProto, field: &FieldDescriptorProto) -> Option<i32> {
+    let index = field.oneof_index?;
+    let oneof = message.oneof_decl.get(index as usize)?.name();
+    let members = message
+        .field
+        .iter()
+        .filter(|f| f.oneof_index == Some(index))
+        .count();
+
+    // Proto3 optional fields are each wrapped in a synthetic oneof of their own.
+    if members == 1 && oneof.starts_with('_') {
+        None
+    } else {
+        Some(index)
+    }
+}
+
+/// Generates protobuf messages, both as JSON (per the proto3 JSON mapping) and in the binary encoding.
+struct ProtobufGenerator<'a> {
+    gen: Generator,
+    /// Messages by their fully-qualified names (with the leading `.').
+    messages: HashMap<String, &'a DescriptorProto>,
+    enums: HashMap<String, &'a EnumDescriptorProto>,
+}
+
+impl<'a> ProtobufGenerator<'a> {
+    fn new(gen: Generator, files: &'a [FileDescriptorProto]) -> Self {
+        let mut generator = Self {
+            gen,
+            messages: HashMap::new(),
+            enums: HashMap::new(),
+        };
+
+        for fd in files {
+            let scope = if fd.package().is_empty() {
+                String::new()
+            } else {
+                format!(".{}", fd.package())
+            };
+
+            for message in &fd.message_type {
+                generator.add_message(message, &scope);
+            }
+
+            for enum_type in &fd.enum_type {
+                generator
+                    .enums
+                    .insert(format!("{}.{}", scope, enum_type.name()), enum_type);
+            }
+        }
+
+        generator
+    }
+
+    fn add_message(&mut self, message: &'a DescriptorProto, scope: &str) {
+        let name = format!("{}.{}", scope, message.name());
+        for nested in &message.nested_type {
+            self.add_message(nested, &name);
+        }
+
+        for enum_type in &message.enum_type {
+            self.enums
+                .insert(format!("{}.{}", name, enum_type.name()), enum_type);
+        }
+
+        self.messages.insert(name, message);
+    }
+
+    fn message(&mut self, name: &str, depth: usize, out: &mut Vec<u8>) -> anyhow::Result<Value> {
+        let message = *self
+            .messages
+            .get(name)
+            .ok_or_else(|| anyhow::format_err!("undefined message type: {}", name))?;
+
+        // Only one field of each oneof may be set.
+        let mut chosen = HashMap::new();
+        for field in &message.field {
+            if let Some(index) = oneof_index(message, field) {
+                let members = chosen.entry(index).or_insert_with(Vec::new);
+                members.push(field.number());
+            }
+        }
+
+        let chosen: HashSet<i32> = chosen
+            .values()
+            .filter_map(|members| members.choose(&mut self.gen.rng).copied())
+            .collect();
+
+        let mut json = Map::new();
+        for field in &message.field {
+            let json_name = match field.json_name() {
+                "" => field.name(),
+                json_name => json_name,
+            };
+
+            if oneof_index(message, field).is_some() {
+                if !chosen.contains(&field.number()) {
+                    continue;
+                }
+            } else if field.oneof_index.is_some() && !self.gen.rng.gen_bool(0.5) {
+                continue;
+            }
+
+            if field.label() == Label::Repeated {
+                let len = self.gen.len(depth);
+                let entry = self
+                    .messages
+                    .get(field.type_name())
+                    .copied()
+                    .filter(|entry| entry.options.as_ref().and_then(|o| o.map_entry) == Some(true));
+
+                let value = match entry {
+                    Some(entry) => {
+                        let (key_field, value_field) = match entry.field.as_slice() {
+                            [key, value] => (key, value),
+                            _ => anyhow::bail!("invalid map entry: {}", field.type_name()),
+                        };
+
+                        let mut entries = Map::new();
+                        for _ in 0..len {
+                            let mut bytes = Vec::new();
+                            let key = self.field(key_field, depth + 1, &mut bytes)?;
+                            let value = self.field(value_field, depth + 1, &mut bytes)?;
+                            let key = match key {
+                                Value::String(key) => key,
+                                key => key.to_string(),
+                            };
+
+                            if entries.insert(key, value).is_none() {
+                                write_varint(u64::from(field.number() as u32) << 3 | 2, out);
+                                write_varint(bytes.len() as u64, out);
+                                out.extend(bytes);
+                            }
+                        }
+
+                        Value::Object(entries)
+                    }
+                    None => {
+                        let mut values = Vec::with_capacity(len);
+                        for _ in 0..len {
+                            values.push(self.field(field, depth + 1, out)?);
+                        }
+
+                        Value::Array(values)
+                    }
+                };
+
+                json.insert(json_name.to_owned(), value);
+            } else if field.r#type() != Type::Message
+                || field.label() == Label::Required
+                || (depth < MAX_DEPTH && self.gen.rng.gen_bool(0.5))
+            {
+                let value = self.field(field, depth + 1, out)?;
+                json.insert(json_name.to_owned(), value);
+            }
+        }
+
+        Ok(Value::Object(json))
+    }
+
+    /// Generates a value of a field, writing it along with its tag.
+    fn field(
+        &mut self,
+        field: &FieldDescriptorProto,
+        depth: usize,
+        out: &mut Vec<u8>,
+    ) -> anyhow::Result<Value> {
+        let tag = u64::from(field.number() as u32) << 3;
+        let rng = &mut self.gen.rng;
+        let value = match field.r#type() {
+            Type::Double => {
+                let value = self.gen.decimal(-1000.0, 1000.0);
+                write_varint(tag | 1, out);
+                out.extend_from_slice(&value.to_le_bytes());
+                Value::from(value)
+            }
+            Type::Float => {
+                let value = self.gen.decimal(-1000.0, 1000.0) as f32;
+                write_varint(tag | 5, out);
+                out.extend_from_slice(&value.to_le_bytes());
+                Value::from(f64::from(value))
+            }
+            Type::Int32 | Type::Int64 => {
+                let value: i64 = rng.gen_range(-1000..=1000);
+                write_varint(tag, out);
+                write_varint(value as u64, out);
+                int_value(field, value)
+            }
+            Type::Uint32 | Type::Uint64 => {
+                let value: i64 = rng.gen_range(0..=1000);
+                write_varint(tag, out);
+                write_varint(value as u64, out);
+                int_value(field, value)
+            }
+            Type::Sint32 | Type::Sint64 => {
+                let value: i64 = rng.gen_range(-1000..=1000);
+                write_varint(tag, out);
+                write_zigzag(value, out);
+                int_value(field, value)
+            }
+            Type::Fixed32 | Type::Sfixed32 => {
+                let value: i32 = if field.r#type() == Type::Fixed32 {
+                    rng.gen_range(0..=1000)
+                } else {
+                    rng.gen_range(-1000..=1000)
+                };
+
+                write_varint(tag | 5, out);
+                out.extend_from_slice(&value.to_le_bytes());
+                Value::from(value)
+            }
+            Type::Fixed64 | Type::Sfixed64 => {
+                let value: i64 = if field.r#type() == Type::Fixed64 {
+                    rng.gen_range(0..=1000)
+                } else {
+                    rng.gen_range(-1000..=1000)
+                };
+
+                write_varint(tag | 1, out);
+                out.extend_from_slice(&value.to_le_bytes());
+                int_value(field, value)
+            }
+            Type::Bool => {
+                let value = rng.gen_bool(0.5);
+                write_varint(tag, out);
+                write_varint(u64::from(value), out);
+                Value::from(value)
+            }
+            Type::String => {
+                let value = self.gen.word(3, 12);
+                write_varint(tag | 2, out);
+                write_varint(value.len() as u64, out);
+                out.extend_from_slice(value.as_bytes());
+                Value::from(value)
+            }
+            Type::Bytes => {
+                let len = rng.gen_range(1..=16);
+                let value: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+                write_varint(tag | 2, out);
+                write_varint(value.len() as u64, out);
+                out.extend_from_slice(&value);
+                Value::from(base64::engine::general_purpose::STANDARD.encode(value))
+            }
+            Type::Enum => {
+                let enum_type = self.enums.get(field.type_name()).copied().ok_or_else(|| {
+                    anyhow::format_err!("undefined enum type: {}", field.type_name())
+                })?;
+
+                let value = enum_type.value.choose(rng).ok_or_else(|| {
+                    anyhow::format_err!("enum without values: {}", field.type_name())
+                })?;
+
+                write_varint(tag, out);
+                write_varint(value.number() as u64, out);
+                Value::from(value.name())
+            }
+            Type::Message => {
+                let mut bytes = Vec::new();
+                let value = self.message(field.type_name(), depth, &mut bytes)?;
+                write_varint(tag | 2, out);
+                write_varint(bytes.len() as u64, out);
+                out.extend(bytes);
+                value
+            }
+            Type::Group => anyhow::bail!("groups are not supported: {}", field.name()),
+        };
+
+        Ok(value)
+    }
+}
+
+/// JSON value of an integer field; 64-bit integers are strings in the proto3 JSON mapping.
+fn int_value(field: &FieldDescriptorProto, value: i64) -> Value {
+    match field.r#type() {
+        Type::Int64 | Type::Uint64 | Type::Sint64 | Type::Fixed64 | Type::Sfixed64 => {
+            Value::from(value.to_string())
+        }
+        _ => Value::from(value),
+    }
+}
+
+/// Locates a message in a file, returning its fully-qualified name and its indexes
+/// (i.e., its position among the file's messages, and then among each enclosing message's nested messages).
+fn find_message(
+    fd: &FileDescriptorProto,
+    name: Option<&str>,
+) -> anyhow::Result<(String, Vec<usize>)> {
+    fn find(
+        messages: &[DescriptorProto],
+        scope: &str,
+        name: &str,
+        indexes: &mut Vec<usize>,
+    ) -> Option<String> {
+        for (i, message) in messages.iter().enumerate() {
+            let full_name = format!("{}.{}", scope, message.name());
+            indexes.push(i);
+            if full_name.trim_start_matches('.') == name {
+                return Some(full_name);
+            }
+
+            if let Some(found) = find(&message.nested_type, &full_name, name, indexes) {
+                return Some(found);
+            }
+
+            indexes.pop();
+        }
+
+        None
+    }
+
+    let scope = if fd.package().is_empty() {
+        String::new()
+    } else {
+        format!(".{}", fd.package())
+    };
+
+    let name = match name {
+        Some(name)
+            if !fd.package().is_empty() && !name.starts_with(&format!("{}.", fd.package())) =>
+        {
+            format!("{}.{}", fd.package(), name)
+        }
+        Some(name) => name.to_owned(),
+        None => {
+            let first = fd
+                .message_type
+                .first()
+                .ok_or_else(|| anyhow::format_err!("schema defines no messages"))?;
+
+            return Ok((format!("{}.{}", scope, first.name()), vec![0]));
+        }
+    };
+
+    let mut indexes = Vec::new();
+    let full_name = find(&fd.message_type, &scope, &name, &mut indexes)
+        .ok_or_else(|| anyhow::format_err!("message not found in schema: {}", name))?;
+
+    Ok((full_name, indexes))
+}
+
+/// Retrieves the given references, and theirs in turn, as schema text by reference name.
+fn get_references(
+    client: &Client,
+    references: &[RegisteredReference],
+    schemas: &mut BTreeMap<String, String>,
+) -> anyhow::Result<()> {
+    for reference in references {
+        if schemas.contains_key(&reference.name) {
+            continue;
+        }
+
+        let schema = client
+            .get_schema(&reference.subject, &reference.version.to_string())
+            .map_err(|e| {
+                e.context(format!(
+                    "error retrieving reference {}/{}",
+                    reference.subject, reference.version
+                ))
+            })?;
+
+        schemas.insert(reference.name.clone(), schema.schema);
+        get_references(client, &schema.references, schemas)?;
+    }
+
+    Ok(())
+}
+
+fn parse_json(name: &str, text: &str) -> anyhow::Result<Value> {
+    serde_json::from_str(text).map_err(|e| anyhow::format_err!("invalid schema {}: {}", name, e))
+}
+
+/// Message indexes as the serializer writes them ahead of a protobuf payload:
+/// just 0 for the first message, and the count and indexes otherwise.
+fn message_indexes(indexes: &[usize]) -> Vec<u8> {
+    let mut header = Vec::new();
+    if indexes == [0] {
+        header.push(0);
+    } else {
+        write_zigzag(indexes.len() as i64, &mut header);
+        for index in indexes {
+            write_zigzag(*index as i64, &mut header);
+        }
+    }
+
+    header
+}
+
+/// Hex-encodes a record in the Confluent wire format (magic byte, schema id, and payload).
+fn wire_frame(id: u32, payload: &[u8]) -> String {
+    std::iter::once(0)
+        .chain(id.to_be_bytes())
+        .chain(payload.iter().copied())
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Generates the given number of records of a schema, whose references are given as schema text by name,
+/// passing each to `emit' as JSON along with its serialized payload (including any message indexes,
+/// but not the magic byte and schema id).
+fn generate<F>(
+    schema: &Schema,
+    texts: &BTreeMap<String, String>,
+    message: Option<&str>,
+    count: usize,
+    gen: Generator,
+    mut emit: F,
+) -> anyhow::Result<()>
+where
+    F: FnMut(Value, Vec<u8>) -> anyhow::Result<()>,
+{
+    let schema_type = schema.schema_type();
+    anyhow::ensure!(
+        message.is_none() || schema_type == SchemaType::Protobuf,
+        "`--message' applies to protobuf schemas only"
+    );
+
+    match schema_type {
+        SchemaType::Avro => {
+            let root = parse_json("", &schema.schema)?;
+            let references = texts
+                .iter()
+                .map(|(name, text)| parse_json(name, text))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let mut defs = HashMap::new();
+            for schema in references.iter().chain(std::iter::once(&root)) {
+                avro::definitions(schema, "", &mut defs);
+            }
+
+            let mut generator = AvroGenerator { gen, defs };
+            for _ in 0..count {
+                let mut payload = Vec::new();
+                let value = generator.generate(&root, "", 0, &mut payload)?;
+                emit(value, payload)?;
+            }
+        }
+        SchemaType::Json => {
+            let root = parse_json("", &schema.schema)?;
+            let references = texts
+                .iter()
+                .map(|(name, text)| Ok((name.clone(), parse_json(name, text)?)))
+                .collect::<anyhow::Result<HashMap<_, _>>>()?;
+
+            let mut generator = JsonGenerator {
+                gen,
+                root: &root,
+                references: &references,
+            };
+
+            for _ in 0..count {
+                let value = generator.generate(generator.root, generator.root, 0)?;
+                let payload = serde_json::to_vec(&value)?;
+                emit(value, payload)?;
+            }
+        }
+        SchemaType::Protobuf => {
+            let tmp = tempfile::Builder::new().prefix("ksrt-sample").tempdir()?;
+            fs::write(tmp.path().join(PROTO_ROOT), &schema.schema)?;
+            for (name, text) in texts {
+                let path = tmp.path().join(search::relative_path(name)?);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                fs::write(path, text)?;
+            }
+
+            let fds =
+                crate::parse_protos(&[tmp.path().join(PROTO_ROOT)], &[tmp.path().to_path_buf()])?;
+            let fd = fds
+                .file
+                .iter()
+                .find(|fd| fd.name() == PROTO_ROOT)
+                .ok_or_else(|| {
+                    anyhow::format_err!("failed to locate file descriptor for schema")
+                })?;
+
+            let (name, indexes) = find_message(fd, message)?;
+            debug!("generating {} (message indexes {:?})", name, indexes);
+
+            let header = message_indexes(&indexes);
+            let mut generator = ProtobufGenerator::new(gen, &fds.file);
+            for _ in 0..count {
+                let mut payload = header.clone();
+                let value = generator.message(&name, 0, &mut payload)?;
+                emit(value, payload)?;
+            }
+        }
+        SchemaType::Other(other) => anyhow::bail!("unsupported schema type: {}", other),
+    }
+
+    Ok(())
+}
+
+pub fn run_sample(client: Client, settings: &SampleSettings) -> anyhow::Result<()> {
+    let sns = crate::subject_name_strategy_from_settings(
+        settings.topic.clone(),
+        settings.record.clone(),
+        settings.topic_key,
+    )?;
+
+    let subject =
+        get_subject(&sns).map_err(|e| anyhow::format_err!("error determining subject: {:?}", e))?;
+
+    let versions = client
+        .get_versions(&subject)
+        .map_err(|e| e.context("error retrieving versions"))?;
+
+    anyhow::ensure!(!versions.is_empty(), "subject not found: {}", subject);
+    let version = settings.version.resolve(&versions)?;
+
+    let schema = client
+        .get_schema(&subject, &version.to_string())
+        .map_err(|e| e.context(format!("error retrieving schema for {}", subject)))?;
+
+    let mut texts = BTreeMap::new();
+    get_references(&client, &schema.references, &mut texts)?;
+
+    let seed = settings.seed.unwrap_or_else(rand::random);
+    info!(
+        "generating {} record(s) for {} version {} with seed {}",
+        settings.count, subject, version, seed
+    );
+
+    let gen = Generator {
+        rng: StdRng::seed_from_u64(seed),
+    };
+
+    // Each record is printed as soon as it's generated.
+    generate(
+        &schema,
+        &texts,
+        settings.message.as_deref(),
+        settings.count,
+        gen,
+        |value, payload| {
+            if settings.wire {
+                outln!("{}", wire_frame(schema.id, &payload));
+            } else {
+                outln!("{}", value);
+            }
+
+            Ok(())
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::convert::TryFrom;
+
+    fn schema(schema_type: &str, schema: &str) -> Schema {
+        serde_json::from_value(json!({
+            "id": 7,
+            "schemaType": schema_type,
+            "schema": schema,
+        }))
+        .unwrap()
+    }
+
+    fn records(
+        schema: &Schema,
+        texts: &BTreeMap<String, String>,
+        message: Option<&str>,
+        count: usize,
+        seed: u64,
+    ) -> Vec<(Value, Vec<u8>)> {
+        let gen = Generator {
+            rng: StdRng::seed_from_u64(seed),
+        };
+
+        let mut records = Vec::new();
+        generate(schema, texts, message, count, gen, |value, payload| {
+            records.push((value, payload));
+            Ok(())
+        })
+        .unwrap();
+
+        records
+    }
+
+    const AVRO: &str = r#"{
+        "type": "record",
+        "name": "Order",
+        "namespace": "com.example",
+        "fields": [
+            {"name": "id", "type": {"type": "string", "logicalType": "uuid"}},
+            {"name": "quantity", "type": "int"},
+            {"name": "total", "type": "long"},
+            {"name": "status", "type": {"type": "enum", "name": "Status", "symbols": ["NEW", "PAID"]}},
+            {"name": "tags", "type": {"type": "array", "items": "string"}},
+            {"name": "attributes", "type": {"type": "map", "values": "long"}},
+            {"name": "line", "type": ["null", {"type": "record", "name": "Line", "fields": [{"name": "sku", "type": "string"}]}]}
+        ]
+    }"#;
+
+    const JSON: &str = r##"{
+        "type": "object",
+        "required": ["id", "balance", "kind"],
+        "properties": {
+            "id": {"type": "string", "format": "uuid"},
+            "balance": {"type": "integer", "maximum": -1},
+            "limit": {"type": "integer", "exclusiveMinimum": 10, "maximum": 20, "multipleOf": 5},
+            "rate": {"type": "number", "exclusiveMaximum": 0},
+            "kind": {"enum": ["a", "b"]},
+            "code": {"type": "string", "minLength": 2, "maxLength": 4},
+            "items": {"type": "array", "items": {"$ref": "#/$defs/Item"}, "minItems": 1, "maxItems": 2}
+        },
+        "$defs": {
+            "Item": {"type": "object", "required": ["sku"], "properties": {"sku": {"const": "x"}}}
+        }
+    }"##;
+
+    #[test]
+    fn encodes_varints_and_zigzag() {
+        let varint = |value| {
+            let mut out = Vec::new();
+            write_varint(value, &mut out);
+            out
+        };
+
+        let zigzag = |value| {
+            let mut out = Vec::new();
+            write_zigzag(value, &mut out);
+            out
+        };
+
+        assert_eq!(varint(0), [0x00]);
+        assert_eq!(varint(127), [0x7f]);
+        assert_eq!(varint(300), [0xac, 0x02]);
+        assert_eq!(
+            varint(u64::MAX),
+            [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]
+        );
+        assert_eq!(zigzag(0), [0x00]);
+        assert_eq!(zigzag(-1), [0x01]);
+        assert_eq!(zigzag(1), [0x02]);
+        assert_eq!(zigzag(-64), [0x7f]);
+        assert_eq!(zigzag(64), [0x80, 0x01]);
+        assert_eq!(zigzag(i64::MIN), varint(u64::MAX));
+    }
+
+    #[test]
+    fn frames_records_for_the_wire() {
+        assert_eq!(message_indexes(&[0]), [0x00]);
+        assert_eq!(message_indexes(&[1]), [0x02, 0x02]);
+        assert_eq!(message_indexes(&[1, 0]), [0x04, 0x02, 0x00]);
+        assert_eq!(wire_frame(0x0102_0304, &[0xab]), "0001020304ab");
+    }
+
+    #[test]
+    fn same_seed_generates_same_records() {
+        let texts = BTreeMap::new();
+        for schema in [schema("AVRO", AVRO), schema("JSON", JSON)] {
+            let first = records(&schema, &texts, None, 20, 42);
+            assert_eq!(first, records(&schema, &texts, None, 20, 42));
+            assert_ne!(first, records(&schema, &texts, None, 20, 43));
+        }
+    }
+
+    #[test]
+    fn generates_valid_avro() {
+        let parsed = avro_rs::Schema::parse_str(AVRO).unwrap();
+        for (value, payload) in records(&schema("AVRO", AVRO), &BTreeMap::new(), None, 50, 1) {
+            let mut reader = payload.as_slice();
+            let datum = avro_rs::from_avro_datum(&parsed, &mut reader, None).unwrap();
+            assert!(reader.is_empty(), "trailing bytes in {:?}", payload);
+            assert!(datum.validate(&parsed));
+            assert_eq!(Value::try_from(datum).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn generates_avro_with_referenced_types() {
+        let texts: BTreeMap<_, _> = [(
+            "com.example.Status".to_owned(),
+            r#"{"type": "enum", "name": "Status", "namespace": "com.example", "symbols": ["NEW"]}"#
+                .to_owned(),
+        )]
+        .into();
+
+        let schema = schema(
+            "AVRO",
+            r#"{"type": "record", "name": "Order", "namespace": "com.example",
+                "fields": [{"name": "status", "type": "Status"}]}"#,
+        );
+
+        for (value, payload) in records(&schema, &texts, None, 5, 1) {
+            assert_eq!(value, json!({"status": "NEW"}));
+            assert_eq!(payload, [0x00]);
+        }
+    }
+
+    #[test]
+    fn generates_valid_json() {
+        for (value, payload) in records(&schema("JSON", JSON), &BTreeMap::new(), None, 200, 1) {
+            assert_eq!(serde_json::from_slice::<Value>(&payload).unwrap(), value);
+
+            let balance = value["balance"].as_i64().unwrap();
+            assert!(balance <= -1, "{}", value);
+            assert!(["a", "b"].contains(&value["kind"].as_str().unwrap()));
+            assert_eq!(value["id"].as_str().unwrap().len(), 36);
+
+            if let Some(limit) = value.get("limit") {
+                let limit = limit.as_i64().unwrap();
+                assert!(limit > 10 && limit <= 20 && limit % 5 == 0, "{}", value);
+            }
+
+            if let Some(rate) = value.get("rate") {
+                assert!(rate.as_f64().unwrap() < 0.0, "{}", value);
+            }
+
+            if let Some(code) = value.get("code") {
+                assert!((2..=4).contains(&code.as_str().unwrap().len()), "{}", value);
+            }
+
+            if let Some(items) = value.get("items") {
+                let items = items.as_array().unwrap();
+                assert!((1..=2).contains(&items.len()), "{}", value);
+                assert!(items.iter().all(|item| item == &json!({"sku": "x"})));
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_unsatisfiable_integer_bounds() {
+        let schema = schema("JSON", r#"{"type": "integer", "minimum": 5, "maximum": 4}"#);
+        let gen = Generator {
+            rng: StdRng::seed_from_u64(1),
+        };
+
+        assert!(generate(&schema, &BTreeMap::new(), None, 1, gen, |_, _| Ok(())).is_err());
+    }
+
+    #[test]
+    fn generates_protobuf_with_message_indexes() {
+        let schema = schema(
+            "PROTOBUF",
+            r#"syntax = "proto3";
+            package example;
+            message First { bool flag = 1; }
+            message Second {
+              message Nested { bool flag = 1; }
+            }
+            "#,
+        );
+
+        let texts = BTreeMap::new();
+        for (message, header) in [
+            (None, vec![0x00]),
+            (Some("Second"), vec![0x02, 0x02]),
+            (Some("Second.Nested"), vec![0x04, 0x02, 0x00]),
+        ] {
+            for (value, payload) in records(&schema, &texts, message, 5, 1) {
+                if message == Some("Second") {
+                    assert_eq!(value, json!({}));
+                    assert_eq!(payload, header);
+                    continue;
+                }
+
+                let flag = value["flag"].as_bool().unwrap();
+                let mut expected = header.clone();
+                expected.extend([0x08, u8::from(flag)]);
+                assert_eq!(payload, expected);
+            }
+        }
+
+        let gen = Generator {
+            rng: StdRng::seed_from_u64(1),
+        };
+
+        assert!(generate(&schema, &texts, Some("Missing"), 1, gen, |_, _| Ok(())).is_err());
+    }
+
+    #[test]
+    fn generates_protobuf_with_imports() {
+        let texts: BTreeMap<_, _> = [(
+            "common/money.proto".to_owned(),
+            "syntax = \"proto3\"; package common; message Money { bool cents = 1; }".to_owned(),
+        )]
+        .into();
+
+        let schema = schema(
+            "PROTOBUF",
+            r#"syntax = "proto3";
+            import "common/money.proto";
+            message Order { common.Money total = 1; }
+            "#,
+        );
+
+        for (value, payload) in records(&schema, &texts, None, 10, 1) {
+            match value.get("total") {
+                Some(total) => {
+                    let cents = u8::from(total["cents"].as_bool().unwrap());
+                    assert_eq!(payload, [0x00, 0x0a, 0x02, 0x08, cents]);
+                }
+                None => assert_eq!(payload, [0x00]),
+            }
+        }
+    }
+}
@@ -0,0 +1,495 @@
+use crate::{
+    avro,
+    registry::{
+        Client,
+        Schema,
+    },
+};
+
+use gumdrop::Options;
+use log::*;
+use prost_types::{
+    DescriptorProto,
+    EnumDescriptorProto,
+};
+
+use schema_registry_converter::schema_registry_common::{
+    RegisteredReference,
+    SchemaType,
+};
+
+use serde_json::Value;
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{
+        Component,
+        Path,
+    },
+};
+
+/// Name of the file the searched protobuf schema is written to (its references go by their own names).
+const PROTO_ROOT: &str = "ksrt-search-root.proto";
+
+/// Search the latest schemas of all subjects for the definitions and uses of a message (record) type,
+/// or for fields with a given name. Matches are printed one per line as subject, version, and match,
+/// separated by tabs.
+#[derive(Debug, Options)]
+pub struct SearchSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// fully-qualified name of a message, enum, or record type to look for
+    #[options(no_short, meta = "NAME")]
+    contains_message: Option<String>,
+
+    /// name of a field to look for
+    #[options(no_short, meta = "NAME")]
+    contains_field: Option<String>,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    pub schema_registry_url: Vec<String>,
+}
+
+struct Query<'a> {
+    message: Option<&'a str>,
+    field: Option<&'a str>,
+}
+
+impl Query<'_> {
+    fn is_message(&self, name: &str) -> bool {
+        self.message == Some(name)
+    }
+
+    fn is_field(&self, name: &str) -> bool {
+        self.field == Some(name)
+    }
+}
+
+/// Checks that the name of a schema (e.g., of a reference, as given by the registry) is a relative path
+/// that stays within the directory it's written to.
+pub(crate) fn relative_path(name: &str) -> anyhow::Result<&Path> {
+    let path = Path::new(name);
+    anyhow::ensure!(
+        !name.is_empty()
+            && path
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir)),
+        "refusing to write {}: not a relative path within the target directory",
+        name
+    );
+
+    Ok(path)
+}
+
+/// Writes the given references, and theirs in turn, to the directory under their names.
+pub(crate) fn write_references(
+    client: &Client,
+    references: &[RegisteredReference],
+    dir: &Path,
+    written: &mut HashSet<String>,
+) -> anyhow::Result<()> {
+    for reference in references {
+        if !written.insert(reference.name.clone()) {
+            continue;
+        }
+
+        let path = dir.join(relative_path(&reference.name)?);
+        let schema = client
+            .get_schema(&reference.subject, &reference.version.to_string())
+            .map_err(|e| {
+                anyhow::format_err!(
+                    "error retrieving reference {}/{}: {}",
+                    reference.subject,
+                    reference.version,
+                    e
+                )
+            })?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&path, &schema.schema)?;
+        write_references(client, &schema.references, dir, written)?;
+    }
+
+    Ok(())
+}
+
+fn search_protobuf_enum(
+    enum_type: &EnumDescriptorProto,
+    scope: &str,
+    query: &Query,
+    matches: &mut Vec<String>,
+) {
+    let name = format!("{}.{}", scope, enum_type.name());
+    if query.is_message(&name[1..]) {
+        matches.push(format!("defines {}", &name[1..]));
+    }
+}
+
+fn search_protobuf_message(
+    message: &DescriptorProto,
+    scope: &str,
+    query: &Query,
+    matches: &mut Vec<String>,
+) {
+    let name = format!("{}.{}", scope, message.name());
+    if query.is_message(&name[1..]) {
+        matches.push(format!("defines {}", &name[1..]));
+    }
+
+    for field in &message.field {
+        if query.is_field(field.name()) {
+            matches.push(format!("field {}.{}", &name[1..], field.name()));
+        }
+
+        if let Some(type_name) = field.type_name.as_deref() {
+            if query.is_message(type_name.trim_start_matches('.')) {
+                matches.push(format!(
+                    "references {} in {}.{}",
+                    &type_name[1..],
+                    &name[1..],
+                    field.name()
+                ));
+            }
+        }
+    }
+
+    for nested in &message.nested_type {
+        search_protobuf_message(nested, &name, query, matches);
+    }
+
+    for enum_type in &message.enum_type {
+        search_protobuf_enum(enum_type, &name, query, matches);
+    }
+}
+
+/// Searches the types declared in a protobuf schema, which is compiled along with its references.
+fn search_protobuf(
+    client: &Client,
+    schema: &Schema,
+    query: &Query,
+    matches: &mut Vec<String>,
+) -> anyhow::Result<()> {
+    let tmp = tempfile::Builder::new().prefix("ksrt-search").tempdir()?;
+    fs::write(tmp.path().join(PROTO_ROOT), &schema.schema)?;
+    write_references(client, &schema.references, tmp.path(), &mut HashSet::new())?;
+
+    let fds = crate::parse_protos(&[tmp.path().join(PROTO_ROOT)], &[tmp.path().to_path_buf()])?;
+    let fd = fds
+        .file
+        .iter()
+        .find(|fd| fd.name() == PROTO_ROOT)
+        .ok_or_else(|| anyhow::format_err!("failed to locate file descriptor for schema"))?;
+
+    let scope = match fd.package() {
+        "" => String::new(),
+        package => format!(".{}", package),
+    };
+
+    for message in &fd.message_type {
+        search_protobuf_message(message, &scope, query, matches);
+    }
+
+    for enum_type in &fd.enum_type {
+        search_protobuf_enum(enum_type, &scope, query, matches);
+    }
+
+    Ok(())
+}
+
+fn search_avro(
+    schema: &Value,
+    namespace: &str,
+    location: &str,
+    query: &Query,
+    matches: &mut Vec<String>,
+) {
+    match schema {
+        Value::String(name) if !avro::PRIMITIVES.contains(&name.as_str()) => {
+            let name = avro::full_name(name, namespace);
+            if query.is_message(&name) {
+                matches.push(format!("references {} in {}", name, location));
+            }
+        }
+        Value::Array(types) => {
+            for schema in types {
+                search_avro(schema, namespace, location, query, matches);
+            }
+        }
+        Value::Object(schema) => match schema.get("type").and_then(Value::as_str) {
+            Some(kind @ ("record" | "error" | "enum" | "fixed")) => {
+                let name = avro::defined_name(schema, namespace);
+                if query.is_message(&name) {
+                    matches.push(format!("defines {}", name));
+                }
+
+                if kind == "enum" || kind == "fixed" {
+                    return;
+                }
+
+                let namespace = avro::namespace_of(&name);
+                let fields = schema.get("fields").and_then(Value::as_array);
+                for field in fields.into_iter().flatten() {
+                    let field_name = field
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default();
+                    let location = format!("{}.{}", name, field_name);
+                    if query.is_field(field_name) {
+                        matches.push(format!("field {}", location));
+                    }
+
+                    if let Some(schema) = field.get("type") {
+                        search_avro(schema, namespace, &location, query, matches);
+                    }
+                }
+            }
+            Some("array") => {
+                if let Some(items) = schema.get("items") {
+                    search_avro(items, namespace, location, query, matches);
+                }
+            }
+            Some("map") => {
+                if let Some(values) = schema.get("values") {
+                    search_avro(values, namespace, location, query, matches);
+                }
+            }
+            _ => {
+                if let Some(schema) = schema.get("type") {
+                    search_avro(schema, namespace, location, query, matches);
+                }
+            }
+        },
+        _ => {}
+    }
+}
+
+/// Extracts the name a JSON Schema `$ref' points to (e.g., `Money' in `#/$defs/Money', or `money' in `money.json#').
+fn json_ref_name(reference: &str) -> &str {
+    let name = reference.trim_end_matches('#');
+    let name = name.rsplit(&['/', '#'][..]).next().unwrap_or(name);
+    name.strip_suffix(".json").unwrap_or(name)
+}
+
+fn search_json(schema: &Value, pointer: &str, query: &Query, matches: &mut Vec<String>) {
+    let schema = match schema {
+        Value::Object(schema) => schema,
+        Value::Array(schemas) => {
+            for (i, schema) in schemas.iter().enumerate() {
+                search_json(schema, &format!("{}/{}", pointer, i), query, matches);
+            }
+
+            return;
+        }
+        _ => return,
+    };
+
+    let location = if pointer.is_empty() { "/" } else { pointer };
+    if let Some(title) = schema.get("title").and_then(Value::as_str) {
+        if query.is_message(title) {
+            matches.push(format!("defines {} at {}", title, location));
+        }
+    }
+
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        // File names are commonly in lower case, so the name referred to is matched regardless of case.
+        let name = json_ref_name(reference);
+        if query.is_message(reference)
+            || query
+                .message
+                .is_some_and(|message| message.eq_ignore_ascii_case(name))
+        {
+            matches.push(format!("references {} at {}", reference, location));
+        }
+    }
+
+    for (key, value) in schema {
+        let pointer = format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"));
+        match key.as_str() {
+            "properties" | "patternProperties" | "$defs" | "definitions" | "dependentSchemas" => {
+                for (name, schema) in value.as_object().into_iter().flatten() {
+                    let pointer =
+                        format!("{}/{}", pointer, name.replace('~', "~0").replace('/', "~1"));
+
+                    if key == "properties" && query.is_field(name) {
+                        matches.push(format!("field {}", pointer));
+                    }
+
+                    if (key == "$defs" || key == "definitions") && query.is_message(name) {
+                        matches.push(format!("defines {} at {}", name, pointer));
+                    }
+
+                    search_json(schema, &pointer, query, matches);
+                }
+            }
+            // Instance data, not schemas.
+            "default" | "const" | "enum" | "examples" => {}
+            _ => search_json(value, &pointer, query, matches),
+        }
+    }
+}
+
+pub fn run_search(client: Client, settings: &SearchSettings) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        settings.contains_message.is_some() || settings.contains_field.is_some(),
+        "either `--contains-message' or `--contains-field' is required"
+    );
+
+    let query = Query {
+        message: settings
+            .contains_message
+            .as_deref()
+            .map(|name| name.trim_start_matches('.')),
+        field: settings.contains_field.as_deref(),
+    };
+
+    let mut subjects = client.list_subjects()?;
+    subjects.sort();
+
+    for subject in subjects {
+        let schema = client.get_schema(&subject, "latest")?;
+        let mut matches = Vec::new();
+        match schema.schema_type() {
+            SchemaType::Avro => match serde_json::from_str(&schema.schema) {
+                Ok(value) => search_avro(&value, "", &subject, &query, &mut matches),
+                Err(e) => warn!("skipping {}: invalid Avro schema: {}", subject, e),
+            },
+            SchemaType::Json => match serde_json::from_str(&schema.schema) {
+                Ok(value) => search_json(&value, "", &query, &mut matches),
+                Err(e) => warn!("skipping {}: invalid JSON schema: {}", subject, e),
+            },
+            SchemaType::Protobuf => {
+                if let Err(e) = search_protobuf(&client, &schema, &query, &mut matches) {
+                    warn!("skipping {}: {}", subject, e);
+                }
+            }
+            SchemaType::Other(other) => {
+                debug!("skipping {}: unsupported schema type {}", subject, other)
+            }
+        }
+
+        let version = schema
+            .version
+            .map_or_else(|| "latest".to_owned(), |v| v.to_string());
+
+        for m in matches {
+            outln!("{}\t{}\t{}", subject, version, m);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(name: &str) -> Query<'_> {
+        Query {
+            message: Some(name),
+            field: None,
+        }
+    }
+
+    #[test]
+    fn extracts_names_from_json_refs() {
+        assert_eq!(json_ref_name("#/$defs/Money"), "Money");
+        assert_eq!(json_ref_name("#/definitions/Money"), "Money");
+        assert_eq!(json_ref_name("money.json#"), "money");
+        assert_eq!(json_ref_name("common/money.json"), "money");
+        assert_eq!(
+            json_ref_name("https://example.com/schemas/money.json#"),
+            "money"
+        );
+    }
+
+    #[test]
+    fn finds_json_refs_by_name() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "total": {"$ref": "#/$defs/Money"},
+                "tax": {"$ref": "money.json#"},
+                "id": {"$ref": "#/$defs/Id"}
+            },
+            "$defs": {"Money": {"type": "number"}}
+        });
+
+        let mut matches = Vec::new();
+        search_json(&schema, "", &message("Money"), &mut matches);
+        assert_eq!(
+            matches,
+            [
+                "references #/$defs/Money at /properties/total",
+                "references money.json# at /properties/tax",
+                "defines Money at /$defs/Money"
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_instance_data_in_json() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "currency": {
+                    "enum": [{"$ref": "#/$defs/Money"}, "Money"],
+                    "const": {"title": "Money"},
+                    "default": {"$ref": "money.json"},
+                    "examples": [{"properties": {"email": {}}}]
+                }
+            }
+        });
+
+        for query in [
+            message("Money"),
+            Query {
+                message: None,
+                field: Some("email"),
+            },
+        ] {
+            let mut matches = Vec::new();
+            search_json(&schema, "", &query, &mut matches);
+            assert!(matches.is_empty(), "{:?}", matches);
+        }
+    }
+
+    #[test]
+    fn finds_avro_references_in_nested_types() {
+        let schema = json!({
+            "type": "record",
+            "name": "Order",
+            "namespace": "com.acme",
+            "fields": [
+                {"name": "total", "type": ["null", "Money"]},
+                {"name": "taxes", "type": {"type": "array", "items": "com.acme.Money"}},
+                {"name": "fees", "type": {"type": "map", "values": ["null", {"type": "array", "items": "Money"}]}},
+                {"name": "note", "type": {"type": "string"}},
+                {"name": "other", "type": "other.Money"}
+            ]
+        });
+
+        let mut matches = Vec::new();
+        search_avro(
+            &schema,
+            "",
+            "orders-value",
+            &message("com.acme.Money"),
+            &mut matches,
+        );
+        assert_eq!(
+            matches,
+            [
+                "references com.acme.Money in com.acme.Order.total",
+                "references com.acme.Money in com.acme.Order.taxes",
+                "references com.acme.Money in com.acme.Order.fees"
+            ]
+        );
+    }
+}
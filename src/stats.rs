@@ -0,0 +1,172 @@
+use crate::registry::{
+    self,
+    Client,
+};
+
+use gumdrop::Options;
+use log::*;
+use serde::Serialize;
+
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+    HashMap,
+};
+
+/// Summarize the contents of the Kafka Schema Registry: the number of subjects, versions, and (distinct) schemas
+/// by type, the subjects whose latest schemas have references, the subjects referenced by older versions only
+/// (orphaned reference subjects), and the number of subjects at each compatibility level in effect.
+#[derive(Debug, Options)]
+pub struct StatsSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// print as JSON
+    #[options(no_short)]
+    json: bool,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    pub schema_registry_url: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Stats {
+    subjects: usize,
+    versions: usize,
+    schemas: usize,
+    schemas_by_type: BTreeMap<String, usize>,
+    subjects_with_references: usize,
+    orphaned_reference_subjects: BTreeSet<String>,
+    /// Number of subjects by the compatibility level in effect (their own, or else the global one).
+    compatibility_levels: BTreeMap<String, usize>,
+    /// Number of subjects with a compatibility level of their own.
+    compatibility_overrides: usize,
+}
+
+fn collect(client: &Client) -> anyhow::Result<Stats> {
+    let mut stats = Stats::default();
+    let global = client
+        .get_global_compatibility()
+        .map_err(|e| e.context("error retrieving global compatibility level"))?;
+
+    let subjects = client
+        .list_subjects()
+        .map_err(|e| e.context("error listing subjects"))?;
+
+    // Schema types by id, since the same schema may be registered under several subjects or versions.
+    let mut schema_types = HashMap::new();
+    // Subjects referenced by any version, and by latest versions.
+    let mut referenced = BTreeSet::new();
+    let mut referenced_by_latest = BTreeSet::new();
+
+    for subject in &subjects {
+        let versions = client
+            .get_versions(subject)
+            .map_err(|e| e.context(format!("error retrieving versions of {}", subject)))?;
+
+        stats.versions += versions.len();
+        for version in &versions {
+            let schema = client
+                .get_schema(subject, &version.to_string())
+                .map_err(|e| {
+                    e.context(format!("error retrieving {} version {}", subject, version))
+                })?;
+
+            let schema_type = schema.schema_type();
+            schema_types.insert(
+                schema.id,
+                registry::schema_type_name(&schema_type).to_owned(),
+            );
+
+            let latest = Some(version) == versions.last();
+            if latest && !schema.references.is_empty() {
+                stats.subjects_with_references += 1;
+            }
+
+            for reference in &schema.references {
+                referenced.insert(reference.subject.clone());
+                if latest {
+                    referenced_by_latest.insert(reference.subject.clone());
+                }
+            }
+        }
+
+        let compatibility = client.get_compatibility(subject).map_err(|e| {
+            e.context(format!(
+                "error retrieving compatibility level of {}",
+                subject
+            ))
+        })?;
+
+        if compatibility.is_some() {
+            stats.compatibility_overrides += 1;
+        }
+
+        let level = compatibility.unwrap_or(global).to_string();
+        *stats.compatibility_levels.entry(level).or_default() += 1;
+    }
+
+    stats.subjects = subjects.len();
+    stats.schemas = schema_types.len();
+    for schema_type in schema_types.into_values() {
+        *stats.schemas_by_type.entry(schema_type).or_default() += 1;
+    }
+
+    stats.orphaned_reference_subjects = referenced
+        .difference(&referenced_by_latest)
+        .filter(|subject| subjects.contains(subject))
+        .cloned()
+        .collect();
+
+    Ok(stats)
+}
+
+fn counts(counts: &BTreeMap<String, usize>) -> String {
+    counts
+        .iter()
+        .map(|(key, count)| format!("{} {}", key, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn run_stats(client: Client, settings: &StatsSettings) -> anyhow::Result<()> {
+    let stats = collect(&client)?;
+    debug!("{:?}", stats);
+
+    if settings.json {
+        outln!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    outln!("subjects: {}", stats.subjects);
+    outln!("versions: {}", stats.versions);
+    outln!("schemas: {}", stats.schemas);
+    if !stats.schemas_by_type.is_empty() {
+        outln!("schemas by type: {}", counts(&stats.schemas_by_type));
+    }
+
+    outln!(
+        "subjects with references: {}",
+        stats.subjects_with_references
+    );
+    outln!(
+        "orphaned reference subjects: {}",
+        stats.orphaned_reference_subjects.len()
+    );
+
+    for subject in &stats.orphaned_reference_subjects {
+        outln!("\t{}", subject);
+    }
+
+    if !stats.compatibility_levels.is_empty() {
+        outln!(
+            "compatibility levels: {} ({} set per subject)",
+            counts(&stats.compatibility_levels),
+            stats.compatibility_overrides
+        );
+    }
+
+    Ok(())
+}
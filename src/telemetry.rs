@@ -0,0 +1,126 @@
+use crate::registry;
+
+use opentelemetry::{
+    global,
+    sdk::{
+        trace,
+        Resource,
+    },
+    KeyValue,
+};
+
+use opentelemetry_otlp::WithExportConfig;
+
+use std::{
+    env,
+    fmt,
+    io,
+    str::FromStr,
+};
+
+use tracing_subscriber::{
+    fmt::layer as fmt_layer,
+    layer::SubscriberExt,
+    registry as tracing_registry,
+    util::SubscriberInitExt,
+    EnvFilter as TracingEnvFilter,
+};
+
+/// Full URL of the OTLP/HTTP traces endpoint.
+const TRACES_ENDPOINT_VAR: &str = "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT";
+
+/// Base URL of the OTLP/HTTP endpoint (traces are sent to `/v1/traces' under it).
+const ENDPOINT_VAR: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// Format of the log output.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let format = match s {
+            "text" => Self::Text,
+            "json" => Self::Json,
+            _ => anyhow::bail!("unsupported log format: {}", s),
+        };
+
+        Ok(format)
+    }
+}
+
+fn otlp_endpoint() -> Option<String> {
+    match env::var(TRACES_ENDPOINT_VAR) {
+        Ok(endpoint) => Some(endpoint),
+        Err(_) => env::var(ENDPOINT_VAR)
+            .ok()
+            .map(|base| format!("{}/v1/traces", base.trim_end_matches('/'))),
+    }
+}
+
+/// Sets up logging to standard error (keeping standard output for command output only, e.g., `get --raw > schema.proto'),
+/// and exporting spans of registry requests over OTLP/HTTP if an endpoint is configured in the environment.
+/// When quiet, only errors are logged.
+pub fn init(format: LogFormat, quiet: bool) -> anyhow::Result<()> {
+    let mut filter = if quiet {
+        TracingEnvFilter::new("error")
+    } else {
+        TracingEnvFilter::from_default_env()
+    };
+
+    let tracer = match otlp_endpoint() {
+        Some(endpoint) => {
+            let tracer = opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .http()
+                        .with_env()
+                        .with_endpoint(&endpoint),
+                )
+                .with_trace_config(
+                    trace::config()
+                        .with_resource(Resource::new(vec![KeyValue::new("service.name", "ksrt")])),
+                )
+                .install_simple()
+                .map_err(|e| anyhow::format_err!("error setting up OTLP exporter: {}", e))?;
+
+            filter = filter.add_directive(format!("{}=info", registry::SPAN_TARGET).parse()?);
+            Some(tracer)
+        }
+        None => None,
+    };
+
+    let (text, json) = match format {
+        LogFormat::Text => (Some(fmt_layer().with_writer(io::stderr)), None),
+        LogFormat::Json => (None, Some(fmt_layer().json().with_writer(io::stderr))),
+    };
+
+    tracing_registry()
+        .with(filter)
+        .with(text)
+        .with(json)
+        .with(tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer)))
+        .init();
+
+    Ok(())
+}
+
+/// Exports any spans not yet sent; call before exiting.
+pub fn shutdown() {
+    global::shutdown_tracer_provider();
+}
@@ -0,0 +1,112 @@
+use log::*;
+
+use std::{
+    sync::{
+        atomic::{
+            AtomicU64,
+            Ordering,
+        },
+        Mutex,
+    },
+    thread,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+/// Number of requests delayed, and for how long in total.
+struct Delays {
+    count: AtomicU64,
+    millis: AtomicU64,
+}
+
+impl Delays {
+    const fn new() -> Self {
+        Self {
+            count: AtomicU64::new(0),
+            millis: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, delay: Duration) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.millis
+            .fetch_add(delay.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn summary(&self) -> Option<(u64, f64)> {
+        match self.count.load(Ordering::Relaxed) {
+            0 => None,
+            count => Some((count, self.millis.load(Ordering::Relaxed) as f64 / 1000.0)),
+        }
+    }
+}
+
+/// Requests delayed to stay within the rate limit.
+static LIMITED: Delays = Delays::new();
+
+/// Requests retried after the registry rate limited them.
+static THROTTLED: Delays = Delays::new();
+
+/// Spaces out requests evenly so that no more than the given number are sent per second.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    /// when the next request may be sent
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / per_second.max(1),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Blocks until the next request may be sent.
+    pub fn acquire(&self) {
+        let now = Instant::now();
+        let start = {
+            let mut next = self.next.lock().expect("rate limiter");
+            let start = (*next).max(now);
+            *next = start + self.interval;
+            start
+        };
+
+        let delay = start - now;
+        if !delay.is_zero() {
+            trace!("rate limit: waiting {:?}", delay);
+            thread::sleep(delay);
+            LIMITED.record(delay);
+        }
+    }
+}
+
+/// Records the delay before retrying a request the registry rate limited.
+pub fn record_throttled(delay: Duration) {
+    THROTTLED.record(delay);
+}
+
+/// Prints how long requests were delayed by the rate limit and by the registry's rate limiting, if at all,
+/// to standard error (so as not to mix with command output), unless suppressed with `--quiet'.
+pub fn print_summary() {
+    if crate::QUIET.load(Ordering::Relaxed) {
+        return;
+    }
+
+    if let Some((count, secs)) = LIMITED.summary() {
+        eprintln!(
+            "rate limit delayed {} request(s) by {:.1}s in total",
+            count, secs
+        );
+    }
+
+    if let Some((count, secs)) = THROTTLED.summary() {
+        eprintln!(
+            "registry rate limited {} request(s), retried after {:.1}s in total",
+            count, secs
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use log::*;
+use schema_registry_converter::schema_registry_common::{
+    SchemaType,
+    SuppliedReference,
+    SuppliedSchema,
+};
+
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    process::{
+        Command,
+        Stdio,
+    },
+};
+
+/// Name of the file a transformed protobuf schema is written to for verification.
+const PROTO_ROOT: &str = "ksrt-transform-root.proto";
+
+/// Pipes the text of a schema through a shell command, which gets the schema's name and type
+/// in `KSRT_SCHEMA_NAME' and `KSRT_SCHEMA_TYPE', and returns what it prints.
+fn run(command: &str, name: &str, schema_type: &SchemaType, text: &str) -> anyhow::Result<String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("KSRT_SCHEMA_NAME", name)
+        .env(
+            "KSRT_SCHEMA_TYPE",
+            crate::registry::schema_type_name(schema_type).to_lowercase(),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::format_err!("error running transform `{}': {}", command, e))?;
+
+    // Writing on another thread keeps a command that prints as it reads from blocking on a full pipe.
+    let mut stdin = child.stdin.take().expect("stdin");
+    let input = text.to_owned();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let output = child.wait_with_output()?;
+    let written = writer.join().expect("transform input");
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut message = format!(
+            "transform `{}' failed for {} ({})",
+            command, name, output.status
+        );
+        if !stderr.trim().is_empty() {
+            message.push_str(": ");
+            message.push_str(stderr.trim());
+        }
+
+        anyhow::bail!(message);
+    }
+
+    written.map_err(|e| anyhow::format_err!("error piping {} to `{}': {}", name, command, e))?;
+    String::from_utf8(output.stdout).map_err(|_| {
+        anyhow::format_err!("transform `{}' printed invalid UTF-8 for {}", command, name)
+    })
+}
+
+fn transform_text(
+    commands: &[String],
+    name: &str,
+    schema_type: &SchemaType,
+    text: &str,
+) -> anyhow::Result<String> {
+    commands.iter().try_fold(text.to_owned(), |text, command| {
+        debug!("transforming {} with `{}'", name, command);
+        run(command, name, schema_type, &text)
+    })
+}
+
+fn transform_references(
+    commands: &[String],
+    schema_type: &SchemaType,
+    references: &mut [SuppliedReference],
+) -> anyhow::Result<()> {
+    for reference in references {
+        reference.schema =
+            transform_text(commands, &reference.name, schema_type, &reference.schema)?;
+        transform_references(commands, schema_type, &mut reference.references)?;
+    }
+
+    Ok(())
+}
+
+fn write_references(dir: &Path, references: &[SuppliedReference]) -> anyhow::Result<()> {
+    for reference in references {
+        let path = dir.join(&reference.name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, &reference.schema)?;
+        write_references(dir, &reference.references)?;
+    }
+
+    Ok(())
+}
+
+fn verify_json(name: &str, text: &str, kind: &str) -> anyhow::Result<()> {
+    serde_json::from_str::<serde_json::Value>(text)
+        .map(|_| ())
+        .map_err(|e| {
+            anyhow::format_err!("transformed {} is not a valid {} schema: {}", name, kind, e)
+        })
+}
+
+fn verify_json_references(references: &[SuppliedReference], kind: &str) -> anyhow::Result<()> {
+    for reference in references {
+        verify_json(&reference.name, &reference.schema, kind)?;
+        verify_json_references(&reference.references, kind)?;
+    }
+
+    Ok(())
+}
+
+/// Checks that the transformed schema (and its references) still parses: Avro and JSON schemas as JSON,
+/// and protobuf schemas by compiling them together.
+fn verify(name: &str, schema: &SuppliedSchema) -> anyhow::Result<()> {
+    match &schema.schema_type {
+        SchemaType::Avro => {
+            verify_json(name, &schema.schema, "Avro")?;
+            verify_json_references(&schema.references, "Avro")
+        }
+        SchemaType::Json => {
+            verify_json(name, &schema.schema, "JSON")?;
+            verify_json_references(&schema.references, "JSON")
+        }
+        SchemaType::Protobuf => {
+            let tmp = tempfile::Builder::new()
+                .prefix("ksrt-transform")
+                .tempdir()?;
+            fs::write(tmp.path().join(PROTO_ROOT), &schema.schema)?;
+            write_references(tmp.path(), &schema.references)?;
+
+            crate::parse_protos(&[tmp.path().join(PROTO_ROOT)], &[tmp.path().to_path_buf()])
+                .map(|_| ())
+                .map_err(|e| anyhow::format_err!("transformed {} does not compile: {}", name, e))
+        }
+        SchemaType::Other(other) => anyhow::bail!("unsupported schema type: {}", other),
+    }
+}
+
+/// Pipes the text of a schema and each of its references through the given commands, in order,
+/// and verifies that the result still parses.
+pub fn transform(
+    commands: &[String],
+    name: &str,
+    mut schema: SuppliedSchema,
+) -> anyhow::Result<SuppliedSchema> {
+    if commands.is_empty() {
+        return Ok(schema);
+    }
+
+    schema.schema = transform_text(commands, name, &schema.schema_type, &schema.schema)?;
+    transform_references(commands, &schema.schema_type, &mut schema.references)?;
+    verify(name, &schema)?;
+
+    Ok(schema)
+}
@@ -0,0 +1,170 @@
+use crate::{
+    diff,
+    registry::Client,
+    DurationOpt,
+};
+
+use gumdrop::Options;
+use log::*;
+
+use std::{
+    collections::BTreeMap,
+    process::Command,
+    thread,
+};
+
+/// Watch subjects for new versions, polling the Kafka Schema Registry at an interval.
+/// Whenever a subject gets a new version, the differences from the version before are printed,
+/// and the hook command, if any, is run (with KSRT_SUBJECT, KSRT_VERSION, and KSRT_PREVIOUS_VERSION,
+/// unless the subject is new, set in its environment). Failing requests and hooks are logged, but don't stop the watch.
+#[derive(Debug, Options)]
+pub struct WatchSettings {
+    /// print usage and exit
+    help: bool,
+
+    /// subject to watch (could be multiple)
+    #[options(meta = "NAME")]
+    subject: Vec<String>,
+
+    /// watch all subjects, including those created while watching
+    all: bool,
+
+    /// how often to poll (e.g., `500ms', `30s', `5m', or `1h')
+    #[options(meta = "DURATION", default = "30s")]
+    interval: DurationOpt,
+
+    /// shell command to run whenever a subject changes (e.g., to trigger code generation)
+    #[options(no_short, meta = "CMD")]
+    exec: Option<String>,
+
+    /// Schema Registry URL(s), or the name of a configured profile (required)
+    #[options(free, required)]
+    pub schema_registry_url: Vec<String>,
+}
+
+struct Watcher<'a> {
+    client: Client,
+    settings: &'a WatchSettings,
+    /// Latest known version of each watched subject (none if it has no versions).
+    latest: BTreeMap<String, Option<u32>>,
+}
+
+impl Watcher<'_> {
+    fn subjects(&self) -> anyhow::Result<Vec<String>> {
+        if self.settings.all {
+            self.client.list_subjects()
+        } else {
+            Ok(self.settings.subject.clone())
+        }
+    }
+
+    fn latest_version(&self, subject: &str) -> anyhow::Result<Option<u32>> {
+        Ok(self.client.get_versions(subject)?.last().copied())
+    }
+
+    /// Records the latest versions of the subjects as they are now.
+    fn init(&mut self) -> anyhow::Result<()> {
+        for subject in self.subjects()? {
+            let latest = self.latest_version(&subject)?;
+            self.latest.insert(subject, latest);
+        }
+
+        info!(
+            "watching {} subject(s) every {:?}",
+            self.latest.len(),
+            self.settings.interval.0
+        );
+
+        Ok(())
+    }
+
+    /// Checks the subjects for new versions, reporting each change.
+    fn poll(&mut self) -> anyhow::Result<()> {
+        for subject in self.subjects()? {
+            let previous = self.latest.get(&subject).copied().flatten();
+            let latest = self.latest_version(&subject)?;
+            match (previous, latest) {
+                (Some(previous), Some(latest)) if latest > previous => {
+                    self.report(&subject, Some(previous), latest)?
+                }
+                (None, Some(latest)) => self.report(&subject, None, latest)?,
+                (Some(previous), latest) if latest < Some(previous) => {
+                    info!("latest version of {} was deleted", subject);
+                }
+                _ => {}
+            }
+
+            // Only once reported, so that a change is reported again should that fail.
+            self.latest.insert(subject, latest);
+        }
+
+        Ok(())
+    }
+
+    fn report(&self, subject: &str, previous: Option<u32>, latest: u32) -> anyhow::Result<()> {
+        let schema = self.client.get_schema(subject, &latest.to_string())?;
+        match previous {
+            Some(previous) => {
+                let from = self.client.get_schema(subject, &previous.to_string())?;
+                diff::print_diff(subject, previous, &from, latest, &schema);
+            }
+            None => {
+                outln!(
+                    "new subject {} version {} (id {}):",
+                    subject,
+                    latest,
+                    schema.id
+                );
+                out!("{}", schema.schema);
+                if !schema.schema.ends_with('\n') {
+                    outln!();
+                }
+            }
+        }
+
+        if let Some(exec) = &self.settings.exec {
+            self.run_hook(exec, subject, previous, latest);
+        }
+
+        Ok(())
+    }
+
+    fn run_hook(&self, exec: &str, subject: &str, previous: Option<u32>, latest: u32) {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(exec)
+            .env("KSRT_SUBJECT", subject)
+            .env("KSRT_VERSION", latest.to_string());
+
+        if let Some(previous) = previous {
+            cmd.env("KSRT_PREVIOUS_VERSION", previous.to_string());
+        }
+
+        match cmd.status() {
+            Ok(status) if status.success() => debug!("hook succeeded for {}", subject),
+            Ok(status) => warn!("hook failed for {} ({})", subject, status),
+            Err(e) => warn!("error running hook for {}: {}", subject, e),
+        }
+    }
+}
+
+pub fn run_watch(client: Client, settings: &WatchSettings) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        settings.all == settings.subject.is_empty(),
+        "either `--subject' or `--all' is required (but not both)"
+    );
+
+    let mut watcher = Watcher {
+        client,
+        settings,
+        latest: BTreeMap::new(),
+    };
+
+    watcher.init()?;
+    loop {
+        thread::sleep(settings.interval.0);
+        if let Err(e) = watcher.poll() {
+            warn!("error polling for changes: {:#}", e);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{
+    manifest::Manifest,
+    registry::Compatibility,
+    SchemaTypeOpt,
+};
+
+use serde::Deserialize;
+
+use std::{
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+/// Collection of schema packages (e.g., the proto packages of a monorepo), each with its own manifest.
+/// Package settings are defaults for the subjects in the package's manifest.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Workspace {
+    #[serde(rename = "package")]
+    pub packages: Vec<Package>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Package {
+    /// Package directory, relative to the workspace file.
+    pub path: PathBuf,
+    /// Manifest file, relative to the package directory.
+    #[serde(default = "default_manifest")]
+    pub manifest: PathBuf,
+    /// Schema type of subjects that don't specify one.
+    #[serde(rename = "type")]
+    pub schema_type: Option<SchemaTypeOpt>,
+    /// Include directories, relative to the workspace file, added to those of every subject.
+    #[serde(default)]
+    pub include: Vec<PathBuf>,
+    /// Strip comments from all schemas in the package.
+    #[serde(default)]
+    pub strip_comments: bool,
+    /// Strip options from all (protobuf) schemas in the package, except those named in `keep-options'.
+    #[serde(default)]
+    pub strip_options: bool,
+    /// Options to keep, added to those of every subject.
+    #[serde(default)]
+    pub keep_options: Vec<String>,
+    /// Compatibility level of subjects that don't specify one.
+    pub compatibility: Option<Compatibility>,
+    /// Prefix of all subject names in the package.
+    #[serde(default)]
+    pub subject_prefix: String,
+}
+
+fn default_manifest() -> PathBuf {
+    PathBuf::from("ksrt.yaml")
+}
+
+impl Workspace {
+    /// Loads the workspace from the given file and combines the manifests of all its packages.
+    pub fn load(path: &Path) -> anyhow::Result<Manifest> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| anyhow::format_err!("failed to read {}: {}", path.display(), e))?;
+
+        let workspace: Self = toml::from_str(&text)
+            .map_err(|e| anyhow::format_err!("invalid workspace {}: {}", path.display(), e))?;
+
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut subjects = Vec::new();
+        for package in workspace.packages {
+            let dir = base.join(&package.path);
+            let mut manifest =
+                Manifest::load_with_type(&dir.join(&package.manifest), package.schema_type)?;
+
+            for entry in &mut manifest.subjects {
+                entry
+                    .include
+                    .extend(package.include.iter().map(|include| base.join(include)));
+
+                entry.strip_comments |= package.strip_comments;
+                entry.strip_options |= package.strip_options;
+                entry
+                    .keep_options
+                    .extend(package.keep_options.iter().cloned());
+                entry.compatibility = entry.compatibility.or(package.compatibility);
+                entry.subject_prefix = package.subject_prefix.clone();
+            }
+
+            subjects.append(&mut manifest.subjects);
+        }
+
+        Ok(Manifest { subjects })
+    }
+}